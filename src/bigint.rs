@@ -0,0 +1,333 @@
+//! Arbitrary precision integers, just what the exact sign computations in [`crate::sturm`] and
+//! the remainder sequence of [`crate::polynomial::Polynomial::gcd`] need
+
+use std::{cmp::Ordering, convert::TryFrom, fmt::Display, ops};
+
+/// sign and magnitude, the magnitude in base `2^32` lowest limb first and without leading zero
+/// limbs, zero is never negative
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct BigInt {
+    negative: bool,
+    magnitude: Vec<u32>,
+}
+
+fn trim(mut m: Vec<u32>) -> Vec<u32> {
+    while m.last() == Some(&0) {
+        m.pop();
+    }
+    m
+}
+
+fn compare(a: &[u32], b: &[u32]) -> Ordering {
+    a.len()
+        .cmp(&b.len())
+        .then_with(|| a.iter().rev().cmp(b.iter().rev()))
+}
+
+fn add(a: &[u32], b: &[u32]) -> Vec<u32> {
+    let mut result = Vec::with_capacity(a.len().max(b.len()) + 1);
+    let mut carry = 0u64;
+    for i in 0..a.len().max(b.len()) {
+        let sum = *a.get(i).unwrap_or(&0) as u64 + *b.get(i).unwrap_or(&0) as u64 + carry;
+        result.push(sum as u32);
+        carry = sum >> 32;
+    }
+    result.push(carry as u32);
+    trim(result)
+}
+
+/// `a - b` for `a >= b`
+fn sub(a: &[u32], b: &[u32]) -> Vec<u32> {
+    let mut result = Vec::with_capacity(a.len());
+    let mut borrow = 0i64;
+    for (i, &x) in a.iter().enumerate() {
+        let mut difference = x as i64 - *b.get(i).unwrap_or(&0) as i64 - borrow;
+        borrow = 0;
+        if difference < 0 {
+            difference += 1 << 32;
+            borrow = 1;
+        }
+        result.push(difference as u32);
+    }
+    trim(result)
+}
+
+fn mul(a: &[u32], b: &[u32]) -> Vec<u32> {
+    let mut result = vec![0u32; a.len() + b.len()];
+    for (i, &x) in a.iter().enumerate() {
+        let mut carry = 0u64;
+        for (j, &y) in b.iter().enumerate() {
+            let product = x as u64 * y as u64 + result[i + j] as u64 + carry;
+            result[i + j] = product as u32;
+            carry = product >> 32;
+        }
+        result[i + b.len()] = carry as u32;
+    }
+    trim(result)
+}
+
+fn shl(a: &[u32], bits: u64) -> Vec<u32> {
+    if a.is_empty() {
+        return Vec::new();
+    }
+    let (limbs, bits) = ((bits / 32) as usize, bits % 32);
+    let mut result = vec![0u32; limbs];
+    let mut carry = 0u32;
+    for &x in a {
+        if bits == 0 {
+            result.push(x);
+        } else {
+            result.push((x << bits) | carry);
+            carry = x >> (32 - bits);
+        }
+    }
+    result.push(carry);
+    trim(result)
+}
+
+fn bits(a: &[u32]) -> u64 {
+    match a.last() {
+        Some(top) => (a.len() as u64 - 1) * 32 + (32 - top.leading_zeros()) as u64,
+        None => 0,
+    }
+}
+
+/// long division one bit at a time
+fn div_rem(a: &[u32], b: &[u32]) -> (Vec<u32>, Vec<u32>) {
+    let mut quotient = vec![0u32; a.len()];
+    let mut remainder = Vec::new();
+    for i in (0..bits(a)).rev() {
+        remainder = shl(&remainder, 1);
+        if (a[(i / 32) as usize] >> (i % 32)) & 1 == 1 {
+            remainder = add(&remainder, &[1]);
+        }
+        if compare(&remainder, b) != Ordering::Less {
+            remainder = sub(&remainder, b);
+            quotient[(i / 32) as usize] |= 1 << (i % 32);
+        }
+    }
+    (trim(quotient), remainder)
+}
+
+impl BigInt {
+    fn new(negative: bool, magnitude: Vec<u32>) -> Self {
+        let magnitude = trim(magnitude);
+        Self {
+            negative: negative && !magnitude.is_empty(),
+            magnitude,
+        }
+    }
+
+    pub fn zero() -> Self {
+        Self::new(false, Vec::new())
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.magnitude.is_empty()
+    }
+
+    pub fn signum(&self) -> i8 {
+        if self.is_zero() {
+            0
+        } else if self.negative {
+            -1
+        } else {
+            1
+        }
+    }
+
+    pub fn abs(&self) -> Self {
+        Self::new(false, self.magnitude.clone())
+    }
+
+    /// `self * 2^bits`
+    pub fn shl(&self, bits: u64) -> Self {
+        Self::new(self.negative, shl(&self.magnitude, bits))
+    }
+
+    /// the number of bits of the magnitude
+    pub fn bits(&self) -> u64 {
+        bits(&self.magnitude)
+    }
+
+    /// the number of trailing zero bits, `0` for zero
+    pub fn trailing_zeros(&self) -> u64 {
+        let mut zeros = 0;
+        for &limb in &self.magnitude {
+            if limb == 0 {
+                zeros += 32;
+            } else {
+                return zeros + limb.trailing_zeros() as u64;
+            }
+        }
+        0
+    }
+
+    /// `self / 2^bits`, rounded towards zero
+    pub fn shr(&self, bits: u64) -> Self {
+        let (limbs, bits) = ((bits / 32) as usize, bits % 32);
+        let magnitude = self.magnitude.get(limbs..).unwrap_or(&[]);
+        let mut result = Vec::with_capacity(magnitude.len());
+        for (i, &x) in magnitude.iter().enumerate() {
+            let high = magnitude
+                .get(i + 1)
+                .map_or(0, |&h| if bits == 0 { 0 } else { h << (32 - bits) });
+            result.push((x >> bits) | high);
+        }
+        Self::new(self.negative, result)
+    }
+
+    /// quotient rounded towards zero and the remainder with the sign of `self`
+    pub fn div_rem(&self, rhs: &Self) -> (Self, Self) {
+        assert!(!rhs.is_zero(), "division by zero");
+        let (quotient, remainder) = div_rem(&self.magnitude, &rhs.magnitude);
+        (
+            Self::new(self.negative != rhs.negative, quotient),
+            Self::new(self.negative, remainder),
+        )
+    }
+
+    pub fn gcd(&self, other: &Self) -> Self {
+        let (mut a, mut b) = (self.abs(), other.abs());
+        while !b.is_zero() {
+            let remainder = a.div_rem(&b).1;
+            a = b;
+            b = remainder;
+        }
+        a
+    }
+
+    pub fn to_f64(&self) -> f64 {
+        let magnitude = self
+            .magnitude
+            .iter()
+            .rev()
+            .fold(0.0, |acc, &limb| acc * 4294967296.0 + limb as f64);
+        if self.negative {
+            -magnitude
+        } else {
+            magnitude
+        }
+    }
+
+    pub fn to_i64(&self) -> Option<i64> {
+        if self.magnitude.len() > 2 {
+            return None;
+        }
+        let magnitude = self
+            .magnitude
+            .iter()
+            .rev()
+            .fold(0u64, |acc, &limb| (acc << 32) | limb as u64);
+        if self.negative {
+            0i64.checked_sub_unsigned(magnitude)
+        } else {
+            i64::try_from(magnitude).ok()
+        }
+    }
+}
+
+impl From<i128> for BigInt {
+    fn from(n: i128) -> Self {
+        let mut magnitude = n.unsigned_abs();
+        let mut limbs = Vec::new();
+        while magnitude > 0 {
+            limbs.push(magnitude as u32);
+            magnitude >>= 32;
+        }
+        Self::new(n < 0, limbs)
+    }
+}
+
+impl From<i64> for BigInt {
+    fn from(n: i64) -> Self {
+        Self::from(n as i128)
+    }
+}
+
+impl Ord for BigInt {
+    fn cmp(&self, other: &Self) -> Ordering {
+        match (self.negative, other.negative) {
+            (false, true) => Ordering::Greater,
+            (true, false) => Ordering::Less,
+            (false, false) => compare(&self.magnitude, &other.magnitude),
+            (true, true) => compare(&other.magnitude, &self.magnitude),
+        }
+    }
+}
+
+impl PartialOrd for BigInt {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl ops::Add for &BigInt {
+    type Output = BigInt;
+
+    fn add(self, rhs: Self) -> BigInt {
+        if self.negative == rhs.negative {
+            return BigInt::new(self.negative, add(&self.magnitude, &rhs.magnitude));
+        }
+        match compare(&self.magnitude, &rhs.magnitude) {
+            Ordering::Less => BigInt::new(rhs.negative, sub(&rhs.magnitude, &self.magnitude)),
+            _ => BigInt::new(self.negative, sub(&self.magnitude, &rhs.magnitude)),
+        }
+    }
+}
+
+impl ops::Sub for &BigInt {
+    type Output = BigInt;
+
+    fn sub(self, rhs: Self) -> BigInt {
+        self + &-rhs
+    }
+}
+
+impl ops::Mul for &BigInt {
+    type Output = BigInt;
+
+    fn mul(self, rhs: Self) -> BigInt {
+        BigInt::new(
+            self.negative != rhs.negative,
+            mul(&self.magnitude, &rhs.magnitude),
+        )
+    }
+}
+
+impl ops::Neg for &BigInt {
+    type Output = BigInt;
+
+    fn neg(self) -> BigInt {
+        BigInt::new(!self.negative, self.magnitude.clone())
+    }
+}
+
+impl Display for BigInt {
+    fn fmt(&self, fmt: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.is_zero() {
+            return write!(fmt, "0");
+        }
+
+        // nine decimal digits at a time
+        let billion = BigInt::from(1_000_000_000i64);
+        let mut chunks = Vec::new();
+        let mut rest = self.abs();
+        while !rest.is_zero() {
+            let (quotient, remainder) = rest.div_rem(&billion);
+            chunks.push(remainder.to_i64().unwrap());
+            rest = quotient;
+        }
+
+        let mut digits = if self.negative {
+            String::from("-")
+        } else {
+            String::new()
+        };
+        digits += &chunks.pop().unwrap().to_string();
+        for chunk in chunks.iter().rev() {
+            digits += &format!("{:09}", chunk);
+        }
+        write!(fmt, "{}", digits)
+    }
+}
@@ -0,0 +1,75 @@
+//! Expression builders that fold away trivial operands like `0 + x` or `1 * x`
+
+use crate::{Expr, Number, Operator};
+
+pub(crate) fn is_zero(n: &Number) -> bool {
+    f64::from(n.clone()) == 0.0
+}
+
+pub(crate) fn is_one(n: &Number) -> bool {
+    f64::from(n.clone()) == 1.0
+}
+
+pub(crate) fn is_negative(n: &Number) -> bool {
+    f64::from(n.clone()) < 0.0
+}
+
+/// equal values, exactly if both are exact
+pub(crate) fn same_number(a: &Number, b: &Number) -> bool {
+    match (a, b) {
+        (Number::Rational(..), Number::Rational(..)) => a == b,
+        _ => f64::from(a.clone()) == f64::from(b.clone()),
+    }
+}
+
+pub(crate) fn negate(expr: Expr) -> Expr {
+    match expr {
+        Expr::Operator(o) if o.value == Operator::Neg => *o.next.unwrap().remove(0),
+        Expr::Operator(o) if o.value == Operator::Sub => {
+            let mut next = o.next.unwrap();
+            let rhs = *next.remove(1);
+            difference(rhs, *next.remove(0))
+        }
+        expr => -expr,
+    }
+}
+
+pub(crate) fn sum(lhs: Expr, rhs: Expr) -> Expr {
+    match (&lhs, &rhs) {
+        (Expr::Number(l), _) if is_zero(l) => rhs,
+        (_, Expr::Number(r)) if is_zero(r) => lhs,
+        (_, Expr::Number(r)) if is_negative(r) => lhs - Expr::from(-r.clone()),
+        (_, Expr::Operator(r)) if r.value == Operator::Neg => lhs - negate(rhs),
+        _ => lhs + rhs,
+    }
+}
+
+pub(crate) fn difference(lhs: Expr, rhs: Expr) -> Expr {
+    match (&lhs, &rhs) {
+        (Expr::Number(l), _) if is_zero(l) => negate(rhs),
+        (_, Expr::Number(r)) if is_zero(r) => lhs,
+        (_, Expr::Number(r)) if is_negative(r) => lhs + Expr::from(-r.clone()),
+        (_, Expr::Operator(r)) if r.value == Operator::Neg => lhs + negate(rhs),
+        _ => lhs - rhs,
+    }
+}
+
+pub(crate) fn product(lhs: Expr, rhs: Expr) -> Expr {
+    match (&lhs, &rhs) {
+        (Expr::Number(l), _) if is_zero(l) => lhs,
+        (_, Expr::Number(r)) if is_zero(r) => rhs,
+        (Expr::Number(l), _) if is_one(l) => rhs,
+        (_, Expr::Number(r)) if is_one(r) => lhs,
+        (Expr::Number(l), _) if is_one(&-l.clone()) => negate(rhs),
+        _ => lhs * rhs,
+    }
+}
+
+pub(crate) fn quotient(lhs: Expr, rhs: Expr) -> Expr {
+    match (&lhs, &rhs) {
+        (Expr::Number(l), _) if is_zero(l) => lhs,
+        (Expr::Number(l), _) if is_negative(l) => negate(quotient(Expr::from(-l.clone()), rhs)),
+        (_, Expr::Number(r)) if is_one(r) => lhs,
+        _ => lhs / rhs,
+    }
+}
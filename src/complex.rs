@@ -0,0 +1,191 @@
+use std::{f64::consts::E, fmt::Display, ops};
+
+use crate::{constants, Expr, Number, Operator};
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct Complex {
+    pub re: f64,
+    pub im: f64,
+}
+
+impl Complex {
+    pub fn new(re: f64, im: f64) -> Self {
+        Self { re, im }
+    }
+
+    pub fn abs(&self) -> f64 {
+        self.re.hypot(self.im)
+    }
+
+    pub fn arg(&self) -> f64 {
+        self.im.atan2(self.re)
+    }
+
+    pub fn conj(&self) -> Self {
+        Self::new(self.re, -self.im)
+    }
+
+    pub fn exp(&self) -> Self {
+        let r = self.re.exp();
+        Self::new(r * self.im.cos(), r * self.im.sin())
+    }
+
+    pub fn sin(&self) -> Self {
+        Self::new(
+            self.re.sin() * self.im.cosh(),
+            self.re.cos() * self.im.sinh(),
+        )
+    }
+
+    pub fn cos(&self) -> Self {
+        Self::new(
+            self.re.cos() * self.im.cosh(),
+            -self.re.sin() * self.im.sinh(),
+        )
+    }
+
+    /// principal branch
+    pub fn ln(&self) -> Self {
+        Self::new(self.abs().ln(), self.arg())
+    }
+
+    /// principal branch
+    pub fn powc(&self, exp: Self) -> Self {
+        if self.re == 0.0 && self.im == 0.0 {
+            return if exp.re == 0.0 && exp.im == 0.0 {
+                Self::new(1.0, 0.0)
+            } else {
+                Self::new(0.0, 0.0)
+            };
+        }
+
+        if exp.im == 0.0 && exp.re.fract() == 0.0 && exp.re.abs() <= 64.0 {
+            // exact repeated multiplication keeps real inputs real
+            let mut result = Self::new(1.0, 0.0);
+            for _ in 0..(exp.re.abs() as u32) {
+                result = result * *self;
+            }
+            return if exp.re < 0.0 {
+                Self::new(1.0, 0.0) / result
+            } else {
+                result
+            };
+        }
+
+        (exp * self.ln()).exp()
+    }
+
+    /// is the imaginary part negligible compared to the magnitude
+    pub fn is_real(&self, tolerance: f64) -> bool {
+        self.im.abs() <= tolerance * self.abs().max(1.0)
+    }
+}
+
+impl Display for Complex {
+    fn fmt(&self, fmt: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.im == 0.0 {
+            write!(fmt, "{}", self.re)
+        } else if self.im < 0.0 {
+            write!(fmt, "{}-{}i", self.re, -self.im)
+        } else {
+            write!(fmt, "{}+{}i", self.re, self.im)
+        }
+    }
+}
+
+impl From<f64> for Complex {
+    fn from(re: f64) -> Self {
+        Self::new(re, 0.0)
+    }
+}
+
+impl From<Number> for Complex {
+    fn from(n: Number) -> Self {
+        Self::new(n.into(), 0.0)
+    }
+}
+
+impl ops::Add for Complex {
+    type Output = Complex;
+
+    fn add(self, rhs: Self) -> Self::Output {
+        Complex::new(self.re + rhs.re, self.im + rhs.im)
+    }
+}
+
+impl ops::Sub for Complex {
+    type Output = Complex;
+
+    fn sub(self, rhs: Self) -> Self::Output {
+        Complex::new(self.re - rhs.re, self.im - rhs.im)
+    }
+}
+
+impl ops::Neg for Complex {
+    type Output = Complex;
+
+    fn neg(self) -> Self::Output {
+        Complex::new(-self.re, -self.im)
+    }
+}
+
+impl ops::Mul for Complex {
+    type Output = Complex;
+
+    fn mul(self, rhs: Self) -> Self::Output {
+        Complex::new(
+            self.re * rhs.re - self.im * rhs.im,
+            self.re * rhs.im + self.im * rhs.re,
+        )
+    }
+}
+
+impl ops::Div for Complex {
+    type Output = Complex;
+
+    fn div(self, rhs: Self) -> Self::Output {
+        let denom = rhs.re * rhs.re + rhs.im * rhs.im;
+        Complex::new(
+            (self.re * rhs.re + self.im * rhs.im) / denom,
+            (self.im * rhs.re - self.re * rhs.im) / denom,
+        )
+    }
+}
+
+/// Numeric value of an expression that only contains numbers and known constants
+pub fn approximate(expr: &Expr) -> Option<Complex> {
+    match expr {
+        Expr::Number(n) => Some(n.clone().into()),
+        Expr::Variable(v) if v == constants::I => Some(Complex::new(0.0, 1.0)),
+        Expr::Variable(v) if v == constants::E => Some(Complex::from(E)),
+        Expr::Function(f) => {
+            let next = f.next.as_ref()?;
+            match (f.value.as_str(), next.len()) {
+                ("ln", 1) => Some(approximate(&next[0])?.ln()),
+                ("exp", 1) => Some(approximate(&next[0])?.exp()),
+                ("sin", 1) => Some(approximate(&next[0])?.sin()),
+                ("cos", 1) => Some(approximate(&next[0])?.cos()),
+                ("tan", 1) => {
+                    let z = approximate(&next[0])?;
+                    Some(z.sin() / z.cos())
+                }
+                _ => None,
+            }
+        }
+        Expr::Operator(o) => {
+            let next = o.next.as_ref()?;
+            let a = approximate(&next[0])?;
+            match o.value {
+                Operator::Pos => Some(a),
+                Operator::Neg => Some(-a),
+                Operator::Add => Some(a + approximate(&next[1])?),
+                Operator::Sub => Some(a - approximate(&next[1])?),
+                Operator::Mul => Some(a * approximate(&next[1])?),
+                Operator::Div => Some(a / approximate(&next[1])?),
+                Operator::Pow => Some(a.powc(approximate(&next[1])?)),
+                _ => None,
+            }
+        }
+        _ => None,
+    }
+}
@@ -1 +1,6 @@
-//TODO: pi, e, ...
+//TODO: pi, ...
+
+/// the imaginary unit, `ⅈ^2 = -1`, written `ⅈ` so the variable `i` stays a variable
+pub const I: &str = "ⅈ";
+/// euler's number
+pub const E: &str = "e";
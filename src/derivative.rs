@@ -0,0 +1,88 @@
+use crate::{
+    build::{difference, negate, product, quotient, sum},
+    constants, Engine, Expr, Number, Operator, SymErr,
+};
+
+/// `a^b` without the trivial exponents `0` and `1`
+fn power(a: Expr, b: Expr) -> Expr {
+    match b {
+        Expr::Number(Number::Rational(0, 1)) => Expr::from(1),
+        Expr::Number(Number::Rational(1, 1)) => a,
+        b => a.pow(b),
+    }
+}
+
+fn ln(a: Expr) -> Expr {
+    match a {
+        Expr::Variable(v) if v == constants::E => Expr::from(1),
+        a => Expr::function("ln", vec![a]),
+    }
+}
+
+/// Derivative of `expr` with respect to `var`.
+/// Functions are differentiated with the chain rule, using the derivatives registered with
+/// [`Engine::with_derivative`], functions without one give [`SymErr::UnknownFunction`].
+pub fn derivative(engine: &Engine, expr: &Expr, var: &str) -> Result<Expr, SymErr> {
+    if !expr.contains(var) {
+        return Ok(Expr::from(0));
+    }
+
+    match expr {
+        Expr::Variable(_) => Ok(Expr::from(1)),
+        Expr::Function(f) => {
+            let next = f.next.as_ref().unwrap();
+            if next.len() != 1 {
+                return Err(SymErr::InvalidFunctionArgCount);
+            }
+            let outer = engine
+                .derivatives
+                .get(f.value.as_str())
+                .ok_or(SymErr::UnknownFunction)?;
+
+            let inner = next[0].as_ref();
+            Ok(product(
+                outer(inner.clone()),
+                derivative(engine, inner, var)?,
+            ))
+        }
+        Expr::Operator(o) => {
+            let next = o.next.as_ref().unwrap();
+            let a = next[0].as_ref().clone();
+            let da = derivative(engine, &a, var)?;
+            if o.value.is_unary() {
+                return Ok(if o.value == Operator::Neg {
+                    negate(da)
+                } else {
+                    da
+                });
+            }
+
+            let b = next[1].as_ref().clone();
+            let db = derivative(engine, &b, var)?;
+            match o.value {
+                Operator::Add => Ok(sum(da, db)),
+                Operator::Sub => Ok(difference(da, db)),
+                Operator::Mul => Ok(sum(product(da, b.clone()), product(a, db))),
+                Operator::Div if !b.contains(var) => Ok(quotient(da, b)),
+                Operator::Div => Ok(quotient(
+                    difference(product(da, b.clone()), product(a, db)),
+                    power(b, Expr::from(2)),
+                )),
+                // d(a^n) = n a^(n-1) da
+                Operator::Pow if !b.contains(var) => {
+                    Ok(product(product(b.clone(), power(a, b - Expr::from(1))), da))
+                }
+                // d(c^b) = c^b ln(c) db
+                Operator::Pow if !a.contains(var) => Ok(product(product(expr.clone(), ln(a)), db)),
+                // d(a^b) = a^b (db ln(a) + b da / a)
+                Operator::Pow => Ok(product(
+                    expr.clone(),
+                    sum(product(db, ln(a.clone())), quotient(product(b, da), a)),
+                )),
+                Operator::Eq => Ok(Expr::equation(da, db)),
+                _ => Err(SymErr::InvalidOP),
+            }
+        }
+        _ => Ok(Expr::from(0)),
+    }
+}
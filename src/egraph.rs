@@ -0,0 +1,476 @@
+//! Equality saturation, the rules of a [`Simplifier`] are applied to an e-graph that keeps every
+//! form they produce, and the cheapest form is extracted at the end
+
+use std::{
+    collections::{HashMap, HashSet},
+    time::Instant,
+};
+
+use crate::{
+    build::same_number,
+    expr,
+    simplifier::{Bindings, Report, Rule, Simplifier, Stop},
+    Engine, Expr, IdKind, Number, Operator, Tree, ID,
+};
+
+/// What [`Strategy::Saturate`](crate::simplifier::Strategy::Saturate) minimizes when it picks
+/// the result out of all equal forms
+#[derive(Debug, Clone, Copy)]
+pub enum Cost {
+    /// numbers, variables, functions and operators
+    NodeCount,
+    /// the longest path from the root to a number or variable
+    Depth,
+    /// the node count where every occurrence of a variable counts twice, so forms that mention
+    /// each variable fewer times win, like `x*(y+1)` over `x*y+x`
+    Factored,
+    /// has to grow with the cost of every subexpression
+    Custom(fn(&Expr) -> f64),
+}
+
+fn children(expr: &Expr) -> &[Box<Expr>] {
+    match expr {
+        Expr::Function(Tree {
+            next: Some(next), ..
+        })
+        | Expr::Operator(Tree {
+            next: Some(next), ..
+        }) => next,
+        _ => &[],
+    }
+}
+
+fn node_count(expr: &Expr) -> f64 {
+    1.0 + children(expr).iter().map(|e| node_count(e)).sum::<f64>()
+}
+
+fn depth(expr: &Expr) -> f64 {
+    1.0 + children(expr).iter().map(|e| depth(e)).fold(0.0, f64::max)
+}
+
+fn occurrences(expr: &Expr) -> f64 {
+    match expr {
+        Expr::Variable(_) => 1.0,
+        expr => children(expr).iter().map(|e| occurrences(e)).sum(),
+    }
+}
+
+impl Cost {
+    pub fn cost(&self, expr: &Expr) -> f64 {
+        match self {
+            Cost::NodeCount => node_count(expr),
+            Cost::Depth => depth(expr),
+            Cost::Factored => node_count(expr) + occurrences(expr),
+            Cost::Custom(cost) => cost(expr),
+        }
+    }
+}
+
+/// an expression node without its operands
+#[derive(Debug, Clone, PartialEq)]
+enum Op {
+    Number(Number),
+    Variable(String),
+    Function(String),
+    Operator(Operator),
+}
+
+/// a node with the classes of its operands
+#[derive(Debug, Clone, PartialEq)]
+struct Node {
+    op: Op,
+    children: Vec<usize>,
+}
+
+impl Node {
+    /// numbers are told apart by their debug form, the only part of `Number` that hashes
+    fn key(&self) -> (String, Vec<usize>) {
+        (format!("{:?}", self.op), self.children.clone())
+    }
+
+    fn to_expr(&self, children: Vec<Expr>) -> Expr {
+        match &self.op {
+            Op::Number(n) => Expr::Number(n.clone()),
+            Op::Variable(v) => Expr::Variable(v.clone()),
+            Op::Function(f) => Expr::function(f.clone(), children),
+            Op::Operator(o) => Expr::Operator(Tree {
+                value: *o,
+                next: Some(children.into_iter().map(Box::new).collect()),
+            }),
+        }
+    }
+}
+
+/// Classes of equal expressions, the operands of a node are classes so every class stands for
+/// all combinations of the forms of its operands
+#[derive(Debug, Default)]
+struct EGraph {
+    /// union find, a class is a root if it is its own parent
+    parents: Vec<usize>,
+    /// the nodes of every root class
+    classes: Vec<Vec<Node>>,
+    /// the class of every node with canonical operands
+    memo: HashMap<(String, Vec<usize>), usize>,
+    /// the node count of the smallest form of every root class, kept up to date by adding and
+    /// merging and settled by [`EGraph::rebuild`]
+    sizes: Vec<usize>,
+}
+
+impl EGraph {
+    fn find(&self, mut class: usize) -> usize {
+        while self.parents[class] != class {
+            class = self.parents[class];
+        }
+        class
+    }
+
+    fn roots(&self) -> impl Iterator<Item = usize> + '_ {
+        (0..self.parents.len()).filter(move |&class| self.parents[class] == class)
+    }
+
+    fn nodes(&self) -> usize {
+        self.roots().map(|class| self.classes[class].len()).sum()
+    }
+
+    fn canonical(&self, node: &Node) -> Node {
+        Node {
+            op: node.op.clone(),
+            children: node.children.iter().map(|&c| self.find(c)).collect(),
+        }
+    }
+
+    /// the node count of the smallest form of `node`
+    fn size(&self, node: &Node) -> usize {
+        node.children
+            .iter()
+            .fold(1, |size, &c| size.saturating_add(self.sizes[self.find(c)]))
+    }
+
+    fn add_node(&mut self, node: Node) -> usize {
+        let node = self.canonical(&node);
+        if let Some(&class) = self.memo.get(&node.key()) {
+            return self.find(class);
+        }
+        let class = self.parents.len();
+        self.parents.push(class);
+        self.memo.insert(node.key(), class);
+        self.sizes.push(self.size(&node));
+        self.classes.push(vec![node]);
+        class
+    }
+
+    /// `expr` with the identifiers replaced by the classes they are bound to
+    fn add(&mut self, expr: &Expr, ids: &HashMap<ID, usize>) -> usize {
+        let children = children(expr).iter().map(|e| self.add(e, ids)).collect();
+        let op = match expr {
+            Expr::Identifier(i) => return self.find(ids[i]),
+            Expr::Number(n) => Op::Number(n.clone()),
+            Expr::Variable(v) => Op::Variable(v.clone()),
+            Expr::Function(f) => Op::Function(f.value.clone()),
+            Expr::Operator(o) => Op::Operator(o.value),
+        };
+        self.add_node(Node { op, children })
+    }
+
+    fn union(&mut self, a: usize, b: usize) -> bool {
+        let (a, b) = (self.find(a), self.find(b));
+        if a == b {
+            return false;
+        }
+        self.parents[b] = a;
+        self.sizes[a] = self.sizes[a].min(self.sizes[b]);
+        let nodes = std::mem::take(&mut self.classes[b]);
+        self.classes[a].extend(nodes);
+        true
+    }
+
+    /// [`EGraph::merge_congruent`], then the smaller forms the merges found are passed on to the
+    /// sizes of the classes that contain them
+    fn rebuild(&mut self) {
+        self.merge_congruent();
+        let mut changed = true;
+        while changed {
+            changed = false;
+            for class in self.roots().collect::<Vec<_>>() {
+                let size = self.classes[class].iter().map(|node| self.size(node)).min();
+                if let Some(size) = size.filter(|&size| size < self.sizes[class]) {
+                    self.sizes[class] = size;
+                    changed = true;
+                }
+            }
+        }
+    }
+
+    /// merges the classes that got equal nodes through earlier merges, until there are none
+    fn merge_congruent(&mut self) {
+        loop {
+            self.memo.clear();
+            let mut merges = Vec::new();
+            for class in self.roots().collect::<Vec<_>>() {
+                let mut nodes = Vec::new();
+                let mut keys = HashSet::new();
+                for node in &self.classes[class] {
+                    let node = self.canonical(node);
+                    if !keys.insert(node.key()) {
+                        continue;
+                    }
+                    match self.memo.get(&node.key()) {
+                        Some(&other) if other != class => merges.push((other, class)),
+                        _ => {
+                            self.memo.insert(node.key(), class);
+                        }
+                    }
+                    nodes.push(node);
+                }
+                self.classes[class] = nodes;
+            }
+
+            if merges.is_empty() {
+                return;
+            }
+            for (a, b) in merges {
+                self.union(a, b);
+            }
+        }
+    }
+
+    fn has(&self, class: usize, op: impl Fn(&Op) -> bool) -> bool {
+        self.classes[self.find(class)]
+            .iter()
+            .any(|node| op(&node.op))
+    }
+
+    /// every extension of the bindings `ids` with which `pattern` matches a form of `class`
+    fn matches(
+        &self,
+        pattern: &Expr,
+        class: usize,
+        ids: &HashMap<ID, usize>,
+    ) -> Vec<HashMap<ID, usize>> {
+        let class = self.find(class);
+        let op = match pattern {
+            Expr::Identifier(i) => {
+                let admits = match i.kind() {
+                    IdKind::Any => true,
+                    IdKind::Number => self.has(class, |op| matches!(op, Op::Number(_))),
+                    IdKind::Symbol => self.has(class, |op| matches!(op, Op::Variable(_))),
+                };
+                return match ids.get(i) {
+                    Some(&bound) if self.find(bound) == class => vec![ids.clone()],
+                    None if admits => {
+                        let mut ids = ids.clone();
+                        ids.insert(i.clone(), class);
+                        vec![ids]
+                    }
+                    _ => Vec::new(),
+                };
+            }
+            Expr::Number(n) => Op::Number(n.clone()),
+            Expr::Variable(v) => Op::Variable(v.clone()),
+            Expr::Function(f) => Op::Function(f.value.clone()),
+            Expr::Operator(o) => Op::Operator(o.value),
+        };
+
+        let patterns = children(pattern);
+        let mut found = Vec::new();
+        for node in &self.classes[class] {
+            let same = match (&node.op, &op) {
+                (Op::Number(a), Op::Number(b)) => same_number(a, b),
+                (a, b) => a == b,
+            };
+            if !same || node.children.len() != patterns.len() {
+                continue;
+            }
+            let matched = patterns.iter().zip(&node.children).fold(
+                vec![ids.clone()],
+                |found, (pattern, &child)| {
+                    found
+                        .iter()
+                        .flat_map(|ids| self.matches(pattern, child, ids))
+                        .collect()
+                },
+            );
+            found.extend(matched);
+        }
+        found
+    }
+
+    /// a number of `class`
+    fn number(&self, class: usize) -> Option<Number> {
+        self.classes[self.find(class)]
+            .iter()
+            .find_map(|node| match &node.op {
+                Op::Number(n) => Some(n.clone()),
+                _ => None,
+            })
+    }
+
+    /// every operation on numbers that [`Simplifier::fold`] folds with the class of its node and
+    /// the folded number
+    fn folds(&self) -> Vec<(usize, Number)> {
+        let mut folds = Vec::new();
+        for class in self.roots() {
+            for node in &self.classes[class] {
+                let numbers = node
+                    .children
+                    .iter()
+                    .map(|&c| self.number(c).map(Expr::Number));
+                let Some(numbers) = numbers.collect::<Option<Vec<_>>>() else {
+                    continue;
+                };
+                if let (Op::Operator(_), Some(Expr::Number(n))) =
+                    (&node.op, Simplifier::fold(&node.to_expr(numbers)))
+                {
+                    folds.push((class, n));
+                }
+            }
+        }
+        folds
+    }
+
+    /// the form of `class` with the fewest nodes, see [`EGraph::sizes`], the forms built on the
+    /// way are kept in `forms` until the graph changes
+    fn smallest(&self, class: usize, forms: &mut HashMap<usize, Expr>) -> Expr {
+        let class = self.find(class);
+        if let Some(form) = forms.get(&class) {
+            return form.clone();
+        }
+        let node = self.classes[class]
+            .iter()
+            .min_by_key(|node| self.size(node))
+            .unwrap();
+        let children = node
+            .children
+            .iter()
+            .map(|&c| self.smallest(c, forms))
+            .collect();
+        let form = node.to_expr(children);
+        forms.insert(class, form.clone());
+        form
+    }
+
+    /// the cheapest form of every class, classes without a finite form are left out
+    fn extract(&self, cost: &Cost) -> HashMap<usize, (f64, Expr)> {
+        let mut best: HashMap<usize, (f64, Expr)> = HashMap::new();
+        // every pass can only shorten the forms of classes that depend on ones that changed
+        for _ in 0..=self.parents.len() {
+            let mut changed = false;
+            for class in self.roots() {
+                for node in &self.classes[class] {
+                    let children = node
+                        .children
+                        .iter()
+                        .map(|c| best.get(&self.find(*c)).map(|(_, e)| e.clone()))
+                        .collect::<Option<Vec<_>>>();
+                    let Some(children) = children else {
+                        continue;
+                    };
+                    let expr = node.to_expr(children);
+                    let c = cost.cost(&expr);
+                    if best.get(&class).is_none_or(|(old, _)| c < *old) {
+                        best.insert(class, (c, expr));
+                        changed = true;
+                    }
+                }
+            }
+            if !changed {
+                break;
+            }
+        }
+        best
+    }
+}
+
+/// `+` and `*` are associative and commutative, and quotients are products with an inverse, so
+/// rules see every grouping and the cheaper form is extracted either way
+fn structural_rules() -> Vec<Rule> {
+    let (a, b, c) = (
+        || expr!(ID::new(0)),
+        || expr!(ID::new(1)),
+        || expr!(ID::new(2)),
+    );
+    let mut rules = Vec::new();
+    for oper in [Operator::Add, Operator::Mul] {
+        let op = |l: Expr, r: Expr| l.operate(oper, Some(r)).unwrap();
+        rules.push(Rule::new(op(a(), b()), op(b(), a())));
+        rules.push(Rule::new(op(op(a(), b()), c()), op(a(), op(b(), c()))));
+        rules.push(Rule::new(op(a(), op(b(), c())), op(op(a(), b()), c())));
+    }
+    rules.push(Rule::new(a() / b(), a() * b().pow(expr!(-1))));
+    rules
+}
+
+/// Adds the forms of `expr` that the rules of `simplifier` lead to until none are new or the
+/// budget of the engine or the node limit of the simplifier is used up, the result is the
+/// cheapest form
+pub(crate) fn saturate(engine: &Engine, simplifier: &Simplifier, expr: &Expr) -> (Expr, Report) {
+    let start = Instant::now();
+    let mut graph = EGraph::default();
+    let root = graph.add(expr, &HashMap::new());
+    let structural = structural_rules();
+    let rules = simplifier.enabled().chain(&structural).collect::<Vec<_>>();
+
+    let mut iterations = 0;
+    let stop = loop {
+        if iterations >= engine.budget().iterations {
+            break Stop::Iterations;
+        }
+        if engine
+            .budget()
+            .time
+            .is_some_and(|time| start.elapsed() >= time)
+        {
+            break Stop::Time;
+        }
+        if graph.nodes() > simplifier.node_limit() {
+            break Stop::Nodes;
+        }
+
+        // guards look at the smallest form of what the identifiers matched
+        let mut smallest = HashMap::new();
+        let mut found = Vec::new();
+        for class in graph.roots() {
+            for rule in &rules {
+                for ids in graph.matches(rule.matcher(), class, &HashMap::new()) {
+                    let bindings = ids
+                        .iter()
+                        .map(|(i, &c)| (i.clone(), graph.smallest(c, &mut smallest)))
+                        .collect::<Bindings>();
+                    if rule.holds(&bindings) {
+                        found.push((class, rule.replace(), ids));
+                    }
+                }
+            }
+        }
+
+        let mut changed = false;
+        for (class, replace, ids) in found {
+            let replaced = graph.add(replace, &ids);
+            changed |= graph.union(class, replaced);
+        }
+        for (class, number) in graph.folds() {
+            let folded = graph.add(&Expr::Number(number), &HashMap::new());
+            changed |= graph.union(class, folded);
+        }
+        graph.rebuild();
+        if !changed {
+            break Stop::Fixpoint;
+        }
+        iterations += 1;
+    };
+
+    let simplified = graph
+        .extract(simplifier.cost())
+        .remove(&graph.find(root))
+        .map_or_else(|| expr.clone(), |(_, e)| e);
+    let mut forms = vec![expr.clone()];
+    if simplified != *expr {
+        forms.push(simplified.clone());
+    }
+    let report = Report {
+        stop,
+        iterations,
+        forms,
+    };
+    (simplified, report)
+}
@@ -1,38 +1,152 @@
-use crate::Engine;
+use crate::{
+    trace::{Step, Trace},
+    Bindings, Engine,
+};
 
-use super::{Expr, Operator, SymErr};
+use super::{Expr, Operator, SymErr, Tree};
 
 pub fn eval_tree(engine: &Engine, tree: &Expr) -> Result<Expr, SymErr> {
+    eval_tree_with(engine, tree, &Bindings::new())
+}
+
+/// like [`eval_tree`], but variables in `bindings` are replaced by their values first
+pub fn eval_tree_with(engine: &Engine, tree: &Expr, bindings: &Bindings) -> Result<Expr, SymErr> {
+    eval(engine, tree, bindings, &mut Vec::new(), None)
+}
+
+/// [`eval_tree_with`] with a step for every substituted variable, function and operator
+pub fn eval_tree_with_trace(
+    engine: &Engine,
+    tree: &Expr,
+    bindings: &Bindings,
+) -> Result<Trace, SymErr> {
+    let mut steps = Vec::new();
+    eval(engine, tree, bindings, &mut Vec::new(), Some(&mut steps))?;
+    Ok(Trace {
+        input: tree.clone(),
+        steps,
+    })
+}
+
+/// `before` at `path` became `after`, unless nothing changed
+fn record(
+    steps: Option<&mut Vec<Step>>,
+    rule: &str,
+    path: &[usize],
+    before: impl FnOnce() -> Expr,
+    after: &Expr,
+) {
+    if let Some(steps) = steps {
+        let before = before();
+        if before != *after {
+            steps.push(Step {
+                rule: String::from(rule),
+                path: path.to_vec(),
+                before,
+                after: after.clone(),
+                conditions: Vec::new(),
+            });
+        }
+    }
+}
+
+/// `oper` applied to the operands without evaluating it
+fn operation(oper: Operator, operands: &[&Expr]) -> Expr {
+    Expr::Operator(Tree {
+        value: oper,
+        next: Some(operands.iter().map(|&e| Box::new(e.clone())).collect()),
+    })
+}
+
+/// evaluates the operand at `index` of the expression at `path`
+fn operand(
+    engine: &Engine,
+    tree: &Expr,
+    bindings: &Bindings,
+    path: &mut Vec<usize>,
+    steps: &mut Option<&mut Vec<Step>>,
+    index: usize,
+) -> Result<Expr, SymErr> {
+    path.push(index);
+    let value = eval(engine, tree, bindings, path, steps.as_deref_mut());
+    path.pop();
+    value
+}
+
+fn eval(
+    engine: &Engine,
+    tree: &Expr,
+    bindings: &Bindings,
+    path: &mut Vec<usize>,
+    mut steps: Option<&mut Vec<Step>>,
+) -> Result<Expr, SymErr> {
     match &tree {
+        Expr::Variable(v) => {
+            let value = bindings
+                .get(v.as_str())
+                .cloned()
+                .unwrap_or_else(|| tree.clone());
+            record(steps, "substitute", path, || tree.clone(), &value);
+            Ok(value)
+        }
         Expr::Function(f) => {
             if let Some(function) = engine.functions.get(f.value.as_str()) {
-                function.1(engine, f.next.as_ref().unwrap())
+                let next = f.next.as_ref().unwrap();
+                let (arguments, result) = if bindings.is_empty() {
+                    (None, function.1(engine, next)?)
+                } else {
+                    let arguments = next
+                        .iter()
+                        .enumerate()
+                        .map(|(i, e)| {
+                            Ok(Box::new(operand(engine, e, bindings, path, &mut steps, i)?))
+                        })
+                        .collect::<Result<Vec<_>, SymErr>>()?;
+                    let result = function.1(engine, &arguments)?;
+                    (Some(arguments), result)
+                };
+                let before = || match arguments {
+                    Some(arguments) => {
+                        Expr::function(f.value.clone(), arguments.into_iter().map(|e| *e).collect())
+                    }
+                    None => tree.clone(),
+                };
+                record(steps, &f.value, path, before, &result);
+                Ok(result)
             } else {
                 Err(SymErr::UnknownFunction)
             }
         }
         Expr::Operator(o) => match o.value {
             Operator::Pos | Operator::Neg => {
-                let value = eval_tree(engine, o.next.as_ref().unwrap()[0].as_ref())?;
+                let next = o.next.as_ref().unwrap();
+                let value = operand(engine, &next[0], bindings, path, &mut steps, 0)?;
 
                 let string = format!("Evaluating, {}({})", o.value.to(), value);
+                let before = steps.is_some().then(|| operation(o.value, &[&value]));
                 let result = value.operate(o.value, None).unwrap();
                 if engine.debugging {
                     println!("{} = {}", string, result);
                 }
+                record(steps, "evaluate", path, || before.unwrap(), &result);
                 Ok(result)
             }
             _ => {
-                let left = eval_tree(engine, o.next.as_ref().unwrap()[0].as_ref())?;
-                let right = eval_tree(engine, o.next.as_ref().unwrap()[1].as_ref())?;
+                let next = o.next.as_ref().unwrap();
+                let left = operand(engine, &next[0], bindings, path, &mut steps, 0)?;
+                let right = operand(engine, &next[1], bindings, path, &mut steps, 1)?;
 
                 let string = format!("Evaluating, {}{}{}", left, o.value.to(), right);
+                let before = steps
+                    .is_some()
+                    .then(|| operation(o.value, &[&left, &right]));
                 let result = left.operate(o.value, Some(right)).unwrap();
 
                 if engine.debugging {
                     println!("{} = {}", string, result);
                 }
 
+                record(steps, "evaluate", path, || before.unwrap(), &result);
                 Ok(result)
             }
         },
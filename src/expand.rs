@@ -0,0 +1,218 @@
+use std::cmp::Ordering;
+
+use crate::{
+    build::{difference, is_negative, is_zero, product, quotient, sum},
+    Expr, Number, Operator, Tree,
+};
+
+/// powers of the factors that can not be multiplied out, sorted by the printed factor
+pub(crate) type Monomial = Vec<(Expr, i64)>;
+
+/// sum of terms with a coefficient each, no two with the same monomial
+pub(crate) type Terms = Vec<(Monomial, Number)>;
+
+fn one() -> Number {
+    Number::Rational(1, 1)
+}
+
+fn atom(expr: Expr) -> Terms {
+    vec![(vec![(expr, 1)], one())]
+}
+
+fn constant(n: Number) -> Terms {
+    if is_zero(&n) {
+        Vec::new()
+    } else {
+        vec![(Vec::new(), n)]
+    }
+}
+
+/// the product of two monomials, factors with an exponent of zero cancel
+pub(crate) fn join(a: &[(Expr, i64)], b: &[(Expr, i64)]) -> Monomial {
+    let mut monomial = a.to_vec();
+    for (factor, exponent) in b {
+        match monomial.iter().position(|(f, _)| f == factor) {
+            Some(i) => monomial[i].1 += exponent,
+            None => monomial.push((factor.clone(), *exponent)),
+        }
+    }
+    monomial.retain(|(_, exponent)| *exponent != 0);
+    monomial.sort_by_key(|(factor, _)| factor.print());
+    monomial
+}
+
+fn add(mut a: Terms, b: Terms) -> Terms {
+    for (monomial, c) in b {
+        match a.iter().position(|(m, _)| *m == monomial) {
+            Some(i) => a[i].1 = a[i].1.clone() + c,
+            None => a.push((monomial, c)),
+        }
+    }
+    a.retain(|(_, c)| !is_zero(c));
+    a
+}
+
+fn scale(a: Terms, factor: Number) -> Terms {
+    if is_zero(&factor) {
+        return Vec::new();
+    }
+    a.into_iter()
+        .map(|(m, c)| (m, c * factor.clone()))
+        .collect()
+}
+
+pub(crate) fn multiply(a: &Terms, b: &Terms) -> Terms {
+    let mut terms = Vec::new();
+    for (ma, ca) in a {
+        for (mb, cb) in b {
+            terms = add(terms, vec![(join(ma, mb), ca.clone() * cb.clone())]);
+        }
+    }
+    terms
+}
+
+/// the exponent if it is an integer
+fn integer(expr: &Expr) -> Option<i64> {
+    match expr {
+        Expr::Number(Number::Rational(n, 1)) => Some(*n),
+        _ => None,
+    }
+}
+
+/// `base^exponent`, multiplied out for sums with a natural exponent
+fn power(base: Terms, exponent: i64) -> Terms {
+    match base.as_slice() {
+        [] if exponent > 0 => Vec::new(),
+        [(monomial, c)] => {
+            let c = c.clone().pow(Number::Rational(exponent, 1));
+            let monomial = monomial
+                .iter()
+                .map(|(factor, e)| (factor.clone(), e * exponent))
+                .collect::<Vec<_>>();
+            vec![(join(&monomial, &[]), c)]
+        }
+        _ if exponent >= 0 => {
+            // by squaring, so the multinomial coefficients come out of combining like terms
+            let (mut result, mut square, mut n) = (constant(one()), base, exponent);
+            while n > 0 {
+                if n % 2 == 1 {
+                    result = multiply(&result, &square);
+                }
+                n /= 2;
+                if n > 0 {
+                    square = multiply(&square, &square);
+                }
+            }
+            result
+        }
+        _ => vec![(vec![(to_expr(&base), exponent)], one())],
+    }
+}
+
+pub(crate) fn terms(expr: &Expr, functions: bool) -> Terms {
+    match expr {
+        Expr::Number(n) => constant(n.clone()),
+        Expr::Function(f) if functions => {
+            let next = f.next.iter().flatten().map(|e| expand(e, true)).collect();
+            atom(Expr::function(f.value.clone(), next))
+        }
+        Expr::Operator(o) => {
+            let next = o.next.as_ref().unwrap();
+            let lhs = || terms(&next[0], functions);
+            let rhs = || terms(&next[1], functions);
+            match o.value {
+                Operator::Pos => lhs(),
+                Operator::Neg => scale(lhs(), -one()),
+                Operator::Add => add(lhs(), rhs()),
+                Operator::Sub => add(lhs(), scale(rhs(), -one())),
+                Operator::Mul => multiply(&lhs(), &rhs()),
+                Operator::Div => match rhs().as_slice() {
+                    [(monomial, c)] => {
+                        let inverse = monomial.iter().map(|(f, e)| (f.clone(), -e)).collect();
+                        multiply(&lhs(), &vec![(inverse, one() / c.clone())])
+                    }
+                    _ => multiply(&lhs(), &power(rhs(), -1)),
+                },
+                Operator::Pow => match integer(&next[1]) {
+                    Some(exponent) => power(lhs(), exponent),
+                    None => atom(to_expr(&lhs()).pow(expand(&next[1], functions))),
+                },
+                _ => atom(expr.clone()),
+            }
+        }
+        expr => atom(expr.clone()),
+    }
+}
+
+/// larger total degree first, then lexicographically by factor
+fn compare(a: &[(Expr, i64)], b: &[(Expr, i64)]) -> Ordering {
+    let degree = |m: &[(Expr, i64)]| m.iter().map(|(_, e)| e).sum::<i64>();
+    degree(b).cmp(&degree(a)).then_with(|| {
+        for ((fa, ea), (fb, eb)) in a.iter().zip(b) {
+            let order = fa.print().cmp(&fb.print()).then(eb.cmp(ea));
+            if order != Ordering::Equal {
+                return order;
+            }
+        }
+        b.len().cmp(&a.len())
+    })
+}
+
+fn factor_power(factor: &Expr, exponent: i64) -> Expr {
+    if exponent == 1 {
+        factor.clone()
+    } else {
+        factor.clone().pow(Expr::from(exponent))
+    }
+}
+
+/// negative powers go in the denominator
+pub(crate) fn term(monomial: &[(Expr, i64)], c: Number) -> Expr {
+    let (numerator, denominator) = match c {
+        Number::Rational(n, d) => (Expr::from(n), Expr::from(d)),
+        c => (Expr::from(c), Expr::from(1)),
+    };
+    let numerator = monomial
+        .iter()
+        .filter(|(_, e)| *e > 0)
+        .fold(numerator, |acc, (f, e)| product(acc, factor_power(f, *e)));
+    let denominator = monomial
+        .iter()
+        .filter(|(_, e)| *e < 0)
+        .fold(denominator, |acc, (f, e)| product(acc, factor_power(f, -e)));
+    quotient(numerator, denominator)
+}
+
+pub(crate) fn to_expr(terms: &Terms) -> Expr {
+    let mut sorted = terms.clone();
+    sorted.sort_by(|(a, _), (b, _)| compare(a, b));
+    let mut sorted = sorted.into_iter();
+    let first = match sorted.next() {
+        Some((monomial, c)) => term(&monomial, c),
+        None => return Expr::from(0),
+    };
+    sorted.fold(first, |acc, (monomial, c)| {
+        if is_negative(&c) {
+            difference(acc, term(&monomial, -c))
+        } else {
+            sum(acc, term(&monomial, c))
+        }
+    })
+}
+
+/// Multiplies out products and natural powers of sums into a sum of terms, like terms combined
+/// and sorted by degree. Function arguments are only expanded if `functions` is set, everything
+/// else that can not be multiplied out is kept as a factor. Both sides of relations are expanded.
+pub fn expand(expr: &Expr, functions: bool) -> Expr {
+    match expr {
+        Expr::Operator(Tree {
+            value,
+            next: Some(next),
+        }) if value.is_relation() => Expr::relation(
+            *value,
+            expand(&next[0], functions),
+            expand(&next[1], functions),
+        ),
+        expr => to_expr(&terms(expr, functions)),
+    }
+}
@@ -0,0 +1,846 @@
+use std::{cmp::Ordering, convert::TryFrom, fmt::Display};
+
+use crate::{
+    build::{is_one, product},
+    multivariate::{MonomialOrder, MultiPolynomial},
+    Engine, Expr, Number, SymErr,
+};
+
+/// `unit * factors[0].0^factors[0].1 * factors[1].0^factors[1].1 * ...`
+#[derive(Debug, PartialEq, Clone)]
+pub struct Factorization {
+    pub unit: Number,
+    /// irreducible, primitive with integer coefficients and a positive leading coefficient, each
+    /// with its multiplicity
+    pub factors: Vec<(MultiPolynomial, u32)>,
+}
+
+impl Factorization {
+    /// the product, like `2*(x-1)^2*(x+y)`
+    pub fn to_expr(&self) -> Expr {
+        let mut factors = self.factors.iter().map(|(factor, multiplicity)| {
+            if *multiplicity == 1 {
+                factor.to_expr()
+            } else {
+                factor.to_expr().pow(Expr::from(*multiplicity as i64))
+            }
+        });
+
+        // the unit goes on the first factor, so `-1` prints as `-(x-2)*(x+2)`
+        let first = match factors.next() {
+            Some(first) if is_one(&-self.unit.clone()) => -first,
+            Some(first) => product(Expr::from(self.unit.clone()), first),
+            None => return Expr::from(self.unit.clone()),
+        };
+        factors.fold(first, |acc, factor| acc * factor)
+    }
+}
+
+impl Display for Factorization {
+    fn fmt(&self, fmt: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(fmt, "{}", self.to_expr())
+    }
+}
+
+/// Factors `expr`, a polynomial with rational coefficients in all of its variables, into
+/// irreducible factors over the integers
+pub fn factor(engine: &Engine, expr: &Expr) -> Result<Expr, SymErr> {
+    let vars = expr.variables();
+    let vars = vars.iter().map(String::as_str).collect::<Vec<_>>();
+    let polynomial = MultiPolynomial::from_expr(engine, expr, &vars, MonomialOrder::Lex)?;
+    let factored = factor_polynomial(&polynomial)?.to_expr();
+
+    if engine.debugging {
+        println!("Factoring {}: {}", expr, factored);
+    }
+
+    Ok(factored)
+}
+
+/// Content, square-free decomposition and then every square-free part is split into irreducible
+/// factors with Zassenhaus' algorithm, multivariate ones through a Kronecker substitution after
+/// homogeneous ones lost their last variable. [`SymErr::Inconvertible`] for irrational coefficients, [`SymErr::Unsolvable`] if the
+/// substituted polynomial is of a degree above [`MAX_DEGREE`].
+///
+/// Factors are found with p-adic lifting up to `2^62`, a factor whose coefficients are beyond
+/// that is not split any further.
+pub fn factor_polynomial(polynomial: &MultiPolynomial) -> Result<Factorization, SymErr> {
+    integer_primitive(polynomial)?;
+    if polynomial.is_zero() {
+        return Ok(Factorization {
+            unit: Number::Rational(0, 1),
+            factors: Vec::new(),
+        });
+    }
+
+    let mut factors: Vec<(MultiPolynomial, u32)> = Vec::new();
+    for (part, multiplicity) in square_free(polynomial) {
+        let (_, part) = integer_primitive(&part)?;
+        for factor in irreducible_factors(part)? {
+            let factor = integer_primitive(&factor)?.1;
+            match factors.iter_mut().find(|(f, _)| *f == factor) {
+                Some((_, m)) => *m += multiplicity,
+                None => factors.push((factor, multiplicity)),
+            }
+        }
+    }
+    // lowest degree first, then term by term
+    factors.sort_by(|(a, _), (b, _)| {
+        let terms = |p: &MultiPolynomial| {
+            p.terms()
+                .iter()
+                .map(|(m, c)| (m.clone(), f64::from(c.clone())))
+                .collect::<Vec<_>>()
+        };
+        a.total_degree()
+            .cmp(&b.total_degree())
+            .then_with(|| terms(a).partial_cmp(&terms(b)).unwrap_or(Ordering::Equal))
+    });
+
+    let one = MultiPolynomial::constant(
+        polynomial.vars().to_vec(),
+        polynomial.order(),
+        Number::Rational(1, 1),
+    );
+    let product = factors.iter().fold(one, |acc, (factor, multiplicity)| {
+        acc * factor.pow(*multiplicity)
+    });
+    Ok(Factorization {
+        unit: polynomial.leading_coefficient() / product.leading_coefficient(),
+        factors,
+    })
+}
+
+/// Yun's algorithm in the first variable that appears, the content in that variable is
+/// decomposed recursively. The parts are coprime, `(part, multiplicity)`.
+/// Gives back the whole polynomial as one part if the coefficients of the gcds do not fit.
+pub fn square_free(polynomial: &MultiPolynomial) -> Vec<(MultiPolynomial, u32)> {
+    yun(polynomial).unwrap_or_else(|| vec![(polynomial.clone(), 1)])
+}
+
+fn yun(polynomial: &MultiPolynomial) -> Option<Vec<(MultiPolynomial, u32)>> {
+    let var = match polynomial
+        .vars()
+        .iter()
+        .find(|var| polynomial.degree(var) > Some(0))
+    {
+        Some(var) => var.clone(),
+        None => return Some(Vec::new()),
+    };
+    let exact = |p: MultiPolynomial| {
+        let rational = p
+            .terms()
+            .iter()
+            .all(|(_, c)| matches!(c, Number::Rational(..)));
+        Some(p).filter(|_| rational)
+    };
+
+    let zero = MultiPolynomial::new(polynomial.vars().to_vec(), polynomial.order());
+    let content = polynomial
+        .coefficients_in(&var)
+        .iter()
+        .try_fold(zero, |acc, c| acc.gcd(c))
+        .ok()?;
+    let content = exact(content)?;
+    let mut parts = yun(&content)?;
+
+    let p = polynomial.exact_div(&content).ok()?;
+    let dp = p.derivative(&var);
+    let a = exact(p.gcd(&dp).ok()?)?;
+    let mut b = p.exact_div(&a).ok()?;
+    let mut d = dp.exact_div(&a).ok()? - b.derivative(&var);
+    let mut multiplicity = 1;
+    while b.degree(&var) > Some(0) {
+        let a = exact(b.gcd(&d).ok()?)?;
+        b = b.exact_div(&a).ok()?;
+        d = d.exact_div(&a).ok()? - b.derivative(&var);
+        if !a.is_constant() {
+            parts.push((a, multiplicity));
+        }
+        multiplicity += 1;
+    }
+    Some(parts)
+}
+
+/// `(content, polynomial / content)`, where the result has coprime integer coefficients and a
+/// positive leading coefficient
+fn integer_primitive(polynomial: &MultiPolynomial) -> Result<(Number, MultiPolynomial), SymErr> {
+    let (mut numerator, mut denominator) = (0, 1);
+    for (_, c) in polynomial.terms() {
+        match c {
+            Number::Rational(n, d) => {
+                numerator = gcd(numerator, *n as i128);
+                denominator = denominator / gcd(denominator, *d as i128) * *d as i128;
+            }
+            Number::Irrational(_) => return Err(SymErr::Inconvertible),
+        }
+    }
+    if numerator == 0 {
+        return Ok((Number::Rational(1, 1), polynomial.clone()));
+    }
+
+    let numerator = i64::try_from(numerator).or(Err(SymErr::Inconvertible))?;
+    let denominator = i64::try_from(denominator).or(Err(SymErr::Inconvertible))?;
+    let mut content = Number::Rational(numerator, 1) / Number::Rational(denominator, 1);
+    if f64::from(polynomial.leading_coefficient()) < 0.0 {
+        content = -content;
+    }
+    Ok((
+        content.clone(),
+        polynomial.scale(Number::Rational(1, 1) / content),
+    ))
+}
+
+/// the largest degree of the substituted polynomial that is split, Zassenhaus would be far too
+/// slow beyond it
+pub const MAX_DEGREE: u64 = 512;
+
+/// the indices of the variables that appear in `polynomial`
+fn present(polynomial: &MultiPolynomial) -> Vec<usize> {
+    (0..polynomial.vars().len())
+        .filter(|&i| polynomial.terms().iter().any(|(m, _)| m[i] != 0))
+        .collect()
+}
+
+/// Irreducible factors of a primitive `polynomial`, repeated ones as often as they divide it.
+/// A homogeneous polynomial in more than one variable, with `z` the last one, is `z^d` times its
+/// value at `z = 1`. That value has one variable less and its factors made homogeneous again
+/// are the factors of the polynomial, with a `z` for every degree lost on the way. This keeps
+/// binomials like `x^30-y^30` far below [`MAX_DEGREE`].
+fn irreducible_factors(polynomial: MultiPolynomial) -> Result<Vec<MultiPolynomial>, SymErr> {
+    let present = present(&polynomial);
+    let degree = polynomial.total_degree().unwrap_or(0);
+    let homogeneous = polynomial
+        .terms()
+        .iter()
+        .all(|(m, _)| m.iter().sum::<u32>() == degree);
+    let last = match present.last() {
+        Some(&last) if homogeneous && present.len() > 1 => last,
+        _ => return kronecker(polynomial),
+    };
+
+    let mut factors = Vec::new();
+    let mut dropped = degree;
+    for factor in irreducible_factors(with_exponent(&polynomial, last, |_| 0))? {
+        let d = factor.total_degree().unwrap_or(0);
+        factors.push(with_exponent(&factor, last, |m| d - m.iter().sum::<u32>()));
+        dropped -= d;
+    }
+    let one = MultiPolynomial::constant(
+        polynomial.vars().to_vec(),
+        polynomial.order(),
+        Number::Rational(1, 1),
+    );
+    let z = with_exponent(&one, last, |_| 1);
+    factors.extend(std::iter::repeat_n(z, dropped as usize));
+    Ok(factors)
+}
+
+/// `polynomial` where the variable at `index` has the exponent `e(monomial)` in every term,
+/// `monomial` being the term's one without that variable
+fn with_exponent(
+    polynomial: &MultiPolynomial,
+    index: usize,
+    e: impl Fn(&[u32]) -> u32,
+) -> MultiPolynomial {
+    let terms = polynomial.terms().iter().map(|(monomial, c)| {
+        let mut monomial = monomial.clone();
+        monomial[index] = 0;
+        monomial[index] = e(&monomial);
+        (monomial, c.clone())
+    });
+    MultiPolynomial::from_terms(
+        polynomial.vars().to_vec(),
+        polynomial.order(),
+        terms.collect(),
+    )
+}
+
+/// Irreducible factors of a primitive `polynomial`, repeated ones as often as they divide it:
+/// the variables `x_0, x_1, ...` that appear are substituted with `y^1, y^b_0, y^(b_0*b_1), ...`,
+/// where `b_i` is above the degree in `x_i`, so each factor maps to a product of univariate
+/// factors and back again. [`SymErr::Unsolvable`] if the degree in `y` is above [`MAX_DEGREE`].
+fn kronecker(polynomial: MultiPolynomial) -> Result<Vec<MultiPolynomial>, SymErr> {
+    let present = present(&polynomial);
+    if present.is_empty() {
+        return Ok(vec![polynomial]);
+    }
+    let bases = present
+        .iter()
+        .map(|&i| polynomial.terms().iter().map(|(m, _)| m[i]).max().unwrap() as u64 + 1)
+        .collect::<Vec<_>>();
+    bases
+        .iter()
+        .try_fold(1u64, |acc, &base| acc.checked_mul(base))
+        .filter(|&degree| degree <= MAX_DEGREE + 1)
+        .ok_or(SymErr::Unsolvable)?;
+
+    let exponent = |monomial: &[u32]| {
+        present
+            .iter()
+            .zip(&bases)
+            .rev()
+            .fold(0, |acc, (&i, &base)| acc * base + monomial[i] as u64) as usize
+    };
+    let mut image = Vec::new();
+    for (monomial, c) in polynomial.terms() {
+        let e = exponent(monomial);
+        if image.len() <= e {
+            image.resize(e + 1, 0);
+        }
+        if let Number::Rational(n, _) = c {
+            image[e] = *n as i128;
+        }
+    }
+
+    // a univariate factor with coefficients beyond i64 cannot be a factor of the polynomial
+    let substitute_back = |univariate: &[i128], vars: &[String], order| {
+        let mut terms = Vec::new();
+        for (mut e, &c) in univariate.iter().enumerate().filter(|(_, &c)| c != 0) {
+            let mut monomial = vec![0; vars.len()];
+            for (&i, &base) in present.iter().zip(&bases) {
+                monomial[i] = (e as u64 % base) as u32;
+                e = (e as u64 / base) as usize;
+            }
+            terms.push((monomial, Number::Rational(i64::try_from(c).ok()?, 1)));
+        }
+        Some(MultiPolynomial::from_terms(vars.to_vec(), order, terms))
+    };
+
+    let mut parts = univariate_factors(primitive(image))?;
+    let mut polynomial = polynomial;
+    let mut factors = Vec::new();
+    let mut size = 1;
+    while 2 * size <= parts.len() {
+        let found = subsets(parts.len(), size).into_iter().find_map(|subset| {
+            let image = subset
+                .iter()
+                .try_fold(vec![1], |acc, &i| multiply(&acc, &parts[i]))?;
+            let candidate = substitute_back(&image, polynomial.vars(), polynomial.order())?;
+            let quotient = polynomial.exact_div(&candidate).ok()?;
+            Some((subset, candidate, quotient))
+        });
+        match found {
+            Some((subset, candidate, quotient)) => {
+                factors.push(candidate);
+                polynomial = quotient;
+                parts = remove(parts, &subset);
+            }
+            None => size += 1,
+        }
+    }
+    factors.push(polynomial);
+    Ok(factors)
+}
+
+/// dense polynomial with integer coefficients, lowest degree first, without trailing zeros
+type Zx = Vec<i128>;
+
+fn gcd(a: i128, b: i128) -> i128 {
+    if b == 0 {
+        a.abs()
+    } else {
+        gcd(b, a % b)
+    }
+}
+
+fn trim(mut p: Zx) -> Zx {
+    while p.last() == Some(&0) {
+        p.pop();
+    }
+    p
+}
+
+/// divided by the gcd of the coefficients, with a positive leading coefficient
+fn primitive(p: Zx) -> Zx {
+    let p = trim(p);
+    let content = p.iter().fold(0, |acc, &c| gcd(acc, c)) * p.last().map_or(1, |c| c.signum());
+    if content == 0 {
+        return p;
+    }
+    p.into_iter().map(|c| c / content).collect()
+}
+
+/// exact product, `None` if it does not fit
+fn multiply(a: &[i128], b: &[i128]) -> Option<Zx> {
+    let mut result = vec![0i128; a.len() + b.len() - 1];
+    for (i, x) in a.iter().enumerate() {
+        for (j, y) in b.iter().enumerate() {
+            result[i + j] = result[i + j].checked_add(x.checked_mul(*y)?)?;
+        }
+    }
+    Some(trim(result))
+}
+
+/// `a / b` if it is an integer polynomial, `None` otherwise
+fn exact_quotient(a: &[i128], b: &[i128]) -> Option<Zx> {
+    let mut remainder = a.to_vec();
+    let d = b.len() - 1;
+    if remainder.is_empty() {
+        return Some(remainder);
+    }
+    if remainder.len() <= d {
+        return None;
+    }
+
+    let mut quotient = vec![0; remainder.len() - d];
+    for i in (0..quotient.len()).rev() {
+        if remainder[i + d] % b[d] != 0 {
+            return None;
+        }
+        let factor = remainder[i + d] / b[d];
+        for (j, c) in b.iter().enumerate() {
+            remainder[i + j] = remainder[i + j].checked_sub(factor.checked_mul(*c)?)?;
+        }
+        quotient[i] = factor;
+    }
+    if remainder.iter().any(|&c| c != 0) {
+        return None;
+    }
+    Some(trim(quotient))
+}
+
+/// every subset of `0..n` with `k` elements
+fn subsets(n: usize, k: usize) -> Vec<Vec<usize>> {
+    if k == 0 {
+        return vec![Vec::new()];
+    }
+    (k - 1..n)
+        .flat_map(|last| {
+            subsets(last, k - 1).into_iter().map(move |mut subset| {
+                subset.push(last);
+                subset
+            })
+        })
+        .collect()
+}
+
+fn remove(parts: Vec<Zx>, subset: &[usize]) -> Vec<Zx> {
+    parts
+        .into_iter()
+        .enumerate()
+        .filter(|(i, _)| !subset.contains(i))
+        .map(|(_, part)| part)
+        .collect()
+}
+
+/// the irreducible factors of a primitive polynomial, repeated by their multiplicity.
+/// The image of a square-free polynomial under the substitution need not be square-free, so this
+/// runs Yun's algorithm first, [`SymErr::Overflow`] if the gcds or the lifting overflow.
+fn univariate_factors(f: Zx) -> Result<Vec<Zx>, SymErr> {
+    let yun = || {
+        let df = derivative(&f);
+        let a = integer_gcd(&f, &df)?;
+        let mut b = exact_quotient(&f, &a)?;
+        let mut d = subtract(&exact_quotient(&df, &a)?, &derivative(&b))?;
+
+        let mut parts = Vec::new();
+        let mut multiplicity = 1;
+        while b.len() > 1 {
+            let a = integer_gcd(&b, &d)?;
+            b = exact_quotient(&b, &a)?;
+            d = subtract(&exact_quotient(&d, &a)?, &derivative(&b))?;
+            if a.len() > 1 {
+                parts.push((a, multiplicity));
+            }
+            multiplicity += 1;
+        }
+        Some(parts)
+    };
+
+    let mut factors = Vec::new();
+    for (part, multiplicity) in yun().ok_or(SymErr::Overflow)? {
+        for factor in zassenhaus(part)? {
+            factors.extend(std::iter::repeat_n(factor, multiplicity));
+        }
+    }
+    Ok(factors)
+}
+
+fn derivative(p: &[i128]) -> Zx {
+    p.iter()
+        .enumerate()
+        .skip(1)
+        .map(|(i, c)| c * i as i128)
+        .collect()
+}
+
+fn subtract(a: &[i128], b: &[i128]) -> Option<Zx> {
+    (0..a.len().max(b.len()))
+        .map(|i| a.get(i).unwrap_or(&0).checked_sub(*b.get(i).unwrap_or(&0)))
+        .collect::<Option<_>>()
+        .map(trim)
+}
+
+/// Miller-Rabin with bases that make it exact for every `u64`
+fn is_prime(n: u64) -> bool {
+    let bases = [2, 3, 5, 7, 11, 13, 17, 19, 23, 29, 31, 37];
+    if n < 2 {
+        return false;
+    }
+    if let Some(&base) = bases.iter().find(|&&base| n.is_multiple_of(base)) {
+        return n == base;
+    }
+
+    let n = n as u128;
+    let pow = |mut base: u128, mut exponent: u128| {
+        let mut result = 1;
+        while exponent > 0 {
+            if exponent % 2 == 1 {
+                result = result * base % n;
+            }
+            base = base * base % n;
+            exponent /= 2;
+        }
+        result
+    };
+    let zeros = (n - 1).trailing_zeros();
+    let odd = (n - 1) >> zeros;
+    bases.iter().all(|&base| {
+        let mut x = pow(base as u128, odd);
+        if x == 1 || x == n - 1 {
+            return true;
+        }
+        (1..zeros).any(|_| {
+            x = x * x % n;
+            x == n - 1
+        })
+    })
+}
+
+/// Primitive gcd of integer polynomials: the gcd modulo a prime near `2^61` with the right leading
+/// coefficient is the gcd whenever it divides both. `None` if a few primes did not find it, which
+/// only happens when its coefficients are not far below the prime.
+fn integer_gcd(a: &[i128], b: &[i128]) -> Option<Zx> {
+    let (a, b) = (primitive(a.to_vec()), primitive(b.to_vec()));
+    if a.is_empty() || b.is_empty() {
+        return Some(if a.is_empty() { b } else { a });
+    }
+
+    let (la, lb) = (a[a.len() - 1], b[b.len() - 1]);
+    let lc = gcd(la, lb);
+    (0..1 << 20)
+        .map(|i| (1 << 61) - 1 - 2 * i)
+        .filter(|&p| is_prime(p as u64) && la % p != 0 && lb % p != 0)
+        .take(4)
+        .find_map(|p| {
+            let g = modular::gcd(&modular::reduce(&a, p), &modular::reduce(&b, p), p);
+            let candidate = primitive(modular::symmetric(modular::scale(&g, lc, p), p));
+            exact_quotient(&a, &candidate)?;
+            exact_quotient(&b, &candidate)?;
+            Some(candidate)
+        })
+}
+
+/// p-adic lifting never goes beyond this, so products of two residues fit in an `i128`
+const MAX_MODULUS: i128 = 1 << 62;
+
+/// Irreducible factors of a square-free primitive polynomial: factor it modulo a small prime,
+/// lift the factors to a modulus above the coefficients of any factor and try every product of
+/// them. [`SymErr::Overflow`] if that modulus is above [`MAX_MODULUS`].
+fn zassenhaus(f: Zx) -> Result<Vec<Zx>, SymErr> {
+    let n = f.len() - 1;
+    if n <= 1 {
+        return Ok(vec![f]);
+    }
+
+    let lc = f[n];
+    let p = (3..)
+        .filter(|&p| (2..p).take_while(|d| d * d <= p).all(|d| p % d != 0))
+        .find(|&p| {
+            if lc % p == 0 {
+                return false;
+            }
+            let fp = modular::reduce(&f, p);
+            modular::gcd(&fp, &modular::derivative(&fp, p), p).len() == 1
+        })
+        .unwrap();
+
+    let monic = modular::scale(&modular::reduce(&f, p), modular::inverse(lc, p), p);
+    let factors = modular::factor(monic, p);
+    if factors.len() == 1 {
+        return Ok(vec![f]);
+    }
+
+    // mignotte: the coefficients of any factor times the leading coefficient are below this
+    let norm = f.iter().map(|&c| (c as f64).powi(2)).sum::<f64>().sqrt();
+    let bound = 2.0 * (lc as f64).abs() * 2f64.powi(n as i32) * norm;
+    let mut modulus = p;
+    while (modulus as f64) <= bound {
+        if modulus * p > MAX_MODULUS {
+            return Err(SymErr::Overflow);
+        }
+        modulus *= p;
+    }
+    let mut lifted = modular::lift(&f, &factors, p, modulus);
+
+    let mut f = f;
+    let mut factors = Vec::new();
+    let mut size = 1;
+    while 2 * size <= lifted.len() {
+        let found = subsets(lifted.len(), size).into_iter().find_map(|subset| {
+            let lc = vec![f[f.len() - 1].rem_euclid(modulus)];
+            let candidate = subset
+                .iter()
+                .fold(lc, |acc, &i| modular::multiply(&acc, &lifted[i], modulus));
+            let candidate = primitive(modular::symmetric(candidate, modulus));
+            let quotient = exact_quotient(&f, &candidate)?;
+            Some((subset, candidate, quotient))
+        });
+        match found {
+            Some((subset, candidate, quotient)) => {
+                factors.push(candidate);
+                f = quotient;
+                lifted = remove(lifted, &subset);
+            }
+            None => size += 1,
+        }
+    }
+    factors.push(f);
+    Ok(factors)
+}
+
+/// arithmetic of polynomials with coefficients modulo `m`, which is a prime unless stated
+/// otherwise
+mod modular {
+    use rand::Rng;
+
+    use super::{trim, Zx};
+
+    pub fn reduce(p: &[i128], m: i128) -> Zx {
+        trim(p.iter().map(|c| c.rem_euclid(m)).collect())
+    }
+
+    /// coefficients between `-m/2` and `m/2`
+    pub fn symmetric(p: Zx, m: i128) -> Zx {
+        p.into_iter()
+            .map(|c| if c > m / 2 { c - m } else { c })
+            .collect()
+    }
+
+    /// inverse of `a` modulo any `m` coprime to it
+    pub fn inverse(a: i128, m: i128) -> i128 {
+        let (mut r0, mut r1) = (a.rem_euclid(m), m);
+        let (mut s0, mut s1) = (1, 0);
+        while r1 != 0 {
+            let q = r0 / r1;
+            (r0, r1) = (r1, r0 - q * r1);
+            (s0, s1) = (s1, s0 - q * s1);
+        }
+        s0.rem_euclid(m)
+    }
+
+    pub fn scale(p: &[i128], factor: i128, m: i128) -> Zx {
+        trim(p.iter().map(|c| c * factor % m).collect())
+    }
+
+    pub fn add(a: &[i128], b: &[i128], m: i128) -> Zx {
+        let n = a.len().max(b.len());
+        trim(
+            (0..n)
+                .map(|i| (a.get(i).unwrap_or(&0) + b.get(i).unwrap_or(&0)).rem_euclid(m))
+                .collect(),
+        )
+    }
+
+    pub fn sub(a: &[i128], b: &[i128], m: i128) -> Zx {
+        let n = a.len().max(b.len());
+        trim(
+            (0..n)
+                .map(|i| (a.get(i).unwrap_or(&0) - b.get(i).unwrap_or(&0)).rem_euclid(m))
+                .collect(),
+        )
+    }
+
+    /// modulo any `m` below `2^63`
+    pub fn multiply(a: &[i128], b: &[i128], m: i128) -> Zx {
+        if a.is_empty() || b.is_empty() {
+            return Vec::new();
+        }
+        let mut result = vec![0; a.len() + b.len() - 1];
+        for (i, x) in a.iter().enumerate() {
+            for (j, y) in b.iter().enumerate() {
+                result[i + j] = (result[i + j] + x * y) % m;
+            }
+        }
+        trim(result)
+    }
+
+    /// the leading coefficient of `b` has to be invertible modulo `m`
+    pub fn div_rem(a: &[i128], b: &[i128], m: i128) -> (Zx, Zx) {
+        let d = b.len() - 1;
+        if a.len() <= d {
+            return (Vec::new(), a.to_vec());
+        }
+
+        let inverse = inverse(b[d], m);
+        let mut remainder = a.to_vec();
+        let mut quotient = vec![0; a.len() - d];
+        for i in (0..quotient.len()).rev() {
+            let factor = remainder[i + d] * inverse % m;
+            for (j, c) in b.iter().enumerate() {
+                remainder[i + j] = (remainder[i + j] - factor * c).rem_euclid(m);
+            }
+            quotient[i] = factor;
+        }
+        remainder.truncate(d);
+        (trim(quotient), trim(remainder))
+    }
+
+    pub fn derivative(p: &[i128], m: i128) -> Zx {
+        trim(
+            p.iter()
+                .enumerate()
+                .skip(1)
+                .map(|(i, c)| c * i as i128 % m)
+                .collect(),
+        )
+    }
+
+    /// monic
+    pub fn gcd(a: &[i128], b: &[i128], p: i128) -> Zx {
+        let (mut a, mut b) = (a.to_vec(), b.to_vec());
+        while !b.is_empty() {
+            let remainder = div_rem(&a, &b, p).1;
+            a = b;
+            b = remainder;
+        }
+        match a.last() {
+            Some(&lc) => scale(&a, inverse(lc, p), p),
+            None => a,
+        }
+    }
+
+    /// `(s, t)` with `s*a + t*b = 1` for coprime `a` and `b`
+    pub fn bezout(a: &[i128], b: &[i128], p: i128) -> (Zx, Zx) {
+        let (mut r0, mut r1) = (a.to_vec(), b.to_vec());
+        let (mut s0, mut s1) = (vec![1], Vec::new());
+        let (mut t0, mut t1) = (Vec::new(), vec![1]);
+        while !r1.is_empty() {
+            let (q, r) = div_rem(&r0, &r1, p);
+            let s = sub(&s0, &multiply(&q, &s1, p), p);
+            let t = sub(&t0, &multiply(&q, &t1, p), p);
+            (r0, r1) = (r1, r);
+            (s0, s1) = (s1, s);
+            (t0, t1) = (t1, t);
+        }
+        let inverse = inverse(r0[0], p);
+        (scale(&s0, inverse, p), scale(&t0, inverse, p))
+    }
+
+    /// `base^exponent` modulo the polynomial `f`
+    pub fn pow(base: &[i128], mut exponent: i128, f: &[i128], p: i128) -> Zx {
+        let mut result = vec![1];
+        let mut base = div_rem(base, f, p).1;
+        while exponent > 0 {
+            if exponent % 2 == 1 {
+                result = div_rem(&multiply(&result, &base, p), f, p).1;
+            }
+            base = div_rem(&multiply(&base, &base, p), f, p).1;
+            exponent /= 2;
+        }
+        result
+    }
+
+    /// Monic irreducible factors of the square-free monic `f` modulo the odd prime `p`: distinct
+    /// degree factorization, then Cantor-Zassenhaus for the factors of equal degree
+    pub fn factor(mut f: Zx, p: i128) -> Vec<Zx> {
+        let x = vec![0, 1];
+        let mut factors = Vec::new();
+        let mut h = x.clone();
+        let mut d = 1;
+        while f.len() > 2 * d {
+            // the gcd of f and x^(p^d) - x is the product of the factors of degree d
+            h = pow(&h, p, &f, p);
+            let g = gcd(&f, &sub(&h, &x, p), p);
+            if g.len() > 1 {
+                equal_degree(g.clone(), d, p, &mut factors);
+                f = div_rem(&f, &g, p).0;
+                h = div_rem(&h, &f, p).1;
+            }
+            d += 1;
+        }
+        if f.len() > 1 {
+            factors.push(f);
+        }
+        factors
+    }
+
+    fn equal_degree(f: Zx, d: usize, p: i128, factors: &mut Vec<Zx>) {
+        if f.len() - 1 == d {
+            factors.push(f);
+            return;
+        }
+
+        loop {
+            let a = trim(
+                (0..f.len() - 1)
+                    .map(|_| rand::thread_rng().gen_range(0..p))
+                    .collect(),
+            );
+            if a.len() < 2 {
+                continue;
+            }
+
+            // a^((p^d - 1)/2) = (a * a^p * ... * a^(p^(d-1)))^((p - 1)/2)
+            let mut power = a.clone();
+            let mut norm = a;
+            for _ in 1..d {
+                power = pow(&power, p, &f, p);
+                norm = div_rem(&multiply(&norm, &power, p), &f, p).1;
+            }
+            let b = sub(&pow(&norm, (p - 1) / 2, &f, p), &[1], p);
+            let g = gcd(&f, &b, p);
+            if 1 < g.len() && g.len() < f.len() {
+                let rest = div_rem(&f, &g, p).0;
+                equal_degree(g, d, p, factors);
+                equal_degree(rest, d, p, factors);
+                return;
+            }
+        }
+    }
+
+    /// Lifts `f = lc(f) * g * h (mod p)`, with monic coprime `g` and `h`, to
+    /// `f = lc(f) * g * h (mod modulus)` one power of `p` at a time
+    fn hensel(f: &[i128], mut g: Zx, mut h: Zx, p: i128, modulus: i128) -> (Zx, Zx) {
+        let lc = f[f.len() - 1].rem_euclid(modulus);
+        let lc_inverse = inverse(lc, p);
+        let (s, t) = bezout(&g, &h, p);
+
+        let mut m = p;
+        while m < modulus {
+            let error = sub(
+                f,
+                &multiply(&[lc], &multiply(&g, &h, modulus), modulus),
+                modulus,
+            );
+            let c = scale(
+                &error.iter().map(|e| e / m % p).collect::<Vec<_>>(),
+                lc_inverse,
+                p,
+            );
+            let dg = div_rem(&multiply(&t, &c, p), &g, p).1;
+            let dh = div_rem(&multiply(&s, &c, p), &h, p).1;
+            g = add(&g, &scale(&dg, m, modulus), modulus);
+            h = add(&h, &scale(&dh, m, modulus), modulus);
+            m *= p;
+        }
+        (g, h)
+    }
+
+    /// the monic `factors` of `f` modulo `p`, lifted to `modulus`, a power of `p`
+    pub fn lift(f: &[i128], factors: &[Zx], p: i128, modulus: i128) -> Vec<Zx> {
+        if factors.len() == 1 {
+            let lc = f[f.len() - 1];
+            return vec![scale(&reduce(f, modulus), inverse(lc, modulus), modulus)];
+        }
+
+        let h = factors[1..]
+            .iter()
+            .fold(vec![1], |acc, factor| multiply(&acc, factor, p));
+        let (g, h) = hensel(f, factors[0].clone(), h, p, modulus);
+        let mut lifted = vec![g];
+        lifted.extend(lift(&h, &factors[1..], p, modulus));
+        lifted
+    }
+}
@@ -1,10 +1,10 @@
-use crate::{Engine, Expr, FnMap, Number, SymErr};
+use crate::{constants, DerivMap, Engine, Expr, FnMap, InvMap, Number, SymErr};
 
-pub fn ln(engine: &Engine, arguments: &Vec<Box<Expr>>) -> Result<Expr, SymErr> {
+pub fn ln(engine: &Engine, arguments: &[Box<Expr>]) -> Result<Expr, SymErr> {
     if arguments.len() != 1 {
         Err(SymErr::InvalidFunctionArgCount)
     } else {
-        let arg_0 = arguments.get(0).unwrap().as_ref().eval(engine)?;
+        let arg_0 = arguments.first().unwrap().as_ref().eval(engine)?;
 
         match arg_0 {
             Expr::Number(Number::Rational(0, 1)) => Err(SymErr::Undefined),
@@ -21,6 +21,62 @@ pub fn ln(engine: &Engine, arguments: &Vec<Box<Expr>>) -> Result<Expr, SymErr> {
     }
 }
 
+/// single argument function that is exact at `0` and approximated elsewhere
+fn unary(
+    engine: &Engine,
+    arguments: &[Box<Expr>],
+    name: &str,
+    at_zero: i64,
+    f: fn(f64) -> f64,
+) -> Result<Expr, SymErr> {
+    if arguments.len() != 1 {
+        Err(SymErr::InvalidFunctionArgCount)
+    } else {
+        let arg_0 = arguments.first().unwrap().as_ref().eval(engine)?;
+
+        match arg_0 {
+            Expr::Number(Number::Rational(0, 1)) => Ok(Expr::from(at_zero)),
+            Expr::Number(n) => Ok(Expr::from(f(n.into()))),
+            _ => Expr::func(name, vec![arg_0]),
+        }
+    }
+}
+
+pub fn exp(engine: &Engine, arguments: &[Box<Expr>]) -> Result<Expr, SymErr> {
+    unary(engine, arguments, "exp", 1, f64::exp)
+}
+
+pub fn sin(engine: &Engine, arguments: &[Box<Expr>]) -> Result<Expr, SymErr> {
+    unary(engine, arguments, "sin", 0, f64::sin)
+}
+
+pub fn cos(engine: &Engine, arguments: &[Box<Expr>]) -> Result<Expr, SymErr> {
+    unary(engine, arguments, "cos", 1, f64::cos)
+}
+
+pub fn tan(engine: &Engine, arguments: &[Box<Expr>]) -> Result<Expr, SymErr> {
+    unary(engine, arguments, "tan", 0, f64::tan)
+}
+
 pub fn all(map: &mut FnMap) {
     map.insert("ln", (1, ln));
+    map.insert("exp", (1, exp));
+    map.insert("sin", (1, sin));
+    map.insert("cos", (1, cos));
+    map.insert("tan", (1, tan));
+}
+
+pub fn inverses(map: &mut InvMap) {
+    map.insert("ln", |y| Expr::from(constants::E).pow(y));
+    map.insert("exp", |y| Expr::function("ln", vec![y]));
+}
+
+pub fn derivatives(map: &mut DerivMap) {
+    map.insert("ln", |u| Expr::from(1) / u);
+    map.insert("exp", |u| Expr::function("exp", vec![u]));
+    map.insert("sin", |u| Expr::function("cos", vec![u]));
+    map.insert("cos", |u| -Expr::function("sin", vec![u]));
+    map.insert("tan", |u| {
+        Expr::from(1) / Expr::function("cos", vec![u]).pow(Expr::from(2))
+    });
 }
@@ -0,0 +1,296 @@
+use crate::{
+    build::is_zero,
+    multivariate::{Monomial, MonomialOrder, MultiPolynomial},
+    polynomial::Polynomial,
+    roots::real_roots,
+    solver::sides,
+    Engine, Expr, Number, SymErr,
+};
+
+/// does the monomial `a` divide `b`
+fn divides(a: &[u32], b: &[u32]) -> bool {
+    a.iter().zip(b).all(|(a, b)| a <= b)
+}
+
+/// `c` times the monomial, in the variables of `like`
+fn term(like: &MultiPolynomial, monomial: Monomial, c: Number) -> MultiPolynomial {
+    MultiPolynomial::from_terms(like.vars().to_vec(), like.order(), vec![(monomial, c)])
+}
+
+/// all of them in terms of the same variables, sorted by `order`
+fn common(polynomials: &[MultiPolynomial], order: MonomialOrder) -> Vec<MultiPolynomial> {
+    let mut vars: Vec<String> = Vec::new();
+    for var in polynomials.iter().flat_map(|p| p.vars()) {
+        if !vars.contains(var) {
+            vars.push(var.clone());
+        }
+    }
+    polynomials
+        .iter()
+        .map(|p| p.with_vars(&vars).unwrap().with_order(order))
+        .collect()
+}
+
+/// the polynomial if it is exact like the ones it was computed from, [`SymErr::Overflow`] if
+/// rational coefficients did not stay rational
+fn checked(exact: bool, p: MultiPolynomial) -> Result<MultiPolynomial, SymErr> {
+    if exact && !p.is_exact() {
+        Err(SymErr::Overflow)
+    } else {
+        Ok(p)
+    }
+}
+
+/// Remainder of the multivariate division of `polynomial` by `divisors`: no term of it is
+/// divisible by the leading term of any divisor. Unique if the divisors are a Gröbner basis.
+pub fn reduce(
+    polynomial: &MultiPolynomial,
+    divisors: &[MultiPolynomial],
+) -> Result<MultiPolynomial, SymErr> {
+    let mut all = vec![polynomial.clone()];
+    all.extend_from_slice(divisors);
+    let mut all = common(&all, polynomial.order());
+    let exact = all.iter().all(MultiPolynomial::is_exact);
+    let mut p = all.remove(0);
+
+    let mut remainder = MultiPolynomial::new(p.vars().to_vec(), p.order());
+    while let Some((monomial, c)) = p.leading_term().cloned() {
+        let divisor = all.iter().find(|d| {
+            d.leading_monomial()
+                .is_some_and(|lm| divides(lm, &monomial))
+        });
+        let reduction = match divisor {
+            Some(d) => {
+                let (lm, lc) = d.leading_term().unwrap();
+                let shift = monomial.iter().zip(lm).map(|(a, b)| a - b).collect();
+                term(&p, shift, c / lc.clone()) * d.clone()
+            }
+            None => {
+                let leading = term(&p, monomial, c);
+                remainder = remainder + leading.clone();
+                leading
+            }
+        };
+        p = checked(exact, p - reduction)?;
+    }
+    Ok(remainder)
+}
+
+/// the combination of `f` and `g` where their leading terms cancel
+pub fn s_polynomial(f: &MultiPolynomial, g: &MultiPolynomial) -> Result<MultiPolynomial, SymErr> {
+    let (f, g) = match common(&[f.clone(), g.clone()], f.order()).as_slice() {
+        [f, g] => (f.clone(), g.clone()),
+        _ => unreachable!(),
+    };
+    let (lf, cf) = match f.leading_term() {
+        Some(leading) => leading.clone(),
+        None => return Ok(f),
+    };
+    let (lg, cg) = match g.leading_term() {
+        Some(leading) => leading.clone(),
+        None => return Ok(g),
+    };
+    let exact = f.is_exact() && g.is_exact();
+
+    let lcm = lf
+        .iter()
+        .zip(&lg)
+        .map(|(a, b)| *a.max(b))
+        .collect::<Vec<_>>();
+    let shift = |m: &[u32]| lcm.iter().zip(m).map(|(a, b)| a - b).collect();
+    let one = || Number::Rational(1, 1);
+    checked(
+        exact,
+        term(&f, shift(&lf), one() / cf) * f.clone() - term(&g, shift(&lg), one() / cg) * g,
+    )
+}
+
+/// Reduced Gröbner basis of the ideal generated by `generators` in the monomial `order`, with
+/// Buchberger's algorithm. The basis is sorted by leading monomial, largest first, every element
+/// is monic and none of its terms is divisible by the leading term of another one, so each ideal
+/// has exactly one for each order. [`SymErr::Overflow`] if rational coefficients do not stay
+/// rational on the way.
+pub fn groebner(
+    generators: &[MultiPolynomial],
+    order: MonomialOrder,
+) -> Result<Vec<MultiPolynomial>, SymErr> {
+    let mut basis = common(generators, order)
+        .into_iter()
+        .filter(|p| !p.is_zero())
+        .map(|p| p.monic())
+        .collect::<Vec<_>>();
+    let mut pairs = (0..basis.len())
+        .flat_map(|j| (0..j).map(move |i| (i, j)))
+        .collect::<Vec<_>>();
+
+    let lcm = |basis: &[MultiPolynomial], (i, j): (usize, usize)| {
+        let (a, b) = (basis[i].leading_monomial(), basis[j].leading_monomial());
+        a.unwrap()
+            .iter()
+            .zip(b.unwrap())
+            .map(|(a, b)| *a.max(b))
+            .collect::<Vec<_>>()
+    };
+    while !pairs.is_empty() {
+        // the pair with the smallest lcm first
+        let next = (0..pairs.len())
+            .min_by(|&a, &b| order.compare(&lcm(&basis, pairs[a]), &lcm(&basis, pairs[b])))
+            .unwrap();
+        let (i, j) = pairs.swap_remove(next);
+
+        // leading monomials without common variables always reduce to zero
+        let (a, b) = (basis[i].leading_monomial(), basis[j].leading_monomial());
+        if a.unwrap()
+            .iter()
+            .zip(b.unwrap())
+            .all(|(a, b)| *a == 0 || *b == 0)
+        {
+            continue;
+        }
+
+        let s = reduce(&s_polynomial(&basis[i], &basis[j])?, &basis)?;
+        if !s.is_zero() {
+            let k = basis.len();
+            pairs.extend((0..k).map(|i| (i, k)));
+            basis.push(s.monic());
+        }
+    }
+
+    // keep only the elements whose leading monomial no other one divides
+    let minimal = basis
+        .iter()
+        .enumerate()
+        .filter(|(i, g)| {
+            let lm = g.leading_monomial().unwrap();
+            !basis.iter().enumerate().any(|(j, h)| {
+                let other = h.leading_monomial().unwrap();
+                j != *i && divides(other, lm) && (other != lm || j < *i)
+            })
+        })
+        .map(|(_, g)| g.clone())
+        .collect::<Vec<_>>();
+    let mut reduced = (0..minimal.len())
+        .map(|i| {
+            let others = [&minimal[..i], &minimal[i + 1..]].concat();
+            Ok(reduce(&minimal[i], &others)?.monic())
+        })
+        .collect::<Result<Vec<_>, SymErr>>()?;
+    reduced.sort_by(|a, b| {
+        order.compare(b.leading_monomial().unwrap(), a.leading_monomial().unwrap())
+    });
+    Ok(reduced)
+}
+
+/// is `polynomial` in the ideal generated by `generators`
+pub fn is_member(
+    polynomial: &MultiPolynomial,
+    generators: &[MultiPolynomial],
+) -> Result<bool, SymErr> {
+    Ok(reduce(polynomial, &groebner(generators, polynomial.order())?)?.is_zero())
+}
+
+/// Real solutions of a system of polynomial equations with finitely many solutions, each with a
+/// value for every one of `vars`, exact where the roots are found in closed form.
+/// The lexicographic basis is triangular, its last elements only contain the last variables, so
+/// their roots are substituted back one variable at a time.
+/// [`SymErr::Unsolvable`] if there are infinitely many solutions.
+pub fn solve(engine: &Engine, equations: &[Expr], vars: &[&str]) -> Result<Vec<Vec<Expr>>, SymErr> {
+    let polynomials = equations
+        .iter()
+        .map(|equation| {
+            let (lhs, rhs) = sides(equation);
+            MultiPolynomial::from_expr(engine, &(lhs - rhs), vars, MonomialOrder::Lex)
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    let basis = groebner(&polynomials, MonomialOrder::Lex)?;
+    if basis.iter().any(MultiPolynomial::is_constant) {
+        return Ok(Vec::new());
+    }
+
+    // finitely many solutions exactly when every variable has a power that is a leading monomial
+    let pure = |i: usize| {
+        basis.iter().any(|g| {
+            let lm = g.leading_monomial().unwrap();
+            lm[i] > 0 && lm.iter().enumerate().all(|(j, &e)| j == i || e == 0)
+        })
+    };
+    if !(0..vars.len()).all(pure) {
+        return Err(SymErr::Unsolvable);
+    }
+
+    // the values of the last variables, as numbers to substitute and as expressions
+    let mut solutions: Vec<Vec<(Number, Expr)>> = vec![Vec::new()];
+    for k in (0..vars.len()).rev() {
+        let eliminated = basis
+            .iter()
+            .filter(|g| g.degree(vars[k]) > Some(0))
+            .filter(|g| vars[..k].iter().all(|var| g.degree(var) == Some(0)))
+            .collect::<Vec<_>>();
+
+        let mut next = Vec::new();
+        for solution in &solutions {
+            let mut values = vec![Number::Rational(0, 1); k + 1];
+            values.extend(solution.iter().map(|(n, _)| n.clone()));
+            let univariate = eliminated
+                .iter()
+                .map(|g| {
+                    Polynomial::new(
+                        g.coefficients_in(vars[k])
+                            .iter()
+                            .map(|c| c.evaluate(&values))
+                            .collect(),
+                    )
+                })
+                .filter(|p| !p.is_zero())
+                .collect::<Vec<_>>();
+            let lowest = match univariate.iter().min_by_key(|p| p.degree()) {
+                Some(lowest) => lowest.clone(),
+                None => return Err(SymErr::Unsolvable),
+            };
+
+            let mut roots = real_roots(lowest)?;
+            roots.sort_by(|a, b| a.0.total_cmp(&b.0));
+            roots.dedup_by(|b, a| (a.0 - b.0).abs() <= 1e-9 * a.0.abs().max(1.0));
+            for (x, root) in roots {
+                let value = match &root {
+                    Expr::Number(n) => n.clone(),
+                    _ => Number::Irrational(x),
+                };
+                // a root of one of them has to be a root of all of them
+                let holds = univariate.iter().all(|p| {
+                    let scale = p
+                        .coefficients()
+                        .iter()
+                        .enumerate()
+                        .map(|(i, c)| f64::from(c.clone()).abs() * x.abs().powi(i as i32))
+                        .sum::<f64>();
+                    let residual = p.evaluate(&value);
+                    is_zero(&residual) || f64::from(residual).abs() <= 1e-9 * scale.max(1.0)
+                });
+                if holds {
+                    let mut extended = vec![(value, root)];
+                    extended.extend(solution.iter().cloned());
+                    next.push(extended);
+                }
+            }
+        }
+        solutions = next;
+    }
+
+    let solutions = solutions
+        .into_iter()
+        .map(|solution| solution.into_iter().map(|(_, e)| e).collect::<Vec<_>>())
+        .collect::<Vec<_>>();
+    if engine.debugging {
+        for solution in &solutions {
+            let values = vars
+                .iter()
+                .zip(solution)
+                .map(|(var, e)| format!("{} = {}", var, e))
+                .collect::<Vec<_>>();
+            println!("Solution: {}", values.join(", "));
+        }
+    }
+
+    Ok(solutions)
+}
@@ -0,0 +1,147 @@
+use std::convert::TryFrom;
+
+use crate::{
+    polynomial::Polynomial,
+    roots::real_roots,
+    set::{Endpoint, Interval, Set},
+    Engine, Expr, Number, Operator, SymErr, Tree,
+};
+
+/// `expr` as a quotient of two polynomials in `var`
+fn fraction(engine: &Engine, expr: &Expr, var: &str) -> Result<(Polynomial, Polynomial), SymErr> {
+    if !expr.contains(var) {
+        return match expr.eval(engine)? {
+            Expr::Number(n) => Ok((n.into(), Number::Rational(1, 1).into())),
+            _ => Err(SymErr::Inconvertible),
+        };
+    }
+
+    let o = match expr {
+        Expr::Variable(_) => return Ok((Polynomial::x(), Number::Rational(1, 1).into())),
+        Expr::Operator(o) => o,
+        _ => return Err(SymErr::Inconvertible),
+    };
+    let next = o.next.as_ref().unwrap();
+
+    let (an, ad) = fraction(engine, &next[0], var)?;
+    if o.value.is_unary() {
+        return Ok(if o.value == Operator::Neg {
+            (-an, ad)
+        } else {
+            (an, ad)
+        });
+    }
+
+    if o.value == Operator::Pow {
+        return match next[1].eval(engine)? {
+            Expr::Number(Number::Rational(n, 1)) => {
+                let power = u32::try_from(n.unsigned_abs()).or(Err(SymErr::Inconvertible))?;
+                if n >= 0 {
+                    Ok((an.pow(power), ad.pow(power)))
+                } else {
+                    Ok((ad.pow(power), an.pow(power)))
+                }
+            }
+            _ => Err(SymErr::Inconvertible),
+        };
+    }
+
+    let (bn, bd) = fraction(engine, &next[1], var)?;
+    match o.value {
+        Operator::Add => Ok((an * bd.clone() + bn * ad.clone(), ad * bd)),
+        Operator::Sub => Ok((an * bd.clone() - bn * ad.clone(), ad * bd)),
+        Operator::Mul => Ok((an * bn, ad * bd)),
+        Operator::Div => Ok((an * bd, ad * bn)),
+        _ => Err(SymErr::Inconvertible),
+    }
+}
+
+fn evaluate(polynomial: &Polynomial, x: f64) -> f64 {
+    polynomial
+        .coefficients()
+        .iter()
+        .rev()
+        .fold(0.0, |acc, c| acc * x + f64::from(c.clone()))
+}
+
+/// Solves the inequality `expr` (`lhs < rhs`, `lhs <= rhs`, `lhs > rhs` or `lhs >= rhs`) for
+/// `var` over the reals, `lhs - rhs` has to be a rational function of `var`.
+/// The sign of `lhs - rhs` is constant between the real roots of its numerator and denominator,
+/// so one point per region decides which regions belong to the solution.
+pub fn solve(engine: &Engine, expr: &Expr, var: &str) -> Result<Set, SymErr> {
+    let (oper, lhs, rhs) = match expr {
+        Expr::Operator(Tree {
+            value,
+            next: Some(next),
+        }) if value.is_relation() && *value != Operator::Eq => (*value, &next[0], &next[1]),
+        _ => return Err(SymErr::InvalidOP),
+    };
+    let holds = |value: f64| match oper {
+        Operator::Lt => value < 0.0,
+        Operator::Le => value <= 0.0,
+        Operator::Gt => value > 0.0,
+        _ => value >= 0.0,
+    };
+
+    let (numerator, denominator) =
+        fraction(engine, &(lhs.as_ref().clone() - rhs.as_ref().clone()), var)?;
+    if denominator.is_zero() {
+        return Err(SymErr::Undefined);
+    }
+
+    // critical points, poles are never part of the solution
+    let mut points = real_roots(denominator.clone())?
+        .into_iter()
+        .map(|(x, e)| (x, e, true))
+        .collect::<Vec<_>>();
+    for (x, e) in real_roots(numerator.clone())? {
+        points.push((x, e, false));
+    }
+    points.sort_by(|a, b| a.0.total_cmp(&b.0).then(b.2.cmp(&a.2)));
+    points.dedup_by(|b, a| (a.0 - b.0).abs() <= 1e-9 * a.0.abs().max(1.0));
+
+    let value = |x: f64| evaluate(&numerator, x) / evaluate(&denominator, x);
+    let sample = |region: usize| match (region.checked_sub(1), points.get(region)) {
+        (None, None) => 0.0,
+        (None, Some(right)) => right.0 - 1.0,
+        (Some(left), None) => points[left].0 + 1.0,
+        (Some(left), Some(right)) => 0.5 * (points[left].0 + right.0),
+    };
+
+    // walk left to right over the regions and the points between them
+    let mut intervals = Vec::new();
+    let mut lower = None;
+    for region in 0..=points.len() {
+        if holds(value(sample(region))) {
+            if lower.is_none() {
+                lower = Some(match region.checked_sub(1) {
+                    None => Endpoint::Infinite,
+                    Some(left) => Endpoint::Open(points[left].1.clone()),
+                });
+            }
+        } else if let Some(lower) = lower.take() {
+            let left = points[region - 1].1.clone();
+            intervals.push(Interval::new(lower, Endpoint::Closed(left)));
+        }
+
+        if let Some((_, x, pole)) = points.get(region) {
+            if !pole && holds(0.0) {
+                if lower.is_none() {
+                    lower = Some(Endpoint::Closed(x.clone()));
+                }
+            } else if let Some(lower) = lower.take() {
+                intervals.push(Interval::new(lower, Endpoint::Open(x.clone())));
+            }
+        }
+    }
+    if let Some(lower) = lower {
+        intervals.push(Interval::new(lower, Endpoint::Infinite));
+    }
+
+    let set = Set { intervals };
+    if engine.debugging {
+        println!("Solving {} for {}: {}", expr, var, set);
+    }
+
+    Ok(set)
+}
@@ -1,24 +1,64 @@
 use std::{
     borrow::Borrow,
     collections::HashMap,
+    convert::TryFrom,
     fmt::Display,
     ops::{self, Rem},
 };
 
-use eval::eval_tree;
-use simplifier::Simplifier;
-
+use complex::Complex;
+use eval::{eval_tree, eval_tree_with, eval_tree_with_trace};
+use multivariate::{MonomialOrder, MultiPolynomial};
+use nsolve::{Convergence, Guess};
+use polynomial::Polynomial;
+use rational::Conditional;
+use roots::NumericRoot;
+use set::Set;
+use simplifier::{Budget, Simplifier};
+use solver::Solution;
+use trace::Trace;
+
+mod bigint;
+mod build;
+pub mod complex;
 pub mod constants;
+pub mod derivative;
+pub mod egraph;
 pub mod eval;
+pub mod expand;
+pub mod factor;
 pub mod functions;
+pub mod groebner;
+pub mod inequality;
+pub mod multivariate;
+pub mod nsolve;
 pub mod parse;
+pub mod polynomial;
+pub mod rational;
+pub mod roots;
+pub mod set;
 pub mod simplifier;
+pub mod solver;
+pub mod sturm;
+pub mod trace;
+pub mod trig;
+
+pub type Function = fn(&Engine, &[Box<Expr>]) -> Result<Expr, SymErr>;
+pub type Inverse = fn(Expr) -> Expr;
+pub type Derivative = fn(Expr) -> Expr;
+/// values of variables used by [`Expr::eval_with`]
+pub type Bindings<'a> = HashMap<&'a str, Expr>;
 
-type FnMap<'a> = HashMap<&'a str, (u8, fn(&Engine, &Vec<Box<Expr>>) -> Result<Expr, SymErr>)>;
+type FnMap<'a> = HashMap<&'a str, (u8, Function)>;
+type InvMap<'a> = HashMap<&'a str, Inverse>;
+type DerivMap<'a> = HashMap<&'a str, Derivative>;
 
 pub struct Engine<'a> {
     functions: FnMap<'a>,
+    inverses: InvMap<'a>,
+    derivatives: DerivMap<'a>,
     simplifier: simplifier::Simplifier,
+    budget: Budget,
     debugging: bool,
 }
 
@@ -35,6 +75,12 @@ pub enum SymErr {
     Inconvertible,
     Undefined,
     LeftoverSymbols,
+    Unsolvable,
+    InvalidBracket,
+    NotDivisible,
+    InvalidRule,
+    Unreadable,
+    Overflow,
 }
 
 #[derive(Debug, PartialEq, Clone, Copy)]
@@ -45,7 +91,12 @@ pub enum Operator {
     Sub, // -
     Mul, // *
     Div, // /
-    Pow, // ^ (TODO: or **)
+    Pow, // ^ or **
+    Eq,  // =
+    Lt,  // <
+    Le,  // <=
+    Gt,  // >
+    Ge,  // >=
     LPa, // (
     RPa, // )
 }
@@ -76,14 +127,47 @@ pub struct Tree<T, U> {
     next: Option<Vec<Box<U>>>,
 }
 
+/// What a rule identifier can match
+#[derive(PartialEq, Eq, Debug, Clone, Copy, Hash)]
+pub enum IdKind {
+    Any,
+    /// only number literals
+    Number,
+    /// only variables
+    Symbol,
+}
+
+/// Identifiers with the same number but a different kind are different identifiers
 #[derive(PartialEq, Eq, Debug, Clone, Hash)]
 pub struct ID {
     id: u32,
+    kind: IdKind,
 }
 
 impl ID {
     pub fn new(id: u32) -> Self {
-        Self { id }
+        Self {
+            id,
+            kind: IdKind::Any,
+        }
+    }
+
+    pub fn number(id: u32) -> Self {
+        Self {
+            id,
+            kind: IdKind::Number,
+        }
+    }
+
+    pub fn symbol(id: u32) -> Self {
+        Self {
+            id,
+            kind: IdKind::Symbol,
+        }
+    }
+
+    pub fn kind(&self) -> IdKind {
+        self.kind
     }
 }
 
@@ -118,7 +202,7 @@ impl Display for Symbol {
             Symbol::Number(n) => write!(fmt, "{}", n),
             Symbol::Variable(s) => write!(fmt, "{}", s),
             Symbol::Function(s) => write!(fmt, "{}()", s),
-            Symbol::Operator(Operator::Neg) => write!(fmt, "{}", '-'),
+            Symbol::Operator(Operator::Neg) => write!(fmt, "-"),
             Symbol::Operator(o) => write!(fmt, "{}", o.to()),
         }
     }
@@ -126,44 +210,169 @@ impl Display for Symbol {
 
 impl Expr {
     pub fn parse(engine: &Engine, infix_string: &str) -> Result<Expr, SymErr> {
-        Ok(parse::postfix_to_tree(
-            &engine,
+        parse::postfix_to_tree(
+            engine,
             &parse::to_postfix(engine, &parse::parse_infix(engine, infix_string)?)?,
-        )?)
+        )
     }
 
     pub fn func(function: &str, arguments: Vec<Expr>) -> Result<Expr, SymErr> {
         Ok(Expr::Function(Tree {
             value: String::from(function),
-            next: Some(
-                arguments
-                    .into_iter()
-                    .map(|arg| Box::new(arg))
-                    .collect::<Vec<_>>(),
-            ),
+            next: Some(arguments.into_iter().map(Box::new).collect::<Vec<_>>()),
         }))
     }
 
+    /// see [`Expr::simplify_with_conditions`] for where the result equals `self`
     pub fn simplify(&self, engine: &Engine) -> Expr {
-        engine.simplifier.simplify(engine, &self)
+        self.simplify_with_conditions(engine).value
+    }
+
+    /// [`Expr::simplify`] step by step, cancelling a quotient is a step `cancel` with the
+    /// conditions of [`Expr::simplify_with_conditions`]
+    pub fn simplify_with_trace(&self, engine: &Engine) -> Trace {
+        engine.simplifier.simplify_cancelling(engine, self).1
+    }
+
+    /// Applies the simplification rules and cancels the common factors of the quotients of
+    /// polynomials in between. Cancelling drops the points where `self` is undefined, those are
+    /// excluded by the conditions.
+    pub fn simplify_with_conditions(&self, engine: &Engine) -> Conditional {
+        let (value, trace) = engine.simplifier.simplify_cancelling(engine, self);
+        let mut conditions = Vec::new();
+        for condition in trace.steps.into_iter().flat_map(|step| step.conditions) {
+            if !conditions.contains(&condition) {
+                conditions.push(condition);
+            }
+        }
+        Conditional { value, conditions }
     }
 
     pub fn eval(&self, engine: &Engine) -> Result<Self, SymErr> {
-        eval_tree(engine, &self)
+        eval_tree(engine, self)
+    }
+
+    pub fn eval_with(&self, engine: &Engine, bindings: &Bindings) -> Result<Self, SymErr> {
+        eval_tree_with(engine, self, bindings)
+    }
+
+    /// [`Expr::eval_with`] step by step, the result is the result of the trace
+    pub fn eval_with_trace(&self, engine: &Engine, bindings: &Bindings) -> Result<Trace, SymErr> {
+        eval_tree_with_trace(engine, self, bindings)
+    }
+
+    /// Rewrites with the trigonometric identities until the shortest form, see
+    /// [`Simplifier::trig`]
+    pub fn trigsimp(&self, engine: &Engine) -> Expr {
+        trig::trigsimp(engine, self)
+    }
+
+    /// sines and cosines of sums and natural multiples of angles written with the ones of the
+    /// single angles and multiplied out, like `cos(x)*sin(y)+cos(y)*sin(x)` for `sin(x+y)`
+    pub fn expand_trig(&self, engine: &Engine) -> Expr {
+        trig::expand_trig(engine, self)
+    }
+
+    /// sines, cosines and tangents written with powers of `e`, like `(e^(ⅈ*x)+e^(-ⅈ*x))/2` for
+    /// `cos(x)`
+    pub fn to_exponential(&self, engine: &Engine) -> Expr {
+        trig::to_exponential(engine, self)
+    }
+
+    /// the opposite of [`Expr::to_exponential`], powers of `e` that are not part of a sine,
+    /// cosine or tangent are written with Euler's formula
+    pub fn from_exponential(&self, engine: &Engine) -> Expr {
+        trig::from_exponential(engine, self)
+    }
+
+    pub fn derivative(&self, var: &str, engine: &Engine) -> Result<Expr, SymErr> {
+        derivative::derivative(engine, self, var)
+    }
+
+    pub fn solve(&self, var: &str, engine: &Engine) -> Result<Vec<Solution>, SymErr> {
+        solver::solve(engine, self, var)
+    }
+
+    pub fn solve_inequality(&self, var: &str, engine: &Engine) -> Result<Set, SymErr> {
+        inequality::solve(engine, self, var)
+    }
+
+    pub fn to_polynomial(&self, var: &str, engine: &Engine) -> Result<Polynomial, SymErr> {
+        Polynomial::from_expr(engine, self, var)
+    }
+
+    pub fn to_multivariate(
+        &self,
+        vars: &[&str],
+        order: MonomialOrder,
+        engine: &Engine,
+    ) -> Result<MultiPolynomial, SymErr> {
+        MultiPolynomial::from_expr(engine, self, vars, order)
+    }
+
+    /// products and natural powers multiplied out, see [`expand::expand`]
+    pub fn expand(&self) -> Expr {
+        expand::expand(self, false)
+    }
+
+    /// like [`Expr::expand`], and inside function arguments as well
+    pub fn expand_all(&self) -> Expr {
+        expand::expand(self, true)
+    }
+
+    /// the terms grouped by powers of `var`, see [`rational::collect`]
+    pub fn collect(&self, var: &str) -> Expr {
+        rational::collect(self, var)
+    }
+
+    /// over a common denominator, see [`rational::together`]
+    pub fn together(&self) -> Expr {
+        rational::together(self)
+    }
+
+    /// the common factors of numerator and denominator cancelled, see [`rational::cancel`]
+    pub fn cancel(&self, engine: &Engine) -> Result<Conditional, SymErr> {
+        rational::cancel(engine, self)
+    }
+
+    /// partial fractions in `var`, see [`rational::apart`]
+    pub fn apart(&self, var: &str, engine: &Engine) -> Result<Expr, SymErr> {
+        rational::apart(engine, self, var)
+    }
+
+    /// irreducible factors over the integers, see [`factor::factor`]
+    pub fn factor(&self, engine: &Engine) -> Result<Expr, SymErr> {
+        factor::factor(engine, self)
+    }
+
+    pub fn roots(&self, var: &str, engine: &Engine) -> Result<Vec<Expr>, SymErr> {
+        roots::roots(engine, self, var)
+    }
+
+    pub fn nroots(&self, var: &str, engine: &Engine) -> Result<Vec<NumericRoot>, SymErr> {
+        roots::numeric_roots(engine, self, var)
+    }
+
+    pub fn nsolve(&self, var: &str, guess: Guess, engine: &Engine) -> Result<Convergence, SymErr> {
+        nsolve::nsolve(engine, self, var, guess)
+    }
+
+    pub fn approximate(&self) -> Option<Complex> {
+        complex::approximate(self)
     }
 
     pub fn print(&self) -> String {
-        parse::tree_to_infix(&self)
+        parse::tree_to_infix(self)
     }
 
     pub fn print_latex(&self) -> String {
-        parse::tree_to_latex(&self)
+        parse::tree_to_latex(self)
     }
 
     pub fn print_debug(&self) -> String {
         match &self {
             Expr::Number(n) => format!("{}", n),
-            Expr::Variable(s) => format!("{}", s),
+            Expr::Variable(s) => s.to_string(),
             Expr::Function(f) => {
                 let mut l = String::new();
                 f.next.as_ref().unwrap().iter().for_each(|e| {
@@ -177,8 +386,8 @@ impl Expr {
                 )
             }
             Expr::Operator(o) => match o.value {
-                Operator::Pos => format!("-({})", o.next.as_ref().unwrap()[0]),
-                Operator::Neg => format!("-({})", o.next.as_ref().unwrap()[0]),
+                Operator::Pos => format!("+({})", o.next.as_ref().unwrap()[0].print_debug()),
+                Operator::Neg => format!("-({})", o.next.as_ref().unwrap()[0].print_debug()),
                 _ => format!(
                     "{} -> [ {}, {} ]",
                     o.value.to(),
@@ -340,10 +549,10 @@ impl ops::Add for Number {
     fn add(self, rhs: Self) -> Self::Output {
         match (self, rhs) {
             (Number::Rational(left_nom, left_denom), Number::Rational(right_nom, right_denom)) => {
-                let nom = left_nom * right_denom + right_nom * left_denom;
-                let denom = left_denom * right_denom;
-                let gcf = Number::gcf(nom, denom);
-                Number::Rational(nom / gcf, denom / gcf)
+                let nom =
+                    left_nom as i128 * right_denom as i128 + right_nom as i128 * left_denom as i128;
+                let denom = left_denom as i128 * right_denom as i128;
+                Number::rational(nom, denom)
             }
             (lhs, rhs) => {
                 let lhs: f64 = lhs.into();
@@ -360,10 +569,10 @@ impl ops::Sub for Number {
     fn sub(self, rhs: Self) -> Self::Output {
         match (self, rhs) {
             (Number::Rational(left_nom, left_denom), Number::Rational(right_nom, right_denom)) => {
-                let nom = left_nom * right_denom - right_nom * left_denom;
-                let denom = left_denom * right_denom;
-                let gcf = Number::gcf(nom, denom);
-                Number::Rational(nom / gcf, denom / gcf)
+                let nom =
+                    left_nom as i128 * right_denom as i128 - right_nom as i128 * left_denom as i128;
+                let denom = left_denom as i128 * right_denom as i128;
+                Number::rational(nom, denom)
             }
             (lhs, rhs) => {
                 let lhs: f64 = lhs.into();
@@ -379,7 +588,7 @@ impl ops::Neg for Number {
 
     fn neg(self) -> Self::Output {
         match self {
-            Number::Rational(nom, denom) => Number::Rational(-nom, denom),
+            Number::Rational(nom, denom) => Number::rational(-(nom as i128), denom as i128),
             Number::Irrational(f) => Number::Irrational(-f),
         }
     }
@@ -391,10 +600,9 @@ impl ops::Mul for Number {
     fn mul(self, rhs: Self) -> Self::Output {
         match (self, rhs) {
             (Number::Rational(left_nom, left_denom), Number::Rational(right_nom, right_denom)) => {
-                let nom = left_nom * right_nom;
-                let denom = left_denom * right_denom;
-                let gcf = Number::gcf(nom, denom);
-                Number::Rational(nom / gcf, denom / gcf)
+                let nom = left_nom as i128 * right_nom as i128;
+                let denom = left_denom as i128 * right_denom as i128;
+                Number::rational(nom, denom)
             }
             (lhs, rhs) => {
                 let lhs: f64 = lhs.into();
@@ -411,10 +619,9 @@ impl ops::Div for Number {
     fn div(self, rhs: Self) -> Self::Output {
         match (self, rhs) {
             (Number::Rational(left_nom, left_denom), Number::Rational(right_nom, right_denom)) => {
-                let nom = left_nom * right_denom;
-                let denom = left_denom * right_nom;
-                let gcf = Number::gcf(nom, denom);
-                Number::Rational(nom / gcf, denom / gcf)
+                let nom = left_nom as i128 * right_denom as i128;
+                let denom = left_denom as i128 * right_nom as i128;
+                Number::rational(nom, denom)
             }
             (lhs, rhs) => {
                 let lhs: f64 = lhs.into();
@@ -442,32 +649,80 @@ impl Number {
     pub fn pow(self, rhs: Self) -> Self {
         match (self, rhs) {
             (Number::Rational(left_nom, left_denom), Number::Rational(right_nom, right_denom)) => {
-                if right_denom == 1 && right_nom >= 0 {
-                    let nom = left_nom.pow(right_nom as u32);
-                    let denom = left_denom.pow(right_nom as u32);
-                    let gcf = Number::gcf(nom, denom);
-                    Number::Rational(nom / gcf, denom / gcf)
-                } else if right_denom == 1 {
-                    let nom = left_nom.pow((-right_nom) as u32);
-                    let denom = left_denom.pow((-right_nom) as u32);
-                    let gcf = Number::gcf(nom, denom);
-                    Number::Rational(denom / gcf, nom / gcf)
+                let exp = right_nom.unsigned_abs() as u32;
+                let (nom, denom) = if right_nom >= 0 {
+                    (left_nom.checked_pow(exp), left_denom.checked_pow(exp))
                 } else {
-                    // rationals
-                    let lhs: f64 = (left_nom as f64) / (left_denom as f64);
-                    let rhs: f64 = (right_nom as f64) / (right_denom as f64);
-                    Number::Irrational(lhs / rhs)
+                    (left_denom.checked_pow(exp), left_nom.checked_pow(exp))
+                };
+
+                match (nom, denom) {
+                    (Some(nom), Some(denom)) => {
+                        Number::rational(nom as i128, denom as i128).root(right_denom)
+                    }
+                    _ => {
+                        let lhs: f64 = Number::Rational(left_nom, left_denom).into();
+                        let rhs: f64 = Number::Rational(right_nom, right_denom).into();
+                        Number::Irrational(lhs.powf(rhs))
+                    }
                 }
             }
             (lhs, rhs) => {
                 let lhs: f64 = lhs.into();
                 let rhs: f64 = rhs.into();
-                Number::Irrational(lhs / rhs)
+                Number::Irrational(lhs.powf(rhs))
+            }
+        }
+    }
+
+    /// n:th root, exact if both the nominator and the denominator are perfect powers
+    pub fn root(self, n: i64) -> Self {
+        match self {
+            _ if n == 1 => self,
+            Number::Rational(nom, denom) if nom < 0 && n % 2 != 0 => {
+                -Number::Rational(-nom, denom).root(n)
+            }
+            Number::Rational(nom, denom) if nom >= 0 => {
+                match (Number::integer_root(nom, n), Number::integer_root(denom, n)) {
+                    (Some(nom), Some(denom)) => Number::Rational(nom, denom),
+                    _ => Number::Irrational((nom as f64 / denom as f64).powf(1.0 / n as f64)),
+                }
             }
+            Number::Irrational(f) if f < 0.0 && n % 2 != 0 => {
+                Number::Irrational(-(-f).powf(1.0 / n as f64))
+            }
+            other => {
+                let f: f64 = other.into();
+                Number::Irrational(f.powf(1.0 / n as f64))
+            }
+        }
+    }
+
+    fn integer_root(value: i64, n: i64) -> Option<i64> {
+        if n <= 0 || value < 0 {
+            return None;
         }
+
+        let guess = (value as f64).powf(1.0 / n as f64).round() as i64;
+        (guess.saturating_sub(1)..=guess.saturating_add(1))
+            .find(|r| *r >= 0 && r.checked_pow(n as u32) == Some(value))
     }
 
-    fn gcf(lhs: i64, rhs: i64) -> i64 {
+    /// reduced rational with a positive denominator, falls back to an irrational if it does not fit
+    fn rational(nom: i128, denom: i128) -> Self {
+        if denom == 0 {
+            return Number::Irrational(nom as f64 / 0.0);
+        }
+
+        let gcf = Number::gcf(nom, denom).abs() * denom.signum();
+        let (nom, denom) = (nom / gcf, denom / gcf);
+        match (i64::try_from(nom), i64::try_from(denom)) {
+            (Ok(nom), Ok(denom)) => Number::Rational(nom, denom),
+            _ => Number::Irrational(nom as f64 / denom as f64),
+        }
+    }
+
+    fn gcf(lhs: i128, rhs: i128) -> i128 {
         if rhs == 0 {
             lhs
         } else {
@@ -486,6 +741,9 @@ impl Expr {
             Operator::Mul => Ok(self * rhs.ok_or(SymErr::InvalidFunctionArgCount)?),
             Operator::Div => Ok(self / rhs.ok_or(SymErr::InvalidFunctionArgCount)?),
             Operator::Pow => Ok(self.pow_eval(rhs.ok_or(SymErr::InvalidFunctionArgCount)?)),
+            Operator::Eq | Operator::Lt | Operator::Le | Operator::Gt | Operator::Ge => Ok(
+                Expr::relation(oper, self, rhs.ok_or(SymErr::InvalidFunctionArgCount)?),
+            ),
             _ => Err(SymErr::InvalidOP),
         }
     }
@@ -498,7 +756,7 @@ impl Expr {
         }
 
         Expr::Operator(Tree {
-            value: Operator::Mul,
+            value: Operator::Pow,
             next: Some(vec![Box::new(self), Box::new(exp)]),
         })
     }
@@ -510,25 +768,58 @@ impl Expr {
         })
     }
 
+    pub fn equation(lhs: Self, rhs: Self) -> Expr {
+        Expr::relation(Operator::Eq, lhs, rhs)
+    }
+
+    /// `lhs = rhs`, `lhs < rhs`, ...
+    pub fn relation(oper: Operator, lhs: Self, rhs: Self) -> Expr {
+        Expr::Operator(Tree {
+            value: oper,
+            next: Some(vec![Box::new(lhs), Box::new(rhs)]),
+        })
+    }
+
+    /// does `var` appear anywhere in this expression
+    pub fn contains(&self, var: &str) -> bool {
+        match self {
+            Expr::Variable(v) => v == var,
+            Expr::Function(Tree { next, .. }) | Expr::Operator(Tree { next, .. }) => next
+                .as_ref()
+                .is_some_and(|next| next.iter().any(|e| e.contains(var))),
+            _ => false,
+        }
+    }
+
+    /// the variables in the order they first appear, without the constants `e` and `i`
+    pub fn variables(&self) -> Vec<String> {
+        let mut vars = Vec::new();
+        self.collect_variables(&mut vars);
+        vars
+    }
+
+    fn collect_variables(&self, vars: &mut Vec<String>) {
+        match self {
+            Expr::Variable(v) if v != constants::E && v != constants::I && !vars.contains(v) => {
+                vars.push(v.clone())
+            }
+            Expr::Function(Tree { next, .. }) | Expr::Operator(Tree { next, .. }) => next
+                .iter()
+                .flatten()
+                .for_each(|e| e.collect_variables(vars)),
+            _ => {}
+        }
+    }
+
     pub fn function<S>(name: S, exp: Vec<Self>) -> Expr
     where
         S: Into<String>,
     {
         Expr::Function(Tree {
             value: name.into(),
-            next: Some(exp.into_iter().map(|e| Box::new(e)).collect::<Vec<_>>()),
+            next: Some(exp.into_iter().map(Box::new).collect::<Vec<_>>()),
         })
     }
-
-    fn ty(&self) -> i64 {
-        match self {
-            Expr::Function(_) => 0,
-            Expr::Identifier(_) => 1,
-            Expr::Number(_) => 2,
-            Expr::Operator(_) => 3,
-            Expr::Variable(_) => 4,
-        }
-    }
 }
 
 impl Operator {
@@ -536,6 +827,18 @@ impl Operator {
         *self == Operator::LPa || *self == Operator::RPa
     }
 
+    pub fn is_unary(&self) -> bool {
+        *self == Operator::Pos || *self == Operator::Neg
+    }
+
+    /// `=`, `<`, `<=`, `>` or `>=`
+    pub fn is_relation(&self) -> bool {
+        matches!(
+            self,
+            Operator::Eq | Operator::Lt | Operator::Le | Operator::Gt | Operator::Ge
+        )
+    }
+
     pub fn precedence(&self) -> Result<u8, SymErr> {
         match self {
             Operator::Pos => Ok(4),
@@ -545,6 +848,7 @@ impl Operator {
             Operator::Div => Ok(3),
             Operator::Mul => Ok(3),
             Operator::Pow => Ok(5),
+            Operator::Eq | Operator::Lt | Operator::Le | Operator::Gt | Operator::Ge => Ok(1),
             _ => Err(SymErr::InvalidOP),
         }
     }
@@ -552,7 +856,7 @@ impl Operator {
     pub fn associativity(&self) -> Result<Associativity, SymErr> {
         match self {
             Operator::LPa | Operator::RPa => Err(SymErr::InvalidOP),
-            Operator::Pow => Ok(Associativity::Right),
+            Operator::Pow | Operator::Pos | Operator::Neg => Ok(Associativity::Right),
             _ => Ok(Associativity::Left),
         }
     }
@@ -566,6 +870,11 @@ impl Operator {
             Operator::Div => '/',
             Operator::Mul => '*',
             Operator::Pow => '^',
+            Operator::Eq => '=',
+            Operator::Lt => '<',
+            Operator::Le => '≤',
+            Operator::Gt => '>',
+            Operator::Ge => '≥',
             Operator::LPa => '(',
             Operator::RPa => ')',
         }
@@ -578,14 +887,34 @@ impl Operator {
             '*' => Ok(Operator::Mul),
             '/' => Ok(Operator::Div),
             '^' => Ok(Operator::Pow),
+            '=' => Ok(Operator::Eq),
+            '<' => Ok(Operator::Lt),
+            '≤' => Ok(Operator::Le),
+            '>' => Ok(Operator::Gt),
+            '≥' => Ok(Operator::Ge),
             '(' => Ok(Operator::LPa),
             ')' => Ok(Operator::RPa),
             _ => Err(SymErr::InvalidOP),
         }
     }
 
+    /// how the operator is written in infix, `<=` and `>=` are two characters long
+    pub fn symbol(&self) -> String {
+        match self {
+            Operator::Le => String::from("<="),
+            Operator::Ge => String::from(">="),
+            _ => self.to().to_string(),
+        }
+    }
+
     pub fn is_operator(c: char) -> bool {
-        c == '+' || c == '-' || c == '*' || c == '/' || c == '^' || c == '(' || c == ')'
+        Operator::from(c).is_ok()
+    }
+}
+
+impl<'a> Default for Engine<'a> {
+    fn default() -> Self {
+        Self::new()
     }
 }
 
@@ -593,7 +922,10 @@ impl<'a> Engine<'a> {
     pub fn new() -> Self {
         Self {
             functions: HashMap::new(),
+            inverses: HashMap::new(),
+            derivatives: HashMap::new(),
             simplifier: Simplifier::new(),
+            budget: Budget::default(),
             debugging: false,
         }
     }
@@ -603,8 +935,47 @@ impl<'a> Engine<'a> {
         self
     }
 
+    /// simplifies with `simplifier` instead of [`Simplifier::new`]
+    pub fn with_simplifier(mut self, simplifier: Simplifier) -> Self {
+        self.simplifier = simplifier;
+        self
+    }
+
+    pub fn simplifier(&self) -> &Simplifier {
+        &self.simplifier
+    }
+
+    /// how long simplifying may take, [`Budget::default`] if not set
+    pub fn with_budget(mut self, budget: Budget) -> Self {
+        self.budget = budget;
+        self
+    }
+
+    pub fn budget(&self) -> Budget {
+        self.budget
+    }
+
     pub fn with_functions(mut self) -> Self {
         functions::all(&mut self.functions);
+        functions::inverses(&mut self.inverses);
+        functions::derivatives(&mut self.derivatives);
+        self
+    }
+
+    pub fn with_function(mut self, name: &'a str, argc: u8, function: Function) -> Self {
+        self.functions.insert(name, (argc, function));
+        self
+    }
+
+    /// `inverse(y)` is the value `x` for which `name(x) = y`, used by the solver
+    pub fn with_inverse(mut self, name: &'a str, inverse: Inverse) -> Self {
+        self.inverses.insert(name, inverse);
+        self
+    }
+
+    /// `derivative(u)` is `name'(u)`, the chain rule is applied by [`Expr::derivative`]
+    pub fn with_derivative(mut self, name: &'a str, derivative: Derivative) -> Self {
+        self.derivatives.insert(name, derivative);
         self
     }
 }
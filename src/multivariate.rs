@@ -0,0 +1,646 @@
+use std::{cmp::Ordering, convert::TryFrom, fmt::Display, ops};
+
+use crate::{
+    build::{difference, is_negative, is_zero, product, sum},
+    polynomial::Polynomial,
+    Engine, Expr, Number, Operator, SymErr,
+};
+
+/// Exponent of every variable, in the order of [`MultiPolynomial::vars`]
+pub type Monomial = Vec<u32>;
+
+/// How the terms of a [`MultiPolynomial`] are sorted, the earlier variables are the larger ones
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum MonomialOrder {
+    /// lexicographic, `x > y^5`
+    Lex,
+    /// total degree first, then lexicographic
+    GrLex,
+    /// total degree first, then the smaller exponent of the last differing variable wins
+    GrevLex,
+}
+
+impl MonomialOrder {
+    pub fn compare(&self, a: &[u32], b: &[u32]) -> Ordering {
+        let degree = |m: &[u32]| m.iter().map(|&e| e as u64).sum::<u64>();
+        let lex = || a.cmp(b);
+
+        match self {
+            MonomialOrder::Lex => lex(),
+            MonomialOrder::GrLex => degree(a).cmp(&degree(b)).then_with(lex),
+            MonomialOrder::GrevLex => degree(a).cmp(&degree(b)).then_with(|| {
+                a.iter()
+                    .zip(b)
+                    .rev()
+                    .find(|(a, b)| a != b)
+                    .map_or(Ordering::Equal, |(a, b)| b.cmp(a))
+            }),
+        }
+    }
+}
+
+/// Sparse polynomial in several variables with numeric coefficients.
+/// The terms are always sorted by the monomial order from largest to smallest, with like terms
+/// combined and without zero coefficients, so equal polynomials have equal terms.
+#[derive(Debug, PartialEq, Clone)]
+pub struct MultiPolynomial {
+    vars: Vec<String>,
+    order: MonomialOrder,
+    terms: Vec<(Monomial, Number)>,
+}
+
+impl MultiPolynomial {
+    /// the zero polynomial
+    pub fn new(vars: Vec<String>, order: MonomialOrder) -> Self {
+        Self {
+            vars,
+            order,
+            terms: Vec::new(),
+        }
+    }
+
+    /// like terms are combined, every monomial needs an exponent for each variable
+    pub fn from_terms(
+        vars: Vec<String>,
+        order: MonomialOrder,
+        terms: Vec<(Monomial, Number)>,
+    ) -> Self {
+        let mut polynomial = Self::new(vars, order);
+        polynomial.terms = terms;
+        polynomial.normalize();
+        polynomial
+    }
+
+    pub fn constant(vars: Vec<String>, order: MonomialOrder, c: Number) -> Self {
+        let monomial = vec![0; vars.len()];
+        Self::from_terms(vars, order, vec![(monomial, c)])
+    }
+
+    /// the polynomial `var`, which has to be one of `vars`
+    pub fn var(vars: Vec<String>, order: MonomialOrder, var: &str) -> Result<Self, SymErr> {
+        let mut monomial = vec![0; vars.len()];
+        let index = vars
+            .iter()
+            .position(|v| v == var)
+            .ok_or(SymErr::Inconvertible)?;
+        monomial[index] = 1;
+        Ok(Self::from_terms(
+            vars,
+            order,
+            vec![(monomial, Number::Rational(1, 1))],
+        ))
+    }
+
+    fn normalize(&mut self) {
+        let order = self.order;
+        self.terms.sort_by(|a, b| order.compare(&b.0, &a.0));
+
+        let mut terms: Vec<(Monomial, Number)> = Vec::with_capacity(self.terms.len());
+        for (monomial, c) in self.terms.drain(..) {
+            match terms.last_mut() {
+                Some(last) if last.0 == monomial => last.1 = last.1.clone() + c,
+                _ => terms.push((monomial, c)),
+            }
+        }
+        terms.retain(|(_, c)| !is_zero(c));
+        self.terms = terms;
+    }
+
+    pub fn vars(&self) -> &[String] {
+        &self.vars
+    }
+
+    pub fn order(&self) -> MonomialOrder {
+        self.order
+    }
+
+    /// largest first
+    pub fn terms(&self) -> &[(Monomial, Number)] {
+        &self.terms
+    }
+
+    /// the same polynomial with its terms sorted by `order`
+    pub fn with_order(&self, order: MonomialOrder) -> Self {
+        Self::from_terms(self.vars.clone(), order, self.terms.clone())
+    }
+
+    /// the same polynomial in terms of `vars`, which has to contain every variable that appears
+    pub fn with_vars(&self, vars: &[String]) -> Result<Self, SymErr> {
+        let mut terms = Vec::with_capacity(self.terms.len());
+        for (monomial, c) in &self.terms {
+            let mut mapped = vec![0; vars.len()];
+            for (var, &e) in self.vars.iter().zip(monomial) {
+                if e != 0 {
+                    let index = vars
+                        .iter()
+                        .position(|v| v == var)
+                        .ok_or(SymErr::Inconvertible)?;
+                    mapped[index] = e;
+                }
+            }
+            terms.push((mapped, c.clone()));
+        }
+        Ok(Self::from_terms(vars.to_vec(), self.order, terms))
+    }
+
+    /// both polynomials in terms of the variables of either
+    fn unify(self, rhs: Self) -> (Self, Self) {
+        if self.vars == rhs.vars {
+            return (self, rhs);
+        }
+
+        let mut vars = self.vars.clone();
+        for var in &rhs.vars {
+            if !vars.contains(var) {
+                vars.push(var.clone());
+            }
+        }
+        (
+            self.with_vars(&vars).unwrap(),
+            rhs.with_vars(&vars).unwrap().with_order(self.order),
+        )
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.terms.is_empty()
+    }
+
+    pub fn is_constant(&self) -> bool {
+        self.terms
+            .iter()
+            .all(|(monomial, _)| monomial.iter().all(|&e| e == 0))
+    }
+
+    /// the largest term by the monomial order, `None` for the zero polynomial
+    pub fn leading_term(&self) -> Option<&(Monomial, Number)> {
+        self.terms.first()
+    }
+
+    pub fn leading_monomial(&self) -> Option<&Monomial> {
+        self.leading_term().map(|(monomial, _)| monomial)
+    }
+
+    /// `0` for the zero polynomial
+    pub fn leading_coefficient(&self) -> Number {
+        self.leading_term()
+            .map_or(Number::Rational(0, 1), |(_, c)| c.clone())
+    }
+
+    /// `None` for the zero polynomial
+    pub fn total_degree(&self) -> Option<u32> {
+        self.terms
+            .iter()
+            .map(|(monomial, _)| monomial.iter().sum())
+            .max()
+    }
+
+    /// the highest exponent of `var`, `None` for the zero polynomial
+    pub fn degree(&self, var: &str) -> Option<u32> {
+        let index = self.vars.iter().position(|v| v == var);
+        self.terms
+            .iter()
+            .map(|(monomial, _)| index.map_or(0, |i| monomial[i]))
+            .max()
+    }
+
+    pub fn scale(&self, factor: Number) -> Self {
+        Self::from_terms(
+            self.vars.clone(),
+            self.order,
+            self.terms
+                .iter()
+                .map(|(monomial, c)| (monomial.clone(), c.clone() * factor.clone()))
+                .collect(),
+        )
+    }
+
+    pub fn pow(&self, n: u32) -> Self {
+        let one = Self::constant(self.vars.clone(), self.order, Number::Rational(1, 1));
+        (0..n).fold(one, |acc, _| acc * self.clone())
+    }
+
+    /// `values` has one number for each variable
+    pub fn evaluate(&self, values: &[Number]) -> Number {
+        self.terms
+            .iter()
+            .fold(Number::Rational(0, 1), |acc, (monomial, c)| {
+                let term = monomial
+                    .iter()
+                    .zip(values)
+                    .fold(c.clone(), |term, (&e, x)| {
+                        term * x.clone().pow(Number::Rational(e as i64, 1))
+                    });
+                acc + term
+            })
+    }
+
+    /// divided by the leading coefficient
+    pub fn monic(&self) -> Self {
+        match self.leading_term() {
+            Some((_, c)) => self.scale(Number::Rational(1, 1) / c.clone()),
+            None => self.clone(),
+        }
+    }
+
+    /// `c` times the monomial, in the variables of `self`
+    fn term(&self, monomial: Monomial, c: Number) -> Self {
+        Self::from_terms(self.vars.clone(), self.order, vec![(monomial, c)])
+    }
+
+    fn position(&self, var: &str) -> Option<usize> {
+        self.vars.iter().position(|v| v == var)
+    }
+
+    /// the highest exponent of the variable at `index`, `None` for the zero polynomial
+    fn degree_at(&self, index: usize) -> Option<u32> {
+        self.terms.iter().map(|(monomial, _)| monomial[index]).max()
+    }
+
+    /// the coefficient of the `n`th power of the variable at `index`
+    fn coefficient_at(&self, index: usize, n: u32) -> Self {
+        let terms = self
+            .terms
+            .iter()
+            .filter(|(monomial, _)| monomial[index] == n)
+            .map(|(monomial, c)| {
+                let mut monomial = monomial.clone();
+                monomial[index] = 0;
+                (monomial, c.clone())
+            })
+            .collect();
+        Self::from_terms(self.vars.clone(), self.order, terms)
+    }
+
+    /// the `n`th power of the variable at `index`
+    fn power_at(&self, index: usize, n: u32) -> Self {
+        let mut monomial = vec![0; self.vars.len()];
+        monomial[index] = n;
+        self.term(monomial, Number::Rational(1, 1))
+    }
+
+    /// the coefficients of the powers of `var`, lowest first, which are polynomials in the other
+    /// variables
+    pub fn coefficients_in(&self, var: &str) -> Vec<Self> {
+        match (self.position(var), self.degree(var)) {
+            (Some(index), Some(degree)) => (0..=degree)
+                .map(|n| self.coefficient_at(index, n))
+                .collect(),
+            (None, Some(_)) => vec![self.clone()],
+            (_, None) => Vec::new(),
+        }
+    }
+
+    pub fn derivative(&self, var: &str) -> Self {
+        let index = match self.position(var) {
+            Some(index) => index,
+            None => return Self::new(self.vars.clone(), self.order),
+        };
+        let terms = self
+            .terms
+            .iter()
+            .filter(|(monomial, _)| monomial[index] != 0)
+            .map(|(monomial, c)| {
+                let mut monomial = monomial.clone();
+                let e = monomial[index];
+                monomial[index] -= 1;
+                (monomial, c.clone() * Number::Rational(e as i64, 1))
+            })
+            .collect();
+        Self::from_terms(self.vars.clone(), self.order, terms)
+    }
+
+    /// whether every coefficient is rational
+    pub fn is_exact(&self) -> bool {
+        self.terms
+            .iter()
+            .all(|(_, c)| matches!(c, Number::Rational(..)))
+    }
+
+    /// `self / divisor` if it leaves no remainder, [`SymErr::NotDivisible`] otherwise,
+    /// [`SymErr::Undefined`] when dividing by zero and [`SymErr::Overflow`] if rational
+    /// coefficients do not stay rational
+    pub fn exact_div(&self, divisor: &Self) -> Result<Self, SymErr> {
+        let (mut remainder, divisor) = self.clone().unify(divisor.clone());
+        let (lm, lc) = divisor.leading_term().cloned().ok_or(SymErr::Undefined)?;
+        let exact = remainder.is_exact() && divisor.is_exact();
+
+        // if the divisor divides, its leading term divides the leading term of what is left
+        let mut quotient = Self::new(remainder.vars.clone(), remainder.order);
+        while let Some((monomial, c)) = remainder.leading_term().cloned() {
+            if monomial.iter().zip(&lm).any(|(a, b)| a < b) {
+                return Err(SymErr::NotDivisible);
+            }
+            let monomial = monomial.iter().zip(&lm).map(|(a, b)| a - b).collect();
+            let term = remainder.term(monomial, c / lc.clone());
+            remainder = remainder - term.clone() * divisor.clone();
+            quotient = quotient + term;
+            if exact && !(remainder.is_exact() && quotient.is_exact()) {
+                return Err(SymErr::Overflow);
+            }
+        }
+        Ok(quotient)
+    }
+
+    /// `lc^k * self` reduced by `divisor` as polynomials in the variable at `index`, until the
+    /// degree drops below that of `divisor`
+    fn pseudo_remainder(&self, divisor: &Self, index: usize) -> Self {
+        let d = divisor.degree_at(index).unwrap_or(0);
+        let lc = divisor.coefficient_at(index, d);
+
+        let mut remainder = self.clone();
+        while let Some(n) = remainder.degree_at(index).filter(|&n| n >= d) {
+            let factor = remainder.coefficient_at(index, n) * remainder.power_at(index, n - d);
+            remainder = lc.clone() * remainder - factor * divisor.clone();
+        }
+        remainder
+    }
+
+    /// the gcd of the coefficients in the variable at `index`
+    fn content(&self, index: usize) -> Result<Self, SymErr> {
+        let degree = self.degree_at(index).unwrap_or(0);
+        (0..=degree).try_fold(Self::new(self.vars.clone(), self.order), |acc, n| {
+            acc.gcd(&self.coefficient_at(index, n))
+        })
+    }
+
+    fn primitive_part(&self, index: usize) -> Result<Self, SymErr> {
+        Ok(self.exact_div(&self.content(index)?)?.monic())
+    }
+
+    /// Monic greatest common divisor, zero if both are zero.
+    /// Recursive over the variables: the gcd of the contents in the first variable that appears
+    /// times the gcd of the primitive parts, found with a primitive remainder sequence.
+    /// [`SymErr::Overflow`] if rational coefficients do not stay rational on the way.
+    pub fn gcd(&self, other: &Self) -> Result<Self, SymErr> {
+        let (a, b) = self.clone().unify(other.clone());
+        let exact = a.is_exact() && b.is_exact();
+        if a.is_zero() {
+            return Ok(b.monic());
+        }
+        if b.is_zero() {
+            return Ok(a.monic());
+        }
+
+        let index = (0..a.vars.len()).find(|&i| {
+            a.terms
+                .iter()
+                .chain(&b.terms)
+                .any(|(monomial, _)| monomial[i] != 0)
+        });
+        let index = match index {
+            Some(index) => index,
+            None => return Ok(a.constant_one()),
+        };
+
+        let (ca, cb) = (a.content(index)?, b.content(index)?);
+        let content = ca.gcd(&cb)?;
+        let mut p = a.exact_div(&ca)?;
+        let mut q = b.exact_div(&cb)?;
+        if p.degree_at(index) < q.degree_at(index) {
+            std::mem::swap(&mut p, &mut q);
+        }
+        while !q.is_zero() {
+            let remainder = p.pseudo_remainder(&q, index);
+            if exact && !remainder.is_exact() {
+                return Err(SymErr::Overflow);
+            }
+            p = q;
+            q = if remainder.is_zero() {
+                remainder
+            } else {
+                remainder.primitive_part(index)?
+            };
+        }
+
+        Ok((content * p.primitive_part(index)?).monic())
+    }
+
+    fn constant_one(&self) -> Self {
+        Self::constant(self.vars.clone(), self.order, Number::Rational(1, 1))
+    }
+
+    /// Eliminates `var`: the determinant of the sylvester matrix of both as polynomials in
+    /// `var`, zero exactly when they have a common root in `var` for the other variables.
+    /// [`SymErr::Overflow`] if rational coefficients do not stay rational on the way.
+    pub fn resultant(&self, other: &Self, var: &str) -> Result<Self, SymErr> {
+        let (a, b) = self.clone().unify(other.clone());
+        let (ca, cb) = (a.coefficients_in(var), b.coefficients_in(var));
+        if ca.is_empty() || cb.is_empty() {
+            return Ok(Self::new(a.vars, a.order));
+        }
+
+        let (m, n) = (ca.len() - 1, cb.len() - 1);
+        let zero = Self::new(a.vars.clone(), a.order);
+        let mut matrix = vec![vec![zero; m + n]; m + n];
+        for i in 0..n {
+            for (j, c) in ca.iter().rev().enumerate() {
+                matrix[i][i + j] = c.clone();
+            }
+        }
+        for i in 0..m {
+            for (j, c) in cb.iter().rev().enumerate() {
+                matrix[n + i][i + j] = c.clone();
+            }
+        }
+        a.determinant(matrix)
+    }
+
+    /// fraction free gaussian elimination (bareiss), every division is exact
+    fn determinant(&self, mut matrix: Vec<Vec<Self>>) -> Result<Self, SymErr> {
+        let n = matrix.len();
+        let exact = matrix.iter().flatten().all(Self::is_exact);
+        let mut previous = self.constant_one();
+        let mut negate = false;
+        for k in 0..n {
+            if matrix[k][k].is_zero() {
+                match (k + 1..n).find(|&i| !matrix[i][k].is_zero()) {
+                    Some(i) => {
+                        matrix.swap(i, k);
+                        negate = !negate;
+                    }
+                    None => return Ok(Self::new(self.vars.clone(), self.order)),
+                }
+            }
+
+            for i in k + 1..n {
+                for j in k + 1..n {
+                    let value = matrix[i][j].clone() * matrix[k][k].clone()
+                        - matrix[i][k].clone() * matrix[k][j].clone();
+                    if exact && !value.is_exact() {
+                        return Err(SymErr::Overflow);
+                    }
+                    matrix[i][j] = value.exact_div(&previous)?;
+                }
+            }
+            previous = matrix[k][k].clone();
+        }
+
+        Ok(if negate { -previous } else { previous })
+    }
+
+    /// `(-1)^(n(n-1)/2) res(p, dp/dvar) / lc(p)` in `var`, zero exactly when there is a repeated
+    /// root. [`SymErr::Undefined`] if `var` does not appear.
+    pub fn discriminant(&self, var: &str) -> Result<Self, SymErr> {
+        let coefficients = self.coefficients_in(var);
+        let n = match coefficients.len() {
+            0 | 1 => return Err(SymErr::Undefined),
+            len => len - 1,
+        };
+
+        let resultant = self.resultant(&self.derivative(var), var)?;
+        let discriminant = resultant.exact_div(&coefficients[n])?;
+        Ok(if n * (n - 1) / 2 % 2 == 1 {
+            -discriminant
+        } else {
+            discriminant
+        })
+    }
+
+    /// a polynomial in `var` alone, the other variables must not appear
+    pub fn to_univariate(&self, var: &str) -> Result<Polynomial, SymErr> {
+        let index = self.vars.iter().position(|v| v == var);
+        let mut coefficients = Vec::new();
+        for (monomial, c) in &self.terms {
+            let e = index.map_or(0, |i| monomial[i]) as usize;
+            if monomial
+                .iter()
+                .enumerate()
+                .any(|(i, &e)| e != 0 && Some(i) != index)
+            {
+                return Err(SymErr::Inconvertible);
+            }
+
+            if coefficients.len() <= e {
+                coefficients.resize(e + 1, Number::Rational(0, 1));
+            }
+            coefficients[e] = c.clone();
+        }
+        Ok(Polynomial::new(coefficients))
+    }
+
+    /// Converts `expr` to a polynomial in `vars`, everything else has to evaluate to a number.
+    /// Gives [`SymErr::Inconvertible`] for anything that is not a polynomial, like `x/y`.
+    pub fn from_expr(
+        engine: &Engine,
+        expr: &Expr,
+        vars: &[&str],
+        order: MonomialOrder,
+    ) -> Result<Self, SymErr> {
+        let owned = vars.iter().map(|v| v.to_string()).collect::<Vec<_>>();
+        if !vars.iter().any(|var| expr.contains(var)) {
+            return match expr.eval(engine)? {
+                Expr::Number(n) => Ok(Self::constant(owned, order, n)),
+                _ => Err(SymErr::Inconvertible),
+            };
+        }
+
+        let o = match expr {
+            Expr::Variable(v) => return Self::var(owned, order, v),
+            Expr::Operator(o) => o,
+            _ => return Err(SymErr::Inconvertible),
+        };
+        let next = o.next.as_ref().unwrap();
+
+        let a = Self::from_expr(engine, &next[0], vars, order)?;
+        match o.value {
+            Operator::Pos => Ok(a),
+            Operator::Neg => Ok(-a),
+            Operator::Add => Ok(a + Self::from_expr(engine, &next[1], vars, order)?),
+            Operator::Sub => Ok(a - Self::from_expr(engine, &next[1], vars, order)?),
+            Operator::Mul => Ok(a * Self::from_expr(engine, &next[1], vars, order)?),
+            Operator::Div if !vars.iter().any(|var| next[1].contains(var)) => {
+                match next[1].eval(engine)? {
+                    Expr::Number(n) if !is_zero(&n) => Ok(a.scale(Number::Rational(1, 1) / n)),
+                    _ => Err(SymErr::Inconvertible),
+                }
+            }
+            Operator::Pow if !vars.iter().any(|var| next[1].contains(var)) => {
+                match next[1].eval(engine)? {
+                    Expr::Number(Number::Rational(n, 1)) => {
+                        Ok(a.pow(u32::try_from(n).or(Err(SymErr::Inconvertible))?))
+                    }
+                    _ => Err(SymErr::Inconvertible),
+                }
+            }
+            _ => Err(SymErr::Inconvertible),
+        }
+    }
+
+    /// the terms in the monomial order, like `x^2*y+3*x*y^2`
+    pub fn to_expr(&self) -> Expr {
+        let mut result: Option<Expr> = None;
+        for (monomial, c) in &self.terms {
+            let power = self
+                .vars
+                .iter()
+                .zip(monomial)
+                .filter(|(_, &e)| e != 0)
+                .map(|(var, &e)| match e {
+                    1 => Expr::from(var.as_str()),
+                    e => Expr::from(var.as_str()).pow(Expr::from(e as i64)),
+                })
+                .reduce(product)
+                .unwrap_or_else(|| Expr::from(1));
+
+            let term = |c: Number| product(Expr::from(c), power.clone());
+            result = Some(match result {
+                None => term(c.clone()),
+                Some(acc) if is_negative(c) => difference(acc, term(-c.clone())),
+                Some(acc) => sum(acc, term(c.clone())),
+            });
+        }
+
+        result.unwrap_or_else(|| Expr::from(0))
+    }
+}
+
+impl Display for MultiPolynomial {
+    fn fmt(&self, fmt: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(fmt, "{}", self.to_expr())
+    }
+}
+
+impl ops::Add for MultiPolynomial {
+    type Output = MultiPolynomial;
+
+    fn add(self, rhs: Self) -> Self::Output {
+        let (mut lhs, rhs) = self.unify(rhs);
+        lhs.terms.extend(rhs.terms);
+        lhs.normalize();
+        lhs
+    }
+}
+
+impl ops::Sub for MultiPolynomial {
+    type Output = MultiPolynomial;
+
+    fn sub(self, rhs: Self) -> Self::Output {
+        self + -rhs
+    }
+}
+
+impl ops::Neg for MultiPolynomial {
+    type Output = MultiPolynomial;
+
+    fn neg(mut self) -> Self::Output {
+        self.terms.iter_mut().for_each(|(_, c)| *c = -c.clone());
+        self
+    }
+}
+
+impl ops::Mul for MultiPolynomial {
+    type Output = MultiPolynomial;
+
+    fn mul(self, rhs: Self) -> Self::Output {
+        let (lhs, rhs) = self.unify(rhs);
+
+        let mut terms = Vec::with_capacity(lhs.terms.len() * rhs.terms.len());
+        for (a, ca) in &lhs.terms {
+            for (b, cb) in &rhs.terms {
+                let monomial = a.iter().zip(b).map(|(a, b)| a + b).collect();
+                terms.push((monomial, ca.clone() * cb.clone()));
+            }
+        }
+        MultiPolynomial::from_terms(lhs.vars, lhs.order, terms)
+    }
+}
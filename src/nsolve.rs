@@ -0,0 +1,756 @@
+use std::{f64::consts, fmt::Display};
+
+use crate::{derivative::derivative, eval::eval_tree_with, solver, Bindings, Engine, Expr, SymErr};
+
+/// Where the numeric search starts
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Guess {
+    Start(f64),
+    /// two points where the function has opposite signs
+    Bracket(f64, f64),
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Method {
+    /// Newton-Raphson with a symbolic derivative, falls back to bisection steps when given a
+    /// bracket and a step would leave it
+    Newton,
+    /// Newton-Raphson with the derivative approximated from the last two iterates
+    Secant,
+    Bisection,
+    /// Brent's method, inverse quadratic interpolation safeguarded by bisection
+    Brent,
+    /// Gauss-Newton damped towards gradient descent, minimizes the squared residuals
+    LevenbergMarquardt,
+}
+
+/// Diagnostics of a numeric solve
+#[derive(Debug, PartialEq, Clone)]
+pub struct Convergence {
+    pub root: f64,
+    /// the function value at `root`
+    pub residual: f64,
+    /// size of the last step or half the width of the last bracket
+    pub error: f64,
+    pub iterations: usize,
+    /// the iteration settled where the residual is below the square root of the tolerance, not
+    /// at a pole or a jump of the function
+    pub converged: bool,
+    pub method: Method,
+}
+
+/// Diagnostics of a numeric solve of a system of equations
+#[derive(Debug, PartialEq, Clone)]
+pub struct SystemConvergence {
+    pub solution: Vec<f64>,
+    /// the value of every equation at `solution`
+    pub residuals: Vec<f64>,
+    /// euclidean norm of `residuals`
+    pub residual: f64,
+    /// euclidean norm of the last step
+    pub error: f64,
+    pub iterations: usize,
+    /// the iteration settled where the residual is below the square root of the tolerance
+    pub converged: bool,
+    /// the iteration settled where the residual is larger, a (local) minimum of it like the least
+    /// squares solution of an overdetermined system
+    pub minimum: bool,
+    /// [`Method::LevenbergMarquardt`] if any step fell back to it
+    pub method: Method,
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct NSolver {
+    method: Option<Method>,
+    tolerance: f64,
+    max_iterations: usize,
+}
+
+impl Display for Method {
+    fn fmt(&self, fmt: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Method::Newton => write!(fmt, "Newton"),
+            Method::Secant => write!(fmt, "secant"),
+            Method::Bisection => write!(fmt, "bisection"),
+            Method::Brent => write!(fmt, "Brent"),
+            Method::LevenbergMarquardt => write!(fmt, "Levenberg-Marquardt"),
+        }
+    }
+}
+
+impl Display for Convergence {
+    fn fmt(&self, fmt: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            fmt,
+            "{} +- {} ({} after {} {} iterations, residual {})",
+            self.root,
+            self.error,
+            if self.converged {
+                "converged"
+            } else {
+                "did not converge"
+            },
+            self.iterations,
+            self.method,
+            self.residual
+        )
+    }
+}
+
+impl Display for SystemConvergence {
+    fn fmt(&self, fmt: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(fmt, "(")?;
+        for (i, x) in self.solution.iter().enumerate() {
+            write!(fmt, "{}{}", if i == 0 { "" } else { ", " }, x)?;
+        }
+        write!(
+            fmt,
+            ") +- {} ({} after {} {} iterations, residual {})",
+            self.error,
+            if self.converged {
+                "converged"
+            } else if self.minimum {
+                "settled in a minimum"
+            } else {
+                "did not converge"
+            },
+            self.iterations,
+            self.method,
+            self.residual
+        )
+    }
+}
+
+fn evaluate(engine: &Engine, expr: &Expr, vars: &[&str], x: &[f64]) -> Result<f64, SymErr> {
+    let mut bindings = Bindings::new();
+    bindings.insert(crate::constants::E, Expr::from(consts::E));
+    for (var, x) in vars.iter().zip(x) {
+        bindings.insert(var, Expr::from(*x));
+    }
+
+    match eval_tree_with(engine, expr, &bindings)? {
+        Expr::Number(n) => Ok(n.into()),
+        _ => Err(SymErr::NotANumber),
+    }
+}
+
+fn norm(v: &[f64]) -> f64 {
+    v.iter().map(|x| x * x).sum::<f64>().sqrt()
+}
+
+/// solves `a x = b` with gaussian elimination, `None` if `a` is singular
+fn linear_solve(mut a: Vec<Vec<f64>>, mut b: Vec<f64>) -> Option<Vec<f64>> {
+    let n = b.len();
+    let scale = a.iter().flatten().fold(0.0f64, |max, x| max.max(x.abs()));
+
+    for col in 0..n {
+        let pivot = (col..n).max_by(|&i, &j| a[i][col].abs().total_cmp(&a[j][col].abs()))?;
+        let magnitude = a[pivot][col].abs();
+        if magnitude.is_nan() || magnitude <= scale * n as f64 * f64::EPSILON {
+            return None;
+        }
+        a.swap(col, pivot);
+        b.swap(col, pivot);
+
+        let pivot_row = a[col].clone();
+        for row in col + 1..n {
+            let factor = a[row][col] / pivot_row[col];
+            for (x, p) in a[row][col..].iter_mut().zip(&pivot_row[col..]) {
+                *x -= factor * p;
+            }
+            b[row] -= factor * b[col];
+        }
+    }
+
+    let mut x = vec![0.0; n];
+    for row in (0..n).rev() {
+        let sum = (row + 1..n).map(|k| a[row][k] * x[k]).sum::<f64>();
+        x[row] = (b[row] - sum) / a[row][row];
+    }
+    Some(x)
+}
+
+/// `f(x)` as a float
+struct Function<'a> {
+    engine: &'a Engine<'a>,
+    expr: Expr,
+    var: &'a str,
+}
+
+impl<'a> Function<'a> {
+    fn at(&self, expr: &Expr, x: f64) -> Result<f64, SymErr> {
+        evaluate(self.engine, expr, &[self.var], &[x])
+    }
+
+    fn value(&self, x: f64) -> Result<f64, SymErr> {
+        self.at(&self.expr, x)
+    }
+}
+
+/// `F(x)` and its jacobian as floats
+struct System<'a> {
+    engine: &'a Engine<'a>,
+    exprs: Vec<Expr>,
+    vars: &'a [&'a str],
+    /// symbolic jacobian, `None` if some equation could not be differentiated
+    jacobian: Option<Vec<Vec<Expr>>>,
+}
+
+impl<'a> System<'a> {
+    fn new(engine: &'a Engine<'a>, equations: &[Expr], vars: &'a [&'a str]) -> Self {
+        let exprs = equations
+            .iter()
+            .map(|equation| {
+                let (lhs, rhs) = solver::sides(equation);
+                lhs - rhs
+            })
+            .collect::<Vec<_>>();
+
+        let jacobian = exprs
+            .iter()
+            .map(|expr| {
+                vars.iter()
+                    .map(|var| derivative(engine, expr, var))
+                    .collect::<Result<Vec<_>, _>>()
+            })
+            .collect::<Result<Vec<_>, _>>()
+            .ok();
+
+        Self {
+            engine,
+            exprs,
+            vars,
+            jacobian,
+        }
+    }
+
+    fn value(&self, x: &[f64]) -> Result<Vec<f64>, SymErr> {
+        self.exprs
+            .iter()
+            .map(|expr| evaluate(self.engine, expr, self.vars, x))
+            .collect()
+    }
+
+    /// falls back to forward differences without a symbolic jacobian
+    fn jacobian(&self, x: &[f64], fx: &[f64]) -> Result<Vec<Vec<f64>>, SymErr> {
+        if let Some(jacobian) = &self.jacobian {
+            return jacobian
+                .iter()
+                .map(|row| {
+                    row.iter()
+                        .map(|expr| evaluate(self.engine, expr, self.vars, x))
+                        .collect()
+                })
+                .collect();
+        }
+
+        let mut jacobian = vec![vec![0.0; x.len()]; fx.len()];
+        for j in 0..x.len() {
+            let h = f64::EPSILON.sqrt() * x[j].abs().max(1.0);
+            let mut shifted = x.to_vec();
+            shifted[j] += h;
+            for (i, f) in self.value(&shifted)?.into_iter().enumerate() {
+                jacobian[i][j] = (f - fx[i]) / h;
+            }
+        }
+        Ok(jacobian)
+    }
+}
+
+impl Default for NSolver {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl NSolver {
+    /// Newton from a starting point or Brent inside a bracket, until the step is below `1e-12`
+    /// relative to the root, for at most 100 iterations
+    pub fn new() -> Self {
+        Self {
+            method: None,
+            tolerance: 1e-12,
+            max_iterations: 100,
+        }
+    }
+
+    pub fn with_method(mut self, method: Method) -> Self {
+        self.method = Some(method);
+        self
+    }
+
+    /// stop once the step or bracket is smaller than `tolerance * max(|x|, 1)`
+    pub fn with_tolerance(mut self, tolerance: f64) -> Self {
+        self.tolerance = tolerance;
+        self
+    }
+
+    pub fn with_max_iterations(mut self, max_iterations: usize) -> Self {
+        self.max_iterations = max_iterations;
+        self
+    }
+
+    fn tolerance(&self, x: f64) -> f64 {
+        self.tolerance * x.abs().max(1.0)
+    }
+
+    /// Finds a root of `expr` in `var` numerically.
+    /// `expr` is either an equation `lhs = rhs` or an expression that is assumed to equal zero.
+    /// Bisection and Brent need a [`Guess::Bracket`] with a sign change, otherwise
+    /// [`SymErr::InvalidBracket`] is returned.
+    pub fn solve(
+        &self,
+        engine: &Engine,
+        expr: &Expr,
+        var: &str,
+        guess: Guess,
+    ) -> Result<Convergence, SymErr> {
+        let (lhs, rhs) = solver::sides(expr);
+        let f = Function {
+            engine,
+            expr: lhs - rhs,
+            var,
+        };
+
+        let method = self.method.unwrap_or(match guess {
+            Guess::Start(_) => Method::Newton,
+            Guess::Bracket(..) => Method::Brent,
+        });
+
+        let result = match (method, guess) {
+            (Method::Newton, guess) => match derivative(engine, &f.expr, var) {
+                Ok(df) => self.newton(&f, &df, guess),
+                Err(_) => self.secant(&f, guess),
+            },
+            (Method::Secant, guess) => self.secant(&f, guess),
+            (Method::Bisection, Guess::Bracket(a, b)) => self.bisection(&f, a, b),
+            (Method::Brent, Guess::Bracket(a, b)) => self.brent(&f, a, b),
+            (Method::LevenbergMarquardt, guess) => {
+                let start = match guess {
+                    Guess::Start(x) => x,
+                    Guess::Bracket(a, b) => 0.5 * (a + b),
+                };
+                let vars = [var];
+                let system = System::new(engine, std::slice::from_ref(&f.expr), &vars);
+                let result = self.system(&system, &[start], false)?;
+                Ok(Convergence {
+                    root: result.solution[0],
+                    residual: result.residuals[0],
+                    error: result.error,
+                    iterations: result.iterations,
+                    converged: result.converged,
+                    method: result.method,
+                })
+            }
+            (_, Guess::Start(_)) => Err(SymErr::InvalidBracket),
+        }?;
+
+        if engine.debugging {
+            println!("Numerically solving {} = 0 for {}: {}", f.expr, var, result);
+        }
+
+        Ok(result)
+    }
+
+    /// Solves the system `equations` for `vars` numerically, starting from `start`.
+    /// Every equation is either `lhs = rhs` or an expression that is assumed to equal zero.
+    /// Square systems are solved with Newton steps that are damped until the residual
+    /// decreases, falling back to Levenberg-Marquardt steps where that fails, over- and
+    /// underdetermined systems are solved in the least squares sense with Levenberg-Marquardt.
+    /// Only [`Method::Newton`] and [`Method::LevenbergMarquardt`] apply to systems.
+    pub fn solve_system(
+        &self,
+        engine: &Engine,
+        equations: &[Expr],
+        vars: &[&str],
+        start: &[f64],
+    ) -> Result<SystemConvergence, SymErr> {
+        if vars.len() != start.len() || equations.is_empty() {
+            return Err(SymErr::InvalidFunctionArgCount);
+        }
+
+        let newton = match self.method {
+            None | Some(Method::Newton) => equations.len() == vars.len(),
+            Some(Method::LevenbergMarquardt) => false,
+            Some(_) => return Err(SymErr::InvalidOP),
+        };
+
+        let system = System::new(engine, equations, vars);
+        let result = self.system(&system, start, newton)?;
+
+        if engine.debugging {
+            println!("Numerically solving system for {:?}: {}", vars, result);
+        }
+
+        Ok(result)
+    }
+
+    fn system(
+        &self,
+        system: &System,
+        start: &[f64],
+        newton: bool,
+    ) -> Result<SystemConvergence, SymErr> {
+        let n = start.len();
+        let mut method = if newton {
+            Method::Newton
+        } else {
+            Method::LevenbergMarquardt
+        };
+
+        let mut x = start.to_vec();
+        let mut fx = system.value(&x)?;
+        let mut residual = norm(&fx);
+        let mut damping = None;
+
+        let mut error = f64::INFINITY;
+        let mut iterations = self.max_iterations;
+        let mut stalled = false;
+        for iteration in 1..=self.max_iterations {
+            if residual == 0.0 {
+                error = 0.0;
+                iterations = iteration;
+                break;
+            }
+            let jacobian = system.jacobian(&x, &fx)?;
+
+            // newton step, halved until the residual decreases
+            let mut accepted = None;
+            let step = if newton {
+                linear_solve(jacobian.clone(), fx.iter().map(|f| -f).collect())
+            } else {
+                None
+            };
+            if let Some(step) = step {
+                let mut lambda = 1.0;
+                while lambda >= 1e-3 {
+                    let next = x
+                        .iter()
+                        .zip(&step)
+                        .map(|(x, dx)| x + lambda * dx)
+                        .collect::<Vec<_>>();
+                    let f_next = system.value(&next)?;
+                    let r_next = norm(&f_next);
+                    if r_next <= (1.0 - 1e-4 * lambda) * residual {
+                        accepted = Some((next, f_next, r_next));
+                        break;
+                    }
+                    lambda *= 0.5;
+                }
+            }
+
+            // levenberg-marquardt step (J^T J + mu I) dx = -J^T F
+            if accepted.is_none() {
+                method = Method::LevenbergMarquardt;
+
+                let mut normal = vec![vec![0.0; n]; n];
+                let mut gradient = vec![0.0; n];
+                for (row, f) in jacobian.iter().zip(&fx) {
+                    for j in 0..n {
+                        gradient[j] -= row[j] * f;
+                        for k in 0..n {
+                            normal[j][k] += row[j] * row[k];
+                        }
+                    }
+                }
+
+                let mut mu: f64 = damping.unwrap_or_else(|| {
+                    1e-3 * (0..n).fold(f64::MIN_POSITIVE, |max, j| max.max(normal[j][j]))
+                });
+                while mu.is_finite() && mu < 1e30 {
+                    let mut damped = normal.clone();
+                    (0..n).for_each(|j| damped[j][j] += mu);
+
+                    if let Some(step) = linear_solve(damped, gradient.clone()) {
+                        let next = x
+                            .iter()
+                            .zip(&step)
+                            .map(|(x, dx)| x + dx)
+                            .collect::<Vec<_>>();
+                        let f_next = system.value(&next)?;
+                        let r_next = norm(&f_next);
+                        if r_next < residual {
+                            damping = Some(mu / 10.0);
+                            accepted = Some((next, f_next, r_next));
+                            break;
+                        }
+                    }
+                    mu *= 10.0;
+                }
+            }
+
+            let (next, f_next, r_next) = match accepted {
+                Some(accepted) => accepted,
+                None => {
+                    // no step decreases the residual, a (local) minimum
+                    stalled = true;
+                    iterations = iteration;
+                    break;
+                }
+            };
+
+            error = norm(
+                &next
+                    .iter()
+                    .zip(&x)
+                    .map(|(next, x)| next - x)
+                    .collect::<Vec<_>>(),
+            );
+            x = next;
+            fx = f_next;
+            residual = r_next;
+            if error <= self.tolerance * norm(&x).max(1.0) {
+                iterations = iteration;
+                break;
+            }
+        }
+
+        let settled = stalled || residual == 0.0 || error <= self.tolerance * norm(&x).max(1.0);
+        let converged = settled && residual <= self.tolerance.sqrt();
+        Ok(SystemConvergence {
+            converged,
+            minimum: settled && !converged,
+            solution: x,
+            residuals: fx,
+            residual,
+            error,
+            iterations,
+            method,
+        })
+    }
+
+    /// the bracket with `f(a) <= 0 <= f(b)`
+    fn bracket(&self, f: &Function, a: f64, b: f64) -> Result<(f64, f64), SymErr> {
+        let (fa, fb) = (f.value(a)?, f.value(b)?);
+        if fa <= 0.0 && fb >= 0.0 {
+            Ok((a, b))
+        } else if fa >= 0.0 && fb <= 0.0 {
+            Ok((b, a))
+        } else {
+            Err(SymErr::InvalidBracket)
+        }
+    }
+
+    fn converge(
+        &self,
+        f: &Function,
+        method: Method,
+        root: f64,
+        error: f64,
+        iterations: usize,
+    ) -> Result<Convergence, SymErr> {
+        let residual = f.value(root)?;
+        let settled = residual == 0.0 || error <= self.tolerance(root);
+        Ok(Convergence {
+            root,
+            residual,
+            error,
+            iterations,
+            converged: settled && residual.abs() <= self.tolerance.sqrt(),
+            method,
+        })
+    }
+
+    fn newton(&self, f: &Function, df: &Expr, guess: Guess) -> Result<Convergence, SymErr> {
+        let mut bracket = match guess {
+            Guess::Start(_) => None,
+            Guess::Bracket(a, b) => Some(self.bracket(f, a, b)?),
+        };
+        let mut x = match guess {
+            Guess::Start(x) => x,
+            Guess::Bracket(a, b) => 0.5 * (a + b),
+        };
+
+        let mut error = f64::INFINITY;
+        for iteration in 1..=self.max_iterations {
+            let fx = f.value(x)?;
+            if fx == 0.0 {
+                return self.converge(f, Method::Newton, x, 0.0, iteration);
+            }
+
+            let mut next = x - fx / f.at(df, x)?;
+            if let Some((neg, pos)) = bracket.as_mut() {
+                if fx < 0.0 {
+                    *neg = x;
+                } else {
+                    *pos = x;
+                }
+                if !next.is_finite() || next < neg.min(*pos) || next > neg.max(*pos) {
+                    next = 0.5 * (*neg + *pos);
+                }
+            }
+
+            if !next.is_finite() {
+                break;
+            }
+
+            error = (next - x).abs();
+            x = next;
+            if error <= self.tolerance(x) {
+                return self.converge(f, Method::Newton, x, error, iteration);
+            }
+        }
+
+        self.converge(f, Method::Newton, x, error, self.max_iterations)
+    }
+
+    fn secant(&self, f: &Function, guess: Guess) -> Result<Convergence, SymErr> {
+        let (mut x0, mut x1) = match guess {
+            Guess::Start(x) => (x, x + f64::EPSILON.sqrt() * x.abs().max(1.0)),
+            Guess::Bracket(a, b) => (a, b),
+        };
+        let mut f0 = f.value(x0)?;
+
+        let mut error = (x1 - x0).abs();
+        for iteration in 1..=self.max_iterations {
+            let f1 = f.value(x1)?;
+            if f1 == 0.0 {
+                return self.converge(f, Method::Secant, x1, 0.0, iteration);
+            }
+
+            let next = x1 - f1 * (x1 - x0) / (f1 - f0);
+            if !next.is_finite() {
+                break;
+            }
+
+            error = (next - x1).abs();
+            x0 = x1;
+            f0 = f1;
+            x1 = next;
+            if error <= self.tolerance(x1) {
+                return self.converge(f, Method::Secant, x1, error, iteration);
+            }
+        }
+
+        self.converge(f, Method::Secant, x1, error, self.max_iterations)
+    }
+
+    fn bisection(&self, f: &Function, a: f64, b: f64) -> Result<Convergence, SymErr> {
+        let (mut neg, mut pos) = self.bracket(f, a, b)?;
+
+        let mut x = 0.5 * (neg + pos);
+        for iteration in 1..=self.max_iterations {
+            x = 0.5 * (neg + pos);
+            let error = 0.5 * (pos - neg).abs();
+            let fx = f.value(x)?;
+            if fx == 0.0 || error <= self.tolerance(x) {
+                return self.converge(f, Method::Bisection, x, error, iteration);
+            }
+
+            if fx < 0.0 {
+                neg = x;
+            } else {
+                pos = x;
+            }
+        }
+
+        self.converge(
+            f,
+            Method::Bisection,
+            x,
+            0.5 * (pos - neg).abs(),
+            self.max_iterations,
+        )
+    }
+
+    fn brent(&self, f: &Function, a: f64, b: f64) -> Result<Convergence, SymErr> {
+        self.bracket(f, a, b)?;
+
+        let (mut a, mut b, mut c) = (a, b, b);
+        let (mut fa, mut fb) = (f.value(a)?, f.value(b)?);
+        let mut fc = fb;
+        let (mut d, mut e) = (b - a, b - a);
+
+        let mut error = 0.5 * (b - a).abs();
+        for iteration in 1..=self.max_iterations {
+            if fb.signum() == fc.signum() {
+                // the root is between a and b
+                c = a;
+                fc = fa;
+                d = b - a;
+                e = d;
+            }
+            if fc.abs() < fb.abs() {
+                // b is the best guess so far
+                a = b;
+                b = c;
+                c = a;
+                fa = fb;
+                fb = fc;
+                fc = fa;
+            }
+
+            let tolerance = 2.0 * f64::EPSILON * b.abs() + 0.5 * self.tolerance(b);
+            let middle = 0.5 * (c - b);
+            error = middle.abs();
+            if fb == 0.0 {
+                return self.converge(f, Method::Brent, b, 0.0, iteration);
+            }
+            if error <= tolerance {
+                return self.converge(f, Method::Brent, b, error, iteration);
+            }
+
+            if e.abs() >= tolerance && fa.abs() > fb.abs() {
+                // secant or inverse quadratic interpolation
+                let s = fb / fa;
+                let (mut p, mut q) = if a == c {
+                    (2.0 * middle * s, 1.0 - s)
+                } else {
+                    let q = fa / fc;
+                    let r = fb / fc;
+                    (
+                        s * (2.0 * middle * q * (q - r) - (b - a) * (r - 1.0)),
+                        (q - 1.0) * (r - 1.0) * (s - 1.0),
+                    )
+                };
+                if p > 0.0 {
+                    q = -q;
+                }
+                p = p.abs();
+
+                let min = (3.0 * middle * q - (tolerance * q).abs()).min((e * q).abs());
+                if 2.0 * p < min {
+                    e = d;
+                    d = p / q;
+                } else {
+                    d = middle;
+                    e = d;
+                }
+            } else {
+                d = middle;
+                e = d;
+            }
+
+            a = b;
+            fa = fb;
+            b += if d.abs() > tolerance {
+                d
+            } else {
+                tolerance.copysign(middle)
+            };
+            fb = f.value(b)?;
+        }
+
+        self.converge(f, Method::Brent, b, error, self.max_iterations)
+    }
+}
+
+/// [`NSolver::solve`] with the default settings
+pub fn nsolve(
+    engine: &Engine,
+    expr: &Expr,
+    var: &str,
+    guess: Guess,
+) -> Result<Convergence, SymErr> {
+    NSolver::new().solve(engine, expr, var, guess)
+}
+
+/// [`NSolver::solve_system`] with the default settings
+pub fn nsolve_system(
+    engine: &Engine,
+    equations: &[Expr],
+    vars: &[&str],
+    start: &[f64],
+) -> Result<SystemConvergence, SymErr> {
+    NSolver::new().solve_system(engine, equations, vars, start)
+}
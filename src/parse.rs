@@ -2,9 +2,9 @@ use regex::{Captures, Regex};
 
 use crate::Number;
 
-use super::{Engine, Expr, Operator, SymErr, Symbol, Tree};
+use super::{Associativity, Engine, Expr, Operator, SymErr, Symbol, Tree};
 
-fn split_keep<'a>(str: &'a str) -> Vec<&'a str> {
+fn split_keep(str: &str) -> Vec<&str> {
     let mut result = Vec::new();
     let mut last = 0;
     for (index, matched) in str.match_indices(|c| Operator::is_operator(c) || c == ',') {
@@ -25,12 +25,19 @@ fn split_keep<'a>(str: &'a str) -> Vec<&'a str> {
 
 pub fn parse_infix(engine: &Engine, infix_string: &str) -> Result<Vec<Symbol>, SymErr> {
     let missing_multiply = Regex::new(r"\d[A-Za-z(]").unwrap();
-    let missing_multiply_result = missing_multiply.replace_all(infix_string, |caps: &Captures| {
-        let mut chars = (&caps[0]).chars();
+    let infix_string = infix_string
+        .chars()
+        .filter(|c| !c.is_whitespace())
+        .collect::<String>();
+    let missing_multiply_result = missing_multiply.replace_all(&infix_string, |caps: &Captures| {
+        let mut chars = caps[0].chars();
         format!("{}*{}", chars.next().unwrap(), chars.next().unwrap())
     });
     let python_power = Regex::new(r"\*\*").unwrap();
-    let python_power_result = python_power.replace_all(&missing_multiply_result, "^");
+    let python_power_result = python_power
+        .replace_all(&missing_multiply_result, "^")
+        .replace("<=", "≤")
+        .replace(">=", "≥");
 
     let infix_split = split_keep(&python_power_result);
 
@@ -38,7 +45,7 @@ pub fn parse_infix(engine: &Engine, infix_string: &str) -> Result<Vec<Symbol>, S
         println!("To infix: {}", infix_string);
     }
 
-    Ok(infix_split
+    infix_split
         .iter()
         .enumerate()
         .map(|(i, &to_parse)| {
@@ -46,9 +53,8 @@ pub fn parse_infix(engine: &Engine, infix_string: &str) -> Result<Vec<Symbol>, S
                 Ok(Symbol::Number(number))
             } else if let Ok(oper) = Operator::from(to_parse.chars().next().unwrap()) {
                 let is_sign = i == 0
-                    || infix_split[i - 1] == "("
-                    || infix_split[i - 1] == "-"
-                    || infix_split[i - 1] == "+";
+                    || (infix_split[i - 1] != ")"
+                        && Operator::from(infix_split[i - 1].chars().next().unwrap()).is_ok());
                 if is_sign && oper == Operator::Add {
                     Ok(Symbol::Operator(Operator::Pos))
                 } else if is_sign && oper == Operator::Sub {
@@ -66,7 +72,7 @@ pub fn parse_infix(engine: &Engine, infix_string: &str) -> Result<Vec<Symbol>, S
                 }
             }
         })
-        .collect::<Result<Vec<_>, _>>()?)
+        .collect::<Result<Vec<_>, _>>()
 }
 
 pub fn to_postfix(engine: &Engine, infix: &Vec<Symbol>) -> Result<Vec<Symbol>, SymErr> {
@@ -126,25 +132,27 @@ pub fn to_postfix(engine: &Engine, infix: &Vec<Symbol>) -> Result<Vec<Symbol>, S
                 }
                 false
             }
+            Symbol::Operator(oper) if oper.is_unary() => {
+                operator_stack.push(symbol.clone());
+                false
+            }
             Symbol::Operator(oper) => {
-                let mut operator = *oper;
+                let precedence = oper.precedence()?;
+                let associativity = oper.associativity()?;
 
-                loop {
-                    let top_symbol = operator_stack.pop();
-                    let top_operator = match top_symbol {
-                        Some(Symbol::Operator(oper)) => oper,
-                        _ => break,
-                    };
+                while let Some(Symbol::Operator(top_operator)) = operator_stack.last() {
+                    if top_operator.is_parenthesis() {
+                        break;
+                    }
 
-                    if top_operator.is_parenthesis()
-                        || top_operator.precedence().unwrap() <= operator.precedence().unwrap()
+                    let top_precedence = top_operator.precedence()?;
+                    if top_precedence < precedence
+                        || (top_precedence == precedence && associativity == Associativity::Right)
                     {
-                        operator_stack.push(top_symbol.unwrap());
                         break;
                     }
 
-                    operator = top_operator;
-                    postfix.push(Symbol::Operator(top_operator));
+                    postfix.push(operator_stack.pop().unwrap());
                 }
                 operator_stack.push(symbol.clone());
                 false
@@ -152,11 +160,8 @@ pub fn to_postfix(engine: &Engine, infix: &Vec<Symbol>) -> Result<Vec<Symbol>, S
         }
     }
 
-    loop {
-        match operator_stack.pop() {
-            Some(symbol) => postfix.push(symbol.clone()),
-            _ => break,
-        }
+    while let Some(symbol) = operator_stack.pop() {
+        postfix.push(symbol);
     }
 
     Ok(postfix)
@@ -184,7 +189,7 @@ pub fn postfix_to_tree(engine: &Engine, postfix: &Vec<Symbol>) -> Result<Expr, S
                 let b = mixed_stack.pop().ok_or(SymErr::StackEmpty)?;
 
                 mixed_stack.push(Expr::Operator(Tree {
-                    value: op.clone(),
+                    value: *op,
                     next: Some(vec![Box::new(b), Box::new(a)]),
                 }));
             }
@@ -217,7 +222,7 @@ pub fn postfix_to_tree(engine: &Engine, postfix: &Vec<Symbol>) -> Result<Expr, S
 
     if mixed_stack.len() > 1 {
         Err(SymErr::LeftoverSymbols)
-    } else if mixed_stack.len() == 0 {
+    } else if mixed_stack.is_empty() {
         Err(SymErr::StackEmpty)
     } else {
         Ok(mixed_stack.pop().unwrap())
@@ -232,68 +237,75 @@ enum Ordering {
 }
 
 impl Ordering {
-    pub fn new(left: u8, oper: u8, right: u8) -> Self {
-        // println!("orders {} {} {}", left, oper, right);
-        if left >= oper && oper <= right {
-            Self::Neither
-        } else if left >= oper {
-            Self::Right
-        } else if left < oper && oper <= right {
-            Self::Left
-        } else {
-            Self::Both
+    pub fn new(left: u8, oper: Operator, right: u8) -> Self {
+        let precedence = oper.precedence().unwrap();
+        let left_parens = left < precedence
+            || (left == precedence && oper.associativity() == Ok(Associativity::Right));
+        let right_parens = right < precedence
+            || (right == precedence && (oper == Operator::Sub || oper == Operator::Div));
+
+        match (left_parens, right_parens) {
+            (false, false) => Self::Neither,
+            (false, true) => Self::Right,
+            (true, false) => Self::Left,
+            (true, true) => Self::Both,
         }
     }
 }
 
+fn number_precedence(n: &Number) -> u8 {
+    if f64::from(n.clone()) < 0.0 {
+        Operator::Neg.precedence().unwrap()
+    } else {
+        u8::MAX
+    }
+}
+
 fn tree_to_infix_recurse(expr: &Expr) -> (String, u8) {
     match &expr {
         Expr::Function(f) => (
-            format!(
-                "{}({})",
-                f.value,
-                (|| {
-                    let mut l = String::new();
-                    f.next.as_ref().unwrap().iter().for_each(|e| {
-                        l = format!("{}, {}", l, tree_to_infix(e));
-                    });
-
-                    if l.is_empty() {
-                        l
-                    } else {
-                        String::from(&l[2..])
-                    }
-                })()
-            ),
+            format!("{}({})", f.value, {
+                let mut l = String::new();
+                f.next.as_ref().unwrap().iter().for_each(|e| {
+                    l = format!("{}, {}", l, tree_to_infix(e));
+                });
+
+                if l.is_empty() {
+                    l
+                } else {
+                    String::from(&l[2..])
+                }
+            }),
             u8::MAX,
         ),
         Expr::Operator(o) => match o.value {
-            Operator::Pos => (
-                format!(
-                    "-({})",
-                    tree_to_infix_recurse(&o.next.as_ref().unwrap()[0]).0
-                ),
-                4,
-            ),
-            Operator::Neg => (
-                format!("{}", tree_to_infix_recurse(&o.next.as_ref().unwrap()[0]).0),
-                4,
-            ),
+            Operator::Pos => tree_to_infix_recurse(&o.next.as_ref().unwrap()[0]),
+            Operator::Neg => {
+                let a = tree_to_infix_recurse(&o.next.as_ref().unwrap()[0]);
+                let c = o.value.precedence().unwrap();
+                if a.1 < c {
+                    (format!("-({})", a.0), c)
+                } else {
+                    (format!("-{}", a.0), c)
+                }
+            }
             _ => {
                 let a = tree_to_infix_recurse(&o.next.as_ref().unwrap()[0]);
                 let b = tree_to_infix_recurse(&o.next.as_ref().unwrap()[1]);
                 let c = o.value.precedence().unwrap();
 
-                match Ordering::new(a.1, c, b.1) {
-                    Ordering::Neither => (format!("{}{}{}", a.0, o.value.to(), b.0), c),
-                    Ordering::Right => (format!("{}{}({})", a.0, o.value.to(), b.0), c),
-                    Ordering::Left => (format!("({}){}{}", a.0, o.value.to(), b.0), c),
-                    Ordering::Both => (format!("({}){}({})", a.0, o.value.to(), b.0), c),
+                let symbol = o.value.symbol();
+
+                match Ordering::new(a.1, o.value, b.1) {
+                    Ordering::Neither => (format!("{}{}{}", a.0, symbol, b.0), c),
+                    Ordering::Right => (format!("{}{}({})", a.0, symbol, b.0), c),
+                    Ordering::Left => (format!("({}){}{}", a.0, symbol, b.0), c),
+                    Ordering::Both => (format!("({}){}({})", a.0, symbol, b.0), c),
                 }
             }
         },
-        Expr::Variable(v) => (format!("{}", v), u8::MAX),
-        Expr::Number(n) => (format!("{}", n), u8::MAX),
+        Expr::Variable(v) => (v.to_string(), u8::MAX),
+        Expr::Number(n) => (format!("{}", n), number_precedence(n)),
         Expr::Identifier(i) => (format!("i:{}", i.id), u8::MAX),
     }
 }
@@ -305,36 +317,31 @@ pub fn tree_to_infix(expr: &Expr) -> String {
 fn tree_to_latex_recurse(expr: &Expr) -> (String, u8) {
     match &expr {
         Expr::Function(f) => (
-            format!(
-                "\\{}\\left({}\\right)",
-                f.value,
-                (|| {
-                    let mut l = String::new();
-                    f.next.as_ref().unwrap().iter().for_each(|e| {
-                        l = format!("{}, {}", l, tree_to_latex_recurse(e).0);
-                    });
-
-                    if l.is_empty() {
-                        l
-                    } else {
-                        String::from(&l[2..])
-                    }
-                })()
-            ),
+            format!("\\{}\\left({}\\right)", f.value, {
+                let mut l = String::new();
+                f.next.as_ref().unwrap().iter().for_each(|e| {
+                    l = format!("{}, {}", l, tree_to_latex_recurse(e).0);
+                });
+
+                if l.is_empty() {
+                    l
+                } else {
+                    String::from(&l[2..])
+                }
+            }),
             u8::MAX,
         ),
         Expr::Operator(o) => match o.value {
-            Operator::Pos => (
-                format!(
-                    "-({})",
-                    tree_to_latex_recurse(&o.next.as_ref().unwrap()[0]).0
-                ),
-                4,
-            ),
-            Operator::Neg => (
-                format!("{}", tree_to_latex_recurse(&o.next.as_ref().unwrap()[0]).0),
-                4,
-            ),
+            Operator::Pos => tree_to_latex_recurse(&o.next.as_ref().unwrap()[0]),
+            Operator::Neg => {
+                let a = tree_to_latex_recurse(&o.next.as_ref().unwrap()[0]);
+                let c = o.value.precedence().unwrap();
+                if a.1 < c {
+                    (format!("-\\left({}\\right)", a.0), c)
+                } else {
+                    (format!("-{}", a.0), c)
+                }
+            }
             _ => {
                 let a = tree_to_latex_recurse(&o.next.as_ref().unwrap()[0]);
                 let b = tree_to_latex_recurse(&o.next.as_ref().unwrap()[1]);
@@ -342,39 +349,47 @@ fn tree_to_latex_recurse(expr: &Expr) -> (String, u8) {
 
                 match o.value {
                     Operator::Div => (format!("\\frac{{{}}}{{{}}}", a.0, b.0), c),
-                    Operator::Mul => match Ordering::new(a.1, c, b.1) {
+                    Operator::Mul => match Ordering::new(a.1, o.value, b.1) {
                         Ordering::Neither => (format!("{}\\cdot {}", a.0, b.0), c),
-                        Ordering::Right => (format!("{}\\cdot \\left({}\\left)", a.0, b.0), c),
-                        Ordering::Left => (format!("\\left({}\\left)\\cdot {}", a.0, b.0), c),
+                        Ordering::Right => (format!("{}\\cdot \\left({}\\right)", a.0, b.0), c),
+                        Ordering::Left => (format!("\\left({}\\right)\\cdot {}", a.0, b.0), c),
                         Ordering::Both => (
-                            format!("\\left({}\\left)\\cdot \\left({}\\left)", a.0, b.0),
+                            format!("\\left({}\\right)\\cdot \\left({}\\right)", a.0, b.0),
                             c,
                         ),
                     },
-                    Operator::Pow => match Ordering::new(a.1, c, b.1) {
-                        Ordering::Neither => (format!("{}^{}", a.0, b.0), c),
+                    Operator::Pow => match Ordering::new(a.1, o.value, b.1) {
+                        Ordering::Neither => (format!("{}^{{{}}}", a.0, b.0), c),
                         Ordering::Right => (format!("{}^{{{}}}", a.0, b.0), c),
-                        Ordering::Left => (format!("\\left({}\\left)^{}", a.0, b.0), c),
-                        Ordering::Both => (format!("\\left({}\\left)^{{{}}}", a.0, b.0), c),
+                        Ordering::Left => (format!("\\left({}\\right)^{{{}}}", a.0, b.0), c),
+                        Ordering::Both => (format!("\\left({}\\right)^{{{}}}", a.0, b.0), c),
                     },
-                    _ => match Ordering::new(a.1, c, b.1) {
-                        Ordering::Neither => (format!("{}{}{}", a.0, o.value.to(), b.0), c),
-                        Ordering::Right => {
-                            (format!("{}{}\\left({}\\left)", a.0, o.value.to(), b.0), c)
-                        }
-                        Ordering::Left => {
-                            (format!("\\left({}\\left){}{}", a.0, o.value.to(), b.0), c)
+                    _ => {
+                        let symbol = match o.value {
+                            Operator::Le => String::from("\\leq "),
+                            Operator::Ge => String::from("\\geq "),
+                            oper => oper.symbol(),
+                        };
+
+                        match Ordering::new(a.1, o.value, b.1) {
+                            Ordering::Neither => (format!("{}{}{}", a.0, symbol, b.0), c),
+                            Ordering::Right => {
+                                (format!("{}{}\\left({}\\right)", a.0, symbol, b.0), c)
+                            }
+                            Ordering::Left => {
+                                (format!("\\left({}\\right){}{}", a.0, symbol, b.0), c)
+                            }
+                            Ordering::Both => (
+                                format!("\\left({}\\right){}\\left({}\\right)", a.0, symbol, b.0),
+                                c,
+                            ),
                         }
-                        Ordering::Both => (
-                            format!("\\left({}\\left){}\\left({}\\left)", a.0, o.value.to(), b.0),
-                            c,
-                        ),
-                    },
+                    }
                 }
             }
         },
-        Expr::Variable(v) => (format!("{}", v), u8::MAX),
-        Expr::Number(n) => (format!("{}", n), u8::MAX),
+        Expr::Variable(v) => (v.to_string(), u8::MAX),
+        Expr::Number(n) => (format!("{}", n), number_precedence(n)),
         Expr::Identifier(i) => (format!("i:{}", i.id), u8::MAX),
     }
 }
@@ -0,0 +1,424 @@
+use std::{convert::TryFrom, fmt::Display, ops};
+
+use crate::{
+    bigint::BigInt,
+    build::{difference, is_negative, is_zero, product, sum},
+    sturm::{from_polynomial, primitive, trim, Zx},
+    Engine, Expr, Number, Operator, SymErr,
+};
+
+/// Dense polynomial in one variable, `coefficients[i]` belongs to `x^i`.
+/// Arithmetic stays exact as long as the coefficients are rational and fit `i64`, division and
+/// conversion give [`SymErr::Overflow`] once they would not.
+#[derive(Debug, PartialEq, Clone)]
+pub struct Polynomial {
+    /// never has a zero leading coefficient, the zero polynomial has none at all
+    coefficients: Vec<Number>,
+}
+
+fn zero() -> Number {
+    Number::Rational(0, 1)
+}
+
+fn power(x: &BigInt, n: usize) -> BigInt {
+    (0..n).fold(BigInt::from(1i64), |acc, _| &acc * x)
+}
+
+/// `lc(b)^(deg a - deg b + 1) * a` reduced by `b`, which leaves integer coefficients
+fn pseudo_remainder(a: &Zx, b: &Zx) -> Zx {
+    let lc = b.last().unwrap();
+    let mut steps = a.len() + 1 - b.len();
+    let mut r = a.clone();
+    while !r.is_empty() && r.len() >= b.len() {
+        let shift = r.len() - b.len();
+        let top = r.last().unwrap().clone();
+        r = r.iter().map(|c| c * lc).collect();
+        for (i, c) in b.iter().enumerate() {
+            r[i + shift] = &r[i + shift] - &(&top * c);
+        }
+        r = trim(r);
+        steps -= 1;
+    }
+    let rest = power(lc, steps);
+    r.iter().map(|c| c * &rest).collect()
+}
+
+impl Polynomial {
+    /// `coefficients` lowest degree first
+    pub fn new(mut coefficients: Vec<Number>) -> Self {
+        while coefficients.last().is_some_and(is_zero) {
+            coefficients.pop();
+        }
+        Self { coefficients }
+    }
+
+    pub fn zero() -> Self {
+        Self::new(Vec::new())
+    }
+
+    pub fn constant(c: Number) -> Self {
+        Self::new(vec![c])
+    }
+
+    /// `c * x^n`
+    pub fn monomial(c: Number, n: usize) -> Self {
+        let mut coefficients = vec![zero(); n];
+        coefficients.push(c);
+        Self::new(coefficients)
+    }
+
+    /// the polynomial `x`
+    pub fn x() -> Self {
+        Self::monomial(Number::Rational(1, 1), 1)
+    }
+
+    /// lowest degree first, empty for the zero polynomial
+    pub fn coefficients(&self) -> &[Number] {
+        &self.coefficients
+    }
+
+    /// the coefficient of `x^n`
+    pub fn coefficient(&self, n: usize) -> Number {
+        self.coefficients.get(n).cloned().unwrap_or_else(zero)
+    }
+
+    /// `0` for the zero polynomial
+    pub fn leading(&self) -> Number {
+        self.coefficients.last().cloned().unwrap_or_else(zero)
+    }
+
+    /// `None` for the zero polynomial
+    pub fn degree(&self) -> Option<usize> {
+        self.coefficients.len().checked_sub(1)
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.coefficients.is_empty()
+    }
+
+    pub fn is_constant(&self) -> bool {
+        self.coefficients.len() <= 1
+    }
+
+    /// whether every coefficient is rational
+    pub fn is_exact(&self) -> bool {
+        self.coefficients
+            .iter()
+            .all(|c| matches!(c, Number::Rational(..)))
+    }
+
+    pub fn scale(&self, factor: Number) -> Self {
+        Self::new(
+            self.coefficients
+                .iter()
+                .map(|c| c.clone() * factor.clone())
+                .collect(),
+        )
+    }
+
+    /// divided by the leading coefficient
+    pub fn monic(&self) -> Self {
+        match self.coefficients.last() {
+            Some(leading) => self.scale(Number::Rational(1, 1) / leading.clone()),
+            None => self.clone(),
+        }
+    }
+
+    pub fn pow(&self, n: u32) -> Self {
+        (0..n).fold(Self::constant(Number::Rational(1, 1)), |acc, _| {
+            acc * self.clone()
+        })
+    }
+
+    pub fn evaluate(&self, x: &Number) -> Number {
+        self.coefficients
+            .iter()
+            .rev()
+            .fold(zero(), |acc, c| acc * x.clone() + c.clone())
+    }
+
+    pub fn derivative(&self) -> Self {
+        Self::new(
+            self.coefficients
+                .iter()
+                .enumerate()
+                .skip(1)
+                .map(|(i, c)| c.clone() * Number::Rational(i as i64, 1))
+                .collect(),
+        )
+    }
+
+    /// `self(inner(x))`
+    pub fn compose(&self, inner: &Polynomial) -> Self {
+        self.coefficients.iter().rev().fold(Self::zero(), |acc, c| {
+            acc * inner.clone() + Self::constant(c.clone())
+        })
+    }
+
+    /// quotient and remainder of polynomial long division, [`SymErr::Undefined`] when dividing
+    /// by zero and [`SymErr::Overflow`] if rational coefficients do not stay rational
+    pub fn div_rem(&self, divisor: &Polynomial) -> Result<(Self, Self), SymErr> {
+        let d = divisor.degree().ok_or(SymErr::Undefined)?;
+        if self.coefficients.len() <= d {
+            return Ok((Self::zero(), self.clone()));
+        }
+
+        let leading = divisor.leading();
+        let mut remainder = self.coefficients.clone();
+        let mut quotient = vec![zero(); remainder.len() - d];
+        for i in (0..quotient.len()).rev() {
+            let factor = remainder[i + d].clone() / leading.clone();
+            for (j, c) in divisor.coefficients.iter().enumerate() {
+                remainder[i + j] = remainder[i + j].clone() - factor.clone() * c.clone();
+            }
+            quotient[i] = factor;
+        }
+        remainder.truncate(d);
+
+        let (quotient, remainder) = (Self::new(quotient), Self::new(remainder));
+        if self.is_exact() && divisor.is_exact() && !(quotient.is_exact() && remainder.is_exact()) {
+            return Err(SymErr::Overflow);
+        }
+        Ok((quotient, remainder))
+    }
+
+    /// `self / divisor` if it leaves no remainder, [`SymErr::NotDivisible`] otherwise
+    pub fn exact_div(&self, divisor: &Polynomial) -> Result<Self, SymErr> {
+        let (quotient, remainder) = self.div_rem(divisor)?;
+        if remainder.is_zero() {
+            Ok(quotient)
+        } else {
+            Err(SymErr::NotDivisible)
+        }
+    }
+
+    /// Monic greatest common divisor, zero if both are zero. The subresultant remainder
+    /// sequence divides out the known common factors of every remainder, so the integer
+    /// coefficients stay as small as the subresultants and no rational can overflow on the way.
+    /// [`SymErr::Inconvertible`] if the coefficients are not rational, [`SymErr::Overflow`] if
+    /// those of the monic gcd do not fit.
+    pub fn gcd(&self, other: &Polynomial) -> Result<Self, SymErr> {
+        let (mut a, mut b) = (from_polynomial(self)?, from_polynomial(other)?);
+        if a.len() < b.len() {
+            std::mem::swap(&mut a, &mut b);
+        }
+
+        let one = BigInt::from(1i64);
+        let (mut g, mut h) = (one.clone(), one.clone());
+        while !b.is_empty() {
+            let delta = a.len() - b.len();
+            let r = pseudo_remainder(&a, &b);
+            let divisor = &g * &power(&h, delta);
+            a = b;
+            b = r.iter().map(|c| c.div_rem(&divisor).0).collect();
+            g = a.last().unwrap().clone();
+            h = match delta {
+                0 => h,
+                _ => power(&g, delta).div_rem(&power(&h, delta - 1)).0,
+            };
+        }
+
+        let a = primitive(a);
+        let leading = match a.last() {
+            Some(leading) => leading.clone(),
+            None => return Ok(Self::zero()),
+        };
+        let coefficient = |c: &BigInt| {
+            let (n, d) = (c.to_i64(), leading.to_i64());
+            match (n, d) {
+                (Some(n), Some(d)) => Ok(Number::Rational(n, 1) / Number::Rational(d, 1)),
+                _ => Err(SymErr::Overflow),
+            }
+        };
+        Ok(Self::new(
+            a.iter().map(coefficient).collect::<Result<_, _>>()?,
+        ))
+    }
+
+    /// The product of `other` at every root of `self`, times the leading coefficients, zero
+    /// exactly when they have a common root. [`SymErr::Overflow`] if rational coefficients do
+    /// not stay rational on the way.
+    pub fn resultant(&self, other: &Polynomial) -> Result<Number, SymErr> {
+        let (m, n) = match (self.degree(), other.degree()) {
+            (Some(m), Some(n)) => (m, n),
+            _ => return Ok(zero()),
+        };
+        let exact = self.is_exact() && other.is_exact();
+        let checked = |n: Number| match n {
+            Number::Irrational(_) if exact => Err(SymErr::Overflow),
+            n => Ok(n),
+        };
+        if n == 0 {
+            return checked(other.leading().pow(Number::Rational(m as i64, 1)));
+        }
+        if m == 0 {
+            return checked(self.leading().pow(Number::Rational(n as i64, 1)));
+        }
+
+        // res(a, b) = (-1)^(mn) res(b, a) = (-1)^(mn) lc(b)^(m - deg r) res(b, r), r = a mod b
+        let remainder = self.div_rem(other)?.1;
+        if exact && !remainder.is_exact() {
+            return Err(SymErr::Overflow);
+        }
+        let r = match remainder.degree() {
+            Some(r) => r,
+            None => return Ok(zero()),
+        };
+        let sign = if m * n % 2 == 1 {
+            Number::Rational(-1, 1)
+        } else {
+            Number::Rational(1, 1)
+        };
+        checked(
+            sign * other.leading().pow(Number::Rational((m - r) as i64, 1))
+                * other.resultant(&remainder)?,
+        )
+    }
+
+    /// `(-1)^(n(n-1)/2) res(p, p') / lc(p)`, zero exactly when there is a repeated root
+    pub fn discriminant(&self) -> Result<Number, SymErr> {
+        let n = match self.degree() {
+            Some(n) if n > 0 => n,
+            _ => return Err(SymErr::Undefined),
+        };
+        let sign = if n * (n - 1) / 2 % 2 == 1 {
+            Number::Rational(-1, 1)
+        } else {
+            Number::Rational(1, 1)
+        };
+        Ok(sign * self.resultant(&self.derivative())? / self.leading())
+    }
+
+    /// Converts `expr` to a polynomial in `var`, everything else has to evaluate to a number.
+    /// Gives [`SymErr::Inconvertible`] for anything that is not a polynomial, like `1/x`, and
+    /// [`SymErr::Overflow`] if rational coefficients do not stay rational.
+    pub fn from_expr(engine: &Engine, expr: &Expr, var: &str) -> Result<Self, SymErr> {
+        if !expr.contains(var) {
+            return match expr.eval(engine)? {
+                Expr::Number(n) => Ok(Self::constant(n)),
+                _ => Err(SymErr::Inconvertible),
+            };
+        }
+
+        let o = match expr {
+            Expr::Variable(_) => return Ok(Self::x()),
+            Expr::Operator(o) => o,
+            _ => return Err(SymErr::Inconvertible),
+        };
+        let next = o.next.as_ref().unwrap();
+
+        let a = Self::from_expr(engine, &next[0], var)?;
+        let checked = |exact: bool, p: Self| {
+            if exact && !p.is_exact() {
+                Err(SymErr::Overflow)
+            } else {
+                Ok(p)
+            }
+        };
+        let b = || Self::from_expr(engine, &next[1], var);
+        match o.value {
+            Operator::Pos => Ok(a),
+            Operator::Neg => Ok(-a),
+            Operator::Add => b().and_then(|b| checked(a.is_exact() && b.is_exact(), a + b)),
+            Operator::Sub => b().and_then(|b| checked(a.is_exact() && b.is_exact(), a - b)),
+            Operator::Mul => b().and_then(|b| checked(a.is_exact() && b.is_exact(), a * b)),
+            Operator::Div if !next[1].contains(var) => match next[1].eval(engine)? {
+                Expr::Number(n) if !is_zero(&n) => checked(
+                    a.is_exact() && matches!(n, Number::Rational(..)),
+                    a.scale(Number::Rational(1, 1) / n),
+                ),
+                _ => Err(SymErr::Inconvertible),
+            },
+            Operator::Pow if !next[1].contains(var) => match next[1].eval(engine)? {
+                Expr::Number(Number::Rational(n, 1)) => checked(
+                    a.is_exact(),
+                    a.pow(u32::try_from(n).or(Err(SymErr::Inconvertible))?),
+                ),
+                _ => Err(SymErr::Inconvertible),
+            },
+            _ => Err(SymErr::Inconvertible),
+        }
+    }
+
+    /// highest degree first, like `3*x^2-x+1`
+    pub fn to_expr(&self, var: &str) -> Expr {
+        let mut result: Option<Expr> = None;
+        for (n, c) in self.coefficients.iter().enumerate().rev() {
+            if is_zero(c) {
+                continue;
+            }
+
+            let power = match n {
+                0 => Expr::from(1),
+                1 => Expr::from(var),
+                n => Expr::from(var).pow(Expr::from(n as i64)),
+            };
+            let term = |c: Number| product(Expr::from(c), power.clone());
+            result = Some(match result {
+                None => term(c.clone()),
+                Some(acc) if is_negative(c) => difference(acc, term(-c.clone())),
+                Some(acc) => sum(acc, term(c.clone())),
+            });
+        }
+
+        result.unwrap_or_else(|| Expr::from(0))
+    }
+}
+
+impl Display for Polynomial {
+    fn fmt(&self, fmt: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(fmt, "{}", self.to_expr("x"))
+    }
+}
+
+impl ops::Add for Polynomial {
+    type Output = Polynomial;
+
+    fn add(self, rhs: Self) -> Self::Output {
+        Polynomial::new(
+            (0..self.coefficients.len().max(rhs.coefficients.len()))
+                .map(|i| self.coefficient(i) + rhs.coefficient(i))
+                .collect(),
+        )
+    }
+}
+
+impl ops::Sub for Polynomial {
+    type Output = Polynomial;
+
+    fn sub(self, rhs: Self) -> Self::Output {
+        self + -rhs
+    }
+}
+
+impl ops::Neg for Polynomial {
+    type Output = Polynomial;
+
+    fn neg(self) -> Self::Output {
+        Polynomial::new(self.coefficients.into_iter().map(|c| -c).collect())
+    }
+}
+
+impl ops::Mul for Polynomial {
+    type Output = Polynomial;
+
+    fn mul(self, rhs: Self) -> Self::Output {
+        if self.is_zero() || rhs.is_zero() {
+            return Polynomial::zero();
+        }
+
+        let mut result = vec![zero(); self.coefficients.len() + rhs.coefficients.len() - 1];
+        for (i, l) in self.coefficients.iter().enumerate() {
+            for (j, r) in rhs.coefficients.iter().enumerate() {
+                result[i + j] = result[i + j].clone() + l.clone() * r.clone();
+            }
+        }
+        Polynomial::new(result)
+    }
+}
+
+impl From<Number> for Polynomial {
+    fn from(c: Number) -> Self {
+        Polynomial::constant(c)
+    }
+}
@@ -0,0 +1,380 @@
+use std::{cmp::Reverse, fmt::Display};
+
+use crate::{
+    build::{difference, is_negative, is_zero, product, quotient, sum},
+    expand::{expand, join, multiply, term, terms, to_expr, Monomial, Terms},
+    factor::factor_polynomial,
+    multivariate::{MonomialOrder, MultiPolynomial},
+    polynomial::Polynomial,
+    solver::Condition,
+    Engine, Expr, Number, Operator, SymErr, Tree,
+};
+
+/// A value equal to the expression it came from wherever the conditions hold
+#[derive(Debug, PartialEq, Clone)]
+pub struct Conditional {
+    pub value: Expr,
+    pub conditions: Vec<Condition>,
+}
+
+impl Display for Conditional {
+    fn fmt(&self, fmt: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(fmt, "{}", self.value)?;
+        for (i, condition) in self.conditions.iter().enumerate() {
+            write!(
+                fmt,
+                "{}{}",
+                if i == 0 { ", if " } else { " and " },
+                condition
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// `f` applied to both sides of a relation, or to `expr` itself
+fn both_sides(expr: &Expr, f: impl Fn(&Expr) -> Expr) -> Expr {
+    match expr {
+        Expr::Operator(Tree {
+            value,
+            next: Some(next),
+        }) if value.is_relation() => Expr::relation(*value, f(&next[0]), f(&next[1])),
+        expr => f(expr),
+    }
+}
+
+fn gcd(a: i64, b: i64) -> i64 {
+    if b == 0 {
+        a.abs()
+    } else {
+        gcd(b, a % b)
+    }
+}
+
+/// Groups the terms of the expanded `expr` by the power of `var`, highest first, like
+/// `(a+b)*x+c` for `a*x+b*x+c`. Both sides of relations are collected.
+pub fn collect(expr: &Expr, var: &str) -> Expr {
+    both_sides(expr, |expr| {
+        let x = Expr::from(var);
+        let mut groups: Vec<(i64, Terms)> = Vec::new();
+        for (monomial, c) in terms(expr, false) {
+            let power = monomial
+                .iter()
+                .find(|(f, _)| *f == x)
+                .map_or(0, |(_, e)| *e);
+            let rest = monomial.into_iter().filter(|(f, _)| *f != x).collect();
+            match groups.iter().position(|(p, _)| *p == power) {
+                Some(i) => groups[i].1.push((rest, c)),
+                None => groups.push((power, vec![(rest, c)])),
+            }
+        }
+        groups.sort_by_key(|(power, _)| Reverse(*power));
+
+        let mut result: Option<Expr> = None;
+        for (power, mut coefficient) in groups {
+            // a single negative term is subtracted instead
+            let negative = matches!(coefficient.as_slice(), [(_, c)] if is_negative(c));
+            if negative {
+                coefficient[0].1 = -coefficient[0].1.clone();
+            }
+            let coefficient = to_expr(&coefficient);
+            let term = match power {
+                0 => coefficient,
+                1 => product(coefficient, x.clone()),
+                p if p > 0 => product(coefficient, x.clone().pow(Expr::from(p))),
+                -1 => quotient(coefficient, x.clone()),
+                p => quotient(coefficient, x.clone().pow(Expr::from(-p))),
+            };
+            result = Some(match result {
+                None if negative => -term,
+                None => term,
+                Some(acc) if negative => difference(acc, term),
+                Some(acc) => sum(acc, term),
+            });
+        }
+        result.unwrap_or_else(|| Expr::from(0))
+    })
+}
+
+/// Combines the terms of the expanded `expr` over their common denominator, like
+/// `2*x/((x+1)*(x-1))` for `1/(x+1)+1/(x-1)`. Both sides of relations are combined.
+pub fn together(expr: &Expr) -> Expr {
+    both_sides(expr, |expr| {
+        let terms = terms(expr, false);
+
+        // the highest power of every factor in a denominator, and the least common multiple of
+        // the denominators of the coefficients
+        let mut denominator: Monomial = Vec::new();
+        let mut lcm = 1i64;
+        for (monomial, c) in &terms {
+            for (factor, e) in monomial.iter().filter(|(_, e)| *e < 0) {
+                match denominator.iter().position(|(f, _)| f == factor) {
+                    Some(i) => denominator[i].1 = denominator[i].1.max(-e),
+                    None => denominator.push((factor.clone(), -e)),
+                }
+            }
+            if let Number::Rational(_, d) = c {
+                lcm = (lcm / gcd(lcm, *d)).checked_mul(*d).unwrap_or(lcm);
+            }
+        }
+        if denominator.is_empty() && lcm == 1 {
+            return to_expr(&terms);
+        }
+
+        let common = vec![(denominator.clone(), Number::Rational(lcm, 1))];
+        let numerator = expand(&to_expr(&multiply(&terms, &common)), false);
+        quotient(
+            numerator,
+            term(&join(&denominator, &[]), Number::Rational(lcm, 1)),
+        )
+    })
+}
+
+/// numerator and denominator of a quotient, or `expr` over `1`
+fn split(expr: Expr) -> (Expr, Expr) {
+    match expr {
+        Expr::Operator(Tree {
+            value: Operator::Div,
+            next: Some(next),
+        }) => (next[0].as_ref().clone(), next[1].as_ref().clone()),
+        expr => (expr, Expr::from(1)),
+    }
+}
+
+/// every divisor and every base with a negative integer exponent in `expr`
+fn denominators(expr: &Expr, found: &mut Vec<Expr>) {
+    if let Expr::Operator(o) = expr {
+        let next = o.next.as_ref().unwrap();
+        match (o.value, next.get(1).map(|e| e.as_ref())) {
+            (Operator::Div, Some(divisor)) => found.push(divisor.clone()),
+            (Operator::Pow, Some(Expr::Number(n))) if is_negative(n) => {
+                found.push(next[0].as_ref().clone())
+            }
+            _ => {}
+        }
+    }
+    if let Expr::Operator(Tree { next, .. }) | Expr::Function(Tree { next, .. }) = expr {
+        next.iter().flatten().for_each(|e| denominators(e, found));
+    }
+}
+
+/// the positive number that turns the coefficients of all of them into coprime integers
+fn integer_scale(polynomials: &[&MultiPolynomial]) -> Number {
+    let (mut numerator, mut denominator) = (0i64, 1i64);
+    for (_, c) in polynomials.iter().flat_map(|p| p.terms()) {
+        if let Number::Rational(n, d) = c {
+            numerator = gcd(numerator, *n);
+            denominator = (denominator / gcd(denominator, *d))
+                .checked_mul(*d)
+                .unwrap_or(denominator);
+        }
+    }
+    Number::Rational(denominator, numerator.max(1))
+}
+
+/// Cancels the greatest common divisor of numerator and denominator of a rational function in
+/// all of its variables, like `x+1` for `(x^2-1)/(x-1)`. The result has integer coefficients and
+/// a denominator with a positive leading coefficient. Every irreducible factor of a denominator
+/// in `expr` that is gone from the result comes with a [`Condition::NonZero`], the result is
+/// defined where `expr` is not.
+/// [`SymErr::Inconvertible`] if `expr` is not a quotient of polynomials with rational
+/// coefficients, [`SymErr::Undefined`] if the denominator is zero.
+pub fn cancel(engine: &Engine, expr: &Expr) -> Result<Conditional, SymErr> {
+    cancel_common(engine, expr).map(|(cancelled, _)| cancelled)
+}
+
+/// [`cancel`], and whether numerator and denominator had a common factor
+pub(crate) fn cancel_common(engine: &Engine, expr: &Expr) -> Result<(Conditional, bool), SymErr> {
+    let (numerator, denominator) = split(together(expr));
+    let vars = expr.variables();
+    let vars = vars.iter().map(String::as_str).collect::<Vec<_>>();
+    let polynomial = |e: &Expr| MultiPolynomial::from_expr(engine, e, &vars, MonomialOrder::Lex);
+    let (numerator, denominator) = (polynomial(&numerator)?, polynomial(&denominator)?);
+    if denominator.is_zero() {
+        return Err(SymErr::Undefined);
+    }
+    let rational = |p: &MultiPolynomial| {
+        p.terms()
+            .iter()
+            .all(|(_, c)| matches!(c, Number::Rational(..)))
+    };
+    if !rational(&numerator) || !rational(&denominator) {
+        return Err(SymErr::Inconvertible);
+    }
+
+    let gcd = numerator.gcd(&denominator)?;
+    let numerator = numerator.exact_div(&gcd)?;
+    let denominator = denominator.exact_div(&gcd)?;
+    let sign = if is_negative(&denominator.leading_coefficient()) {
+        Number::Rational(-1, 1)
+    } else {
+        Number::Rational(1, 1)
+    };
+    let scale = sign * integer_scale(&[&numerator, &denominator]);
+    let numerator = numerator.scale(scale.clone());
+    let denominator = denominator.scale(scale);
+
+    let value = if denominator.is_constant() {
+        quotient(
+            numerator.to_expr(),
+            Expr::from(denominator.leading_coefficient()),
+        )
+    } else {
+        quotient(numerator.to_expr(), denominator.to_expr())
+    };
+
+    // the irreducible factors of the original denominators that are gone now
+    let mut divisors = Vec::new();
+    denominators(expr, &mut divisors);
+    let mut conditions: Vec<Condition> = Vec::new();
+    for divisor in divisors {
+        for (factor, _) in factor_polynomial(&polynomial(&divisor)?)?.factors {
+            let condition = Condition::NonZero(factor.to_expr());
+            if !factor.is_constant()
+                && denominator.exact_div(&factor).is_err()
+                && !conditions.contains(&condition)
+            {
+                conditions.push(condition);
+            }
+        }
+    }
+
+    let cancelled = Conditional { value, conditions };
+    if engine.debugging {
+        println!("Cancelled {}: {}", expr, cancelled);
+    }
+    Ok((cancelled, !gcd.is_constant()))
+}
+
+/// solves `a x = b` exactly with gaussian elimination, `None` if `a` is singular
+fn linear_solve(mut a: Vec<Vec<Number>>, mut b: Vec<Number>) -> Option<Vec<Number>> {
+    let n = b.len();
+
+    for col in 0..n {
+        let pivot = (col..n).find(|&row| !is_zero(&a[row][col]))?;
+        a.swap(col, pivot);
+        b.swap(col, pivot);
+
+        let pivot_row = a[col].clone();
+        for row in col + 1..n {
+            let factor = a[row][col].clone() / pivot_row[col].clone();
+            for (x, p) in a[row][col..].iter_mut().zip(&pivot_row[col..]) {
+                *x = x.clone() - factor.clone() * p.clone();
+            }
+            b[row] = b[row].clone() - factor * b[col].clone();
+        }
+    }
+
+    let mut x = vec![Number::Rational(0, 1); n];
+    for row in (0..n).rev() {
+        let sum = (row + 1..n).fold(Number::Rational(0, 1), |acc, k| {
+            acc + a[row][k].clone() * x[k].clone()
+        });
+        x[row] = (b[row].clone() - sum) / a[row][row].clone();
+    }
+    Some(x)
+}
+
+/// `numerator / denominator` with the denominators of the coefficients of `numerator` moved
+/// into the denominator, like `(2*x+1)/(3*(x^2+1))`
+fn fraction(numerator: &Polynomial, denominator: Expr, var: &str) -> Expr {
+    let lcm = numerator
+        .coefficients()
+        .iter()
+        .fold(1i64, |lcm, c| match c {
+            Number::Rational(_, d) => (lcm / gcd(lcm, *d)).checked_mul(*d).unwrap_or(lcm),
+            Number::Irrational(_) => lcm,
+        });
+    let scale = Number::Rational(lcm, 1);
+    quotient(
+        numerator.scale(scale.clone()).to_expr(var),
+        product(Expr::from(scale), denominator),
+    )
+}
+
+/// Partial fraction decomposition of a rational function in `var` with rational coefficients:
+/// a polynomial plus a fraction for every power of every irreducible factor of the denominator,
+/// each numerator of lower degree than its factor.
+/// [`SymErr::Inconvertible`] if numerator or denominator are not polynomials in `var`.
+pub fn apart(engine: &Engine, expr: &Expr, var: &str) -> Result<Expr, SymErr> {
+    let (numerator, denominator) = split(together(expr));
+    let numerator = Polynomial::from_expr(engine, &numerator, var)?;
+    let denominator = Polynomial::from_expr(engine, &denominator, var)?;
+    let (polynomial, remainder) = numerator.div_rem(&denominator)?;
+
+    let factorization = factor_polynomial(&MultiPolynomial::from_expr(
+        engine,
+        &denominator.to_expr(var),
+        &[var],
+        MonomialOrder::Lex,
+    )?)?;
+    let factors = factorization
+        .factors
+        .iter()
+        .map(|(f, m)| Ok((f.to_univariate(var)?, *m)))
+        .collect::<Result<Vec<_>, SymErr>>()?;
+    let remainder = remainder.scale(Number::Rational(1, 1) / factorization.unit);
+
+    // remainder / (f_1^m_1 f_2^m_2 ...) = sum of a_ij / f_i^j, the coefficients of the a_ij are
+    // the unknowns of a linear system from comparing the coefficients of both numerators
+    let full = factors.iter().fold(
+        Polynomial::constant(Number::Rational(1, 1)),
+        |acc, (f, m)| acc * f.pow(*m),
+    );
+    let mut columns = Vec::new();
+    for (i, (f, m)) in factors.iter().enumerate() {
+        for j in 1..=*m {
+            let cofactor = full.exact_div(&f.pow(j))?;
+            for l in 0..f.degree().unwrap_or(0) {
+                let column = Polynomial::monomial(Number::Rational(1, 1), l) * cofactor.clone();
+                columns.push((i, j, l, column));
+            }
+        }
+    }
+    let n = columns.len();
+    let a = (0..n)
+        .map(|row| columns.iter().map(|(.., c)| c.coefficient(row)).collect())
+        .collect();
+    let b = (0..n).map(|row| remainder.coefficient(row)).collect();
+    let solution = linear_solve(a, b).ok_or(SymErr::Unsolvable)?;
+
+    let mut result = if polynomial.is_zero() {
+        None
+    } else {
+        Some(polynomial.to_expr(var))
+    };
+    for (i, (f, m)) in factors.iter().enumerate() {
+        for j in 1..=*m {
+            let numerator = columns
+                .iter()
+                .zip(&solution)
+                .filter(|((fi, fj, ..), _)| *fi == i && *fj == j)
+                .fold(Polynomial::zero(), |acc, ((.., l, _), x)| {
+                    acc + Polynomial::monomial(x.clone(), *l)
+                });
+            if numerator.is_zero() {
+                continue;
+            }
+
+            let power = match j {
+                1 => f.to_expr(var),
+                j => f.to_expr(var).pow(Expr::from(j as i64)),
+            };
+            let negative = is_negative(&numerator.leading());
+            let numerator = if negative { -numerator } else { numerator };
+            let term = fraction(&numerator, power, var);
+            result = Some(match result {
+                None if negative => -term,
+                None => term,
+                Some(acc) if negative => difference(acc, term),
+                Some(acc) => sum(acc, term),
+            });
+        }
+    }
+
+    let result = result.unwrap_or_else(|| Expr::from(0));
+    if engine.debugging {
+        println!("Partial fractions of {}: {}", expr, result);
+    }
+    Ok(result)
+}
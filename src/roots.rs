@@ -0,0 +1,633 @@
+use std::{convert::TryFrom, f64::consts::PI, fmt::Display};
+
+use crate::{
+    build::{difference, is_negative, is_zero, negate, product, quotient, sum},
+    complex::{approximate, Complex},
+    constants,
+    polynomial::Polynomial,
+    Engine, Expr, Number, SymErr,
+};
+
+fn one() -> Number {
+    Number::Rational(1, 1)
+}
+
+/// the largest factor [`divisors`] tries, so it stays fast for any `i64`
+const MAX_TRIAL_FACTOR: u64 = 1 << 20;
+
+/// the most pairs of divisors [`rational_roots`] tries, there are millions for numbers like
+/// `963761198400`
+const MAX_CANDIDATES: usize = 1 << 16;
+
+/// Every positive divisor of `n`, from its prime factors found with trial division. `None` for
+/// zero, if a divisor does not fit `i64`, or if what is left after trying every factor up to
+/// [`MAX_TRIAL_FACTOR`] could still have two prime factors.
+fn divisors(n: i64) -> Option<Vec<i64>> {
+    let mut rest = n.unsigned_abs();
+    if rest == 0 {
+        return None;
+    }
+
+    let mut divisors = vec![1u64];
+    let mut factor = 2;
+    while factor <= rest / factor {
+        if factor > MAX_TRIAL_FACTOR {
+            return None;
+        }
+        let count = divisors.len();
+        let mut power = 1;
+        while rest.is_multiple_of(factor) {
+            rest /= factor;
+            power *= factor;
+            for i in 0..count {
+                divisors.push(divisors[i] * power);
+            }
+        }
+        factor += 1;
+    }
+    // no factor up to its square root is left, so it is prime
+    if rest > 1 {
+        for i in 0..divisors.len() {
+            divisors.push(divisors[i] * rest);
+        }
+    }
+    divisors
+        .into_iter()
+        .map(|d| i64::try_from(d).ok())
+        .collect()
+}
+
+/// Finds and divides out every rational root with the rational root theorem. Nothing is found if
+/// the coefficients have too many divisors to try, see [`divisors`] and [`MAX_CANDIDATES`].
+fn rational_roots(polynomial: &mut Polynomial) -> Result<Vec<Number>, SymErr> {
+    // scale to integer coefficients
+    let mut lcm: i64 = 1;
+    for c in polynomial.coefficients() {
+        match c {
+            Number::Rational(_, denom) => match (lcm / gcd(lcm, *denom)).checked_mul(*denom) {
+                Some(next) => lcm = next,
+                None => return Ok(Vec::new()),
+            },
+            Number::Irrational(_) => return Ok(Vec::new()),
+        }
+    }
+    let integers = polynomial.scale(Number::Rational(lcm, 1));
+    let (constant, leading) = match (
+        integers.coefficients().first(),
+        integers.coefficients().last(),
+    ) {
+        (Some(Number::Rational(constant, 1)), Some(Number::Rational(leading, 1))) => {
+            (*constant, *leading)
+        }
+        _ => return Ok(Vec::new()),
+    };
+    let (numerators, denominators) = match (divisors(constant), divisors(leading)) {
+        (Some(p), Some(q)) if p.len() * q.len() <= MAX_CANDIDATES => (p, q),
+        _ => return Ok(Vec::new()),
+    };
+
+    let mut candidates = Vec::new();
+    for p in &numerators {
+        for q in &denominators {
+            for sign in [1, -1] {
+                candidates.push(Number::Rational(sign * p, 1) / Number::Rational(*q, 1));
+            }
+        }
+    }
+    candidates.sort_by(|a, b| f64::from(a.clone()).total_cmp(&f64::from(b.clone())));
+    candidates.dedup();
+
+    let mut roots = Vec::new();
+    for candidate in candidates {
+        if polynomial.is_constant() {
+            break;
+        }
+        let divisor = Polynomial::new(vec![-candidate.clone(), one()]);
+        while !polynomial.is_constant() && is_zero(&polynomial.evaluate(&candidate)) {
+            *polynomial = polynomial.div_rem(&divisor)?.0;
+            roots.push(candidate.clone());
+        }
+    }
+    Ok(roots)
+}
+
+fn gcd(a: i64, b: i64) -> i64 {
+    if b == 0 {
+        a.abs()
+    } else {
+        gcd(b, a % b)
+    }
+}
+
+/// `n = outside^2 * inside` with `inside` square free
+fn square_free(n: i128) -> (i128, i128) {
+    let (mut outside, mut inside) = (1, n);
+    let mut factor = 2;
+    while factor * factor * factor <= inside {
+        while inside % (factor * factor) == 0 {
+            inside /= factor * factor;
+            outside *= factor;
+        }
+        factor += 1;
+    }
+
+    // whatever is left has at most two prime factors
+    let root = (inside as f64).sqrt().round() as i128;
+    if root > 1 && root * root == inside {
+        (outside * root, 1)
+    } else {
+        (outside, inside)
+    }
+}
+
+fn i() -> Expr {
+    Expr::from(constants::I)
+}
+
+fn sqrt_number(n: Number) -> Expr {
+    match n {
+        Number::Rational(nom, denom) if nom < 0 => {
+            product(sqrt_number(Number::Rational(-nom, denom)), i())
+        }
+        Number::Rational(0, _) => Expr::from(0),
+        Number::Rational(nom, denom) => {
+            // sqrt(nom/denom) = sqrt(nom*denom)/denom
+            let (outside, inside) = square_free(nom as i128 * denom as i128);
+            let coefficient = Number::Rational(outside as i64, 1) / Number::Rational(denom, 1);
+            if inside == 1 {
+                Expr::from(coefficient)
+            } else {
+                product(
+                    Expr::from(coefficient),
+                    Expr::from(inside as i64).pow(Expr::from(Number::Rational(1, 2))),
+                )
+            }
+        }
+        Number::Irrational(f) if f < 0.0 => product(Expr::from((-f).sqrt()), i()),
+        Number::Irrational(f) => Expr::from(f.sqrt()),
+    }
+}
+
+/// principal `n`:th root, real roots of negative reals are kept real for odd `n`
+fn radical(value: Expr, n: i64) -> Expr {
+    let exponent = Expr::from(Number::Rational(1, n));
+    match value {
+        Expr::Number(num) if n == 2 => sqrt_number(num),
+        Expr::Number(num) => match num.clone().root(n) {
+            root @ Number::Rational(..) => Expr::from(root),
+            _ if is_negative(&num) => negate(Expr::from(-num).pow(exponent)),
+            _ => Expr::from(num).pow(exponent),
+        },
+        value => match approximate(&value) {
+            Some(c) if c.im == 0.0 && c.re < 0.0 && n == 2 => {
+                product(negate(value).pow(exponent), i())
+            }
+            Some(c) if c.im == 0.0 && c.re < 0.0 && n % 2 == 1 => {
+                negate(negate(value).pow(exponent))
+            }
+            _ => value.pow(exponent),
+        },
+    }
+}
+
+fn linear(c: &[Number]) -> Vec<Expr> {
+    vec![Expr::from(-c[0].clone() / c[1].clone())]
+}
+
+fn quadratic(c: &[Number]) -> Vec<Expr> {
+    let (a, b, c) = (c[2].clone(), c[1].clone(), c[0].clone());
+    let two_a = Number::Rational(2, 1) * a.clone();
+
+    // (-b +- sqrt(b^2 - 4ac)) / 2a
+    let center = Expr::from(-b.clone() / two_a.clone());
+    let discriminant = b.clone() * b - Number::Rational(4, 1) * a * c;
+    let offset = sqrt_number(discriminant / (two_a.clone() * two_a));
+
+    vec![
+        difference(center.clone(), offset.clone()),
+        sum(center, offset),
+    ]
+}
+
+fn cubic(c: &[Number]) -> Vec<Expr> {
+    let a = c[3].clone();
+    let (b, c, d) = (
+        c[2].clone() / a.clone(),
+        c[1].clone() / a.clone(),
+        c[0].clone() / a,
+    );
+    let r = |nom, denom| Number::Rational(nom, denom);
+
+    // x = t - b/3, t^3 + pt + q = 0
+    let shift = Expr::from(-b.clone() / r(3, 1));
+    let p = c.clone() - b.clone() * b.clone() / r(3, 1);
+    let q = r(2, 27) * b.clone() * b.clone() * b.clone() - b * c / r(3, 1) + d;
+
+    let depressed = if is_zero(&p) {
+        let u = radical(Expr::from(-q), 3);
+        cardano(u, Expr::from(0))
+    } else {
+        let discriminant =
+            q.clone() * q.clone() / r(4, 1) + p.clone() * p.clone() * p.clone() / r(27, 1);
+        if is_zero(&discriminant) {
+            let single = Expr::from(r(3, 1) * q.clone() / p.clone());
+            let double = Expr::from(r(-3, 2) * q / p);
+            vec![single, double.clone(), double]
+        } else {
+            let half_q = Expr::from(-q / r(2, 1));
+            let root = sqrt_number(discriminant);
+            let u = radical(sum(half_q.clone(), root.clone()), 3);
+            let v = radical(difference(half_q, root), 3);
+            cardano(u, v)
+        }
+    };
+
+    depressed
+        .into_iter()
+        .map(|t| sum(t, shift.clone()))
+        .collect()
+}
+
+/// `u + v`, `wu + w'v` and `w'u + wv` where `w` is a primitive cube root of unity
+fn cardano(u: Expr, v: Expr) -> Vec<Expr> {
+    let s = sum(u.clone(), v.clone());
+    let d = difference(u, v);
+    let half_s = quotient(negate(s.clone()), Expr::from(2));
+    let offset = product(sqrt_number(Number::Rational(-3, 4)), d);
+
+    vec![
+        s,
+        difference(half_s.clone(), offset.clone()),
+        sum(half_s, offset),
+    ]
+}
+
+/// `None` if rounding hides the positive root of the resolvent cubic that ferrari needs
+fn quartic(c: &[Number]) -> Option<Vec<Expr>> {
+    let a = c[4].clone();
+    let (b, c, d, e) = (
+        c[3].clone() / a.clone(),
+        c[2].clone() / a.clone(),
+        c[1].clone() / a.clone(),
+        c[0].clone() / a,
+    );
+    let r = |nom, denom| Number::Rational(nom, denom);
+    let b2 = b.clone() * b.clone();
+
+    // x = y - b/4, y^4 + py^2 + qy + r = 0
+    let shift = Expr::from(-b.clone() / r(4, 1));
+    let p = c.clone() - r(3, 8) * b2.clone();
+    let q = b2.clone() * b.clone() / r(8, 1) - b.clone() * c.clone() / r(2, 1) + d.clone();
+    let s = r(-3, 256) * b2.clone() * b2.clone() + b2 * c / r(16, 1) - b * d / r(4, 1) + e;
+
+    let depressed = if is_zero(&q) {
+        // biquadratic
+        quadratic(&[s, p, one()])
+            .into_iter()
+            .flat_map(|z| {
+                let root = radical(z, 2);
+                vec![negate(root.clone()), root]
+            })
+            .collect::<Vec<_>>()
+    } else {
+        // ferrari, m is a root of the resolvent 8m^3 + 8pm^2 + (2p^2 - 8r)m - q^2
+        let resolvent = vec![
+            -q.clone() * q.clone(),
+            r(2, 1) * p.clone() * p.clone() - r(8, 1) * s,
+            r(8, 1) * p.clone(),
+            r(8, 1),
+        ];
+        let m = closed_form(Polynomial::new(resolvent))
+            .unwrap()
+            .into_iter()
+            .filter_map(|m| approximate(&m).map(|c| (m, c)))
+            .filter(|(_, c)| c.is_real(1e-9) && c.re > 0.0)
+            .max_by(|(a, _), (b, _)| {
+                matches!(a, Expr::Number(_)).cmp(&matches!(b, Expr::Number(_)))
+            })
+            .map(|(m, _)| m)?;
+
+        // y = (+-1 sqrt(2m) +-2 sqrt(-(2p + 2m +-1 2q/sqrt(2m)))) / 2
+        let root = radical(product(Expr::from(2), m.clone()), 2);
+        let base = sum(Expr::from(r(2, 1) * p), product(Expr::from(2), m));
+        let mut depressed = Vec::new();
+        for sign in [-1, 1] {
+            let term = quotient(Expr::from(r(2 * sign, 1) * q.clone()), root.clone());
+            let inner = radical(negate(sum(base.clone(), term)), 2);
+            let outer = product(Expr::from(sign), root.clone());
+            depressed.push(quotient(
+                difference(outer.clone(), inner.clone()),
+                Expr::from(2),
+            ));
+            depressed.push(quotient(sum(outer, inner), Expr::from(2)));
+        }
+        depressed
+    };
+
+    Some(
+        depressed
+            .into_iter()
+            .map(|y| sum(y, shift.clone()))
+            .collect(),
+    )
+}
+
+/// the roots of [`aberth`] repeated by multiplicity, for when the formulas fail
+fn approximated(polynomial: &Polynomial) -> Result<Vec<Expr>, SymErr> {
+    let mut roots = Vec::new();
+    for root in aberth(polynomial)? {
+        let value = if root.value.im == 0.0 {
+            Expr::from(root.value.re)
+        } else {
+            sum(
+                Expr::from(root.value.re),
+                product(Expr::from(root.value.im), i()),
+            )
+        };
+        roots.extend(std::iter::repeat_n(value, root.multiplicity));
+    }
+    Ok(roots)
+}
+
+/// Every root of `polynomial`, repeated by multiplicity. Rational roots are divided out first,
+/// the rest is solved with the quadratic, cubic (Cardano) and quartic (Ferrari) formulas. Quartics
+/// where Ferrari fails to rounding are approximated with [`aberth`]. [`SymErr::Unsolvable`] for
+/// degrees above four once the roots at zero are divided out, before looking for rational ones.
+pub fn closed_form(mut polynomial: Polynomial) -> Result<Vec<Expr>, SymErr> {
+    if polynomial.is_constant() {
+        return Err(SymErr::Unsolvable);
+    }
+
+    let mut roots = Vec::new();
+    while is_zero(&polynomial.coefficient(0)) {
+        polynomial = polynomial.div_rem(&Polynomial::x())?.0;
+        roots.push(Expr::from(0));
+    }
+    if polynomial.degree() > Some(4) {
+        return Err(SymErr::Unsolvable);
+    }
+
+    roots.extend(rational_roots(&mut polynomial)?.into_iter().map(Expr::from));
+
+    let c = polynomial.coefficients();
+    roots.append(&mut match polynomial.degree().unwrap_or(0) {
+        0 => Vec::new(),
+        1 => linear(c),
+        2 => quadratic(c),
+        3 => cubic(c),
+        4 => match quartic(c) {
+            Some(roots) => roots,
+            None => approximated(&polynomial)?,
+        },
+        _ => return Err(SymErr::Unsolvable),
+    });
+
+    Ok(roots)
+}
+
+/// Exact roots of a polynomial of degree four or less in `var`
+pub fn roots(engine: &Engine, expr: &Expr, var: &str) -> Result<Vec<Expr>, SymErr> {
+    let roots = closed_form(expr.to_polynomial(var, engine)?)?;
+
+    if engine.debugging {
+        roots
+            .iter()
+            .for_each(|root| println!("Root: {} = {}", var, root));
+    }
+
+    Ok(roots)
+}
+
+/// the real roots with their approximate value, exact where [`closed_form`] finds them
+pub(crate) fn real_roots(polynomial: Polynomial) -> Result<Vec<(f64, Expr)>, SymErr> {
+    if polynomial.is_constant() {
+        return Ok(Vec::new());
+    }
+
+    match closed_form(polynomial.clone()) {
+        Ok(roots) => Ok(roots
+            .into_iter()
+            .filter_map(|root| match root.approximate() {
+                Some(c) if c.is_real(1e-9) => Some((c.re, root)),
+                _ => None,
+            })
+            .collect()),
+        Err(SymErr::Unsolvable) => Ok(aberth(&polynomial)?
+            .into_iter()
+            .filter(|root| root.value.im.abs() <= root.error)
+            .map(|root| (root.value.re, Expr::from(root.value.re)))
+            .collect()),
+        Err(err) => Err(err),
+    }
+}
+
+/// A numerically found root, the disk of radius `error` around `value` contains exactly
+/// `multiplicity` roots
+#[derive(Debug, PartialEq, Clone)]
+pub struct NumericRoot {
+    pub value: Complex,
+    pub error: f64,
+    pub multiplicity: usize,
+}
+
+impl Display for NumericRoot {
+    fn fmt(&self, fmt: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(fmt, "{} +- {:e}", self.value, self.error)?;
+        if self.multiplicity > 1 {
+            write!(fmt, " (multiplicity {})", self.multiplicity)?;
+        }
+        Ok(())
+    }
+}
+
+const MAX_ITERATIONS: usize = 1000;
+
+/// value, derivative and a bound for the rounding error of the value
+fn horner(coefficients: &[Complex], z: Complex) -> (Complex, Complex, f64) {
+    let mut value = *coefficients.last().unwrap();
+    let mut derivative = Complex::from(0.0);
+    let mut error = value.abs() / 2.0;
+    for c in coefficients.iter().rev().skip(1) {
+        derivative = derivative * z + value;
+        value = value * z + *c;
+        error = error * z.abs() + value.abs();
+    }
+    (
+        value,
+        derivative,
+        f64::EPSILON * (2.0 * error - value.abs()).max(0.0),
+    )
+}
+
+/// starting points on circles given by the upper convex hull of the newton polygon
+fn initial(coefficients: &[Complex]) -> Vec<Complex> {
+    let n = coefficients.len() - 1;
+    let points = coefficients
+        .iter()
+        .enumerate()
+        .filter(|(_, c)| c.abs() != 0.0)
+        .map(|(i, c)| (i, c.abs().ln()))
+        .collect::<Vec<_>>();
+
+    let mut hull: Vec<(usize, f64)> = Vec::new();
+    for point in points {
+        while hull.len() >= 2 {
+            let (a, b) = (hull[hull.len() - 2], hull[hull.len() - 1]);
+            let cross = (b.0 - a.0) as f64 * (point.1 - a.1) - (b.1 - a.1) * (point.0 - a.0) as f64;
+            if cross >= 0.0 {
+                hull.pop();
+            } else {
+                break;
+            }
+        }
+        hull.push(point);
+    }
+
+    let mut guesses = Vec::new();
+    for pair in hull.windows(2) {
+        let ((k, log_k), (l, log_l)) = (pair[0], pair[1]);
+        let count = l - k;
+        let radius = ((log_k - log_l) / count as f64).exp();
+        for j in 0..count {
+            let angle = 2.0 * PI * j as f64 / count as f64 + 2.0 * PI * k as f64 / n as f64 + 0.4;
+            guesses.push(Complex::new(radius * angle.cos(), radius * angle.sin()));
+        }
+    }
+    guesses
+}
+
+/// Every complex root of `polynomial` with the Aberth-Ehrlich method. Approximations whose
+/// inclusion disks overlap are reported as one root with a multiplicity.
+pub fn aberth(polynomial: &Polynomial) -> Result<Vec<NumericRoot>, SymErr> {
+    if polynomial.is_constant() {
+        return Err(SymErr::Unsolvable);
+    }
+
+    let coefficients = polynomial.coefficients();
+    let zeros = coefficients.iter().take_while(|c| is_zero(c)).count();
+    let coefficients = coefficients[zeros..]
+        .iter()
+        .map(|c| Complex::from(c.clone()))
+        .collect::<Vec<_>>();
+    let n = coefficients.len() - 1;
+
+    let mut z = initial(&coefficients);
+    let mut converged = vec![false; n];
+    for _ in 0..MAX_ITERATIONS {
+        for k in 0..n {
+            if converged[k] {
+                continue;
+            }
+
+            let (value, derivative, error) = horner(&coefficients, z[k]);
+            if value.abs() <= error {
+                converged[k] = true;
+                continue;
+            }
+
+            let repulsion = (0..n)
+                .filter(|j| *j != k)
+                .fold(Complex::from(0.0), |acc, j| {
+                    acc + Complex::from(1.0) / (z[k] - z[j])
+                });
+            let correction = value / (derivative - value * repulsion);
+            if correction.re.is_finite() && correction.im.is_finite() {
+                z[k] = z[k] - correction;
+            }
+
+            if correction.abs() <= f64::EPSILON * z[k].abs() {
+                converged[k] = true;
+            }
+        }
+
+        if converged.iter().all(|c| *c) {
+            break;
+        }
+    }
+
+    // inclusion radii n * |p(z)| / |a_n prod(z - z_j)|
+    let leading = coefficients[n].abs();
+    let radii = (0..n)
+        .map(|k| {
+            let (value, _, error) = horner(&coefficients, z[k]);
+            let distances = (0..n)
+                .filter(|j| *j != k)
+                .fold(leading, |acc, j| acc * (z[k] - z[j]).abs());
+            n as f64 * (value.abs() + error) / distances
+        })
+        .collect::<Vec<_>>();
+
+    // overlapping disks form clusters
+    let mut cluster = (0..n).collect::<Vec<_>>();
+    fn find(cluster: &mut Vec<usize>, i: usize) -> usize {
+        if cluster[i] != i {
+            cluster[i] = find(cluster, cluster[i]);
+        }
+        cluster[i]
+    }
+    for i in 0..n {
+        for j in (i + 1)..n {
+            if (z[i] - z[j]).abs() <= radii[i] + radii[j] {
+                let (a, b) = (find(&mut cluster, i), find(&mut cluster, j));
+                cluster[a] = b;
+            }
+        }
+    }
+
+    let mut roots = Vec::new();
+    if zeros > 0 {
+        roots.push(NumericRoot {
+            value: Complex::from(0.0),
+            error: 0.0,
+            multiplicity: zeros,
+        });
+    }
+    for root in 0..n {
+        let members = (0..n)
+            .filter(|i| find(&mut cluster, *i) == root)
+            .collect::<Vec<_>>();
+        if members.is_empty() {
+            continue;
+        }
+
+        let mut center = members
+            .iter()
+            .fold(Complex::from(0.0), |acc, i| acc + z[*i])
+            / Complex::from(members.len() as f64);
+        let error = members
+            .iter()
+            .map(|i| (z[*i] - center).abs() + radii[*i])
+            .fold(0.0, f64::max);
+
+        // the coefficients are real, so a lone root whose disk contains its own conjugate is real
+        if members.len() % 2 == 1 && center.im.abs() <= error {
+            center.im = 0.0;
+        }
+        roots.push(NumericRoot {
+            value: center,
+            error,
+            multiplicity: members.len(),
+        });
+    }
+
+    roots.sort_by(|a, b| {
+        a.value
+            .re
+            .total_cmp(&b.value.re)
+            .then(a.value.im.total_cmp(&b.value.im))
+    });
+    Ok(roots)
+}
+
+/// Numeric roots of any polynomial in `var`, see [`aberth`]
+pub fn numeric_roots(engine: &Engine, expr: &Expr, var: &str) -> Result<Vec<NumericRoot>, SymErr> {
+    let roots = aberth(&expr.to_polynomial(var, engine)?)?;
+
+    if engine.debugging {
+        roots
+            .iter()
+            .for_each(|root| println!("Root: {} = {}", var, root));
+    }
+
+    Ok(roots)
+}
@@ -0,0 +1,165 @@
+use std::fmt::Display;
+
+use crate::Expr;
+
+/// One end of an [`Interval`], `Infinite` is `-inf` as the lower and `inf` as the upper end
+#[derive(Debug, PartialEq, Clone)]
+pub enum Endpoint {
+    Infinite,
+    Open(Expr),
+    Closed(Expr),
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct Interval {
+    pub lower: Endpoint,
+    pub upper: Endpoint,
+}
+
+/// Union of disjoint intervals of real numbers, sorted from left to right
+#[derive(Debug, PartialEq, Clone)]
+pub struct Set {
+    pub intervals: Vec<Interval>,
+}
+
+impl Endpoint {
+    fn value(&self) -> Option<f64> {
+        match self {
+            Endpoint::Infinite => None,
+            Endpoint::Open(e) | Endpoint::Closed(e) => e.approximate().map(|c| c.re),
+        }
+    }
+}
+
+impl Interval {
+    pub fn new(lower: Endpoint, upper: Endpoint) -> Self {
+        Self { lower, upper }
+    }
+
+    /// the interval `[x, x]`
+    pub fn point(x: Expr) -> Self {
+        Self::new(Endpoint::Closed(x.clone()), Endpoint::Closed(x))
+    }
+
+    pub fn is_point(&self) -> bool {
+        match (&self.lower, &self.upper) {
+            (Endpoint::Closed(a), Endpoint::Closed(b)) => a == b,
+            _ => false,
+        }
+    }
+
+    /// `x` is compared to the approximated endpoints
+    pub fn contains(&self, x: f64) -> bool {
+        let above = match (&self.lower, self.lower.value()) {
+            (Endpoint::Infinite, _) => true,
+            (Endpoint::Open(_), Some(lower)) => x > lower,
+            (Endpoint::Closed(_), Some(lower)) => x >= lower,
+            _ => false,
+        };
+        let below = match (&self.upper, self.upper.value()) {
+            (Endpoint::Infinite, _) => true,
+            (Endpoint::Open(_), Some(upper)) => x < upper,
+            (Endpoint::Closed(_), Some(upper)) => x <= upper,
+            _ => false,
+        };
+        above && below
+    }
+
+    pub fn print(&self) -> String {
+        if self.is_point() {
+            if let Endpoint::Closed(x) = &self.lower {
+                return format!("{{{}}}", x);
+            }
+        }
+
+        let lower = match &self.lower {
+            Endpoint::Infinite => String::from("(-inf"),
+            Endpoint::Open(e) => format!("({}", e),
+            Endpoint::Closed(e) => format!("[{}", e),
+        };
+        let upper = match &self.upper {
+            Endpoint::Infinite => String::from("inf)"),
+            Endpoint::Open(e) => format!("{})", e),
+            Endpoint::Closed(e) => format!("{}]", e),
+        };
+        format!("{}, {}", lower, upper)
+    }
+
+    pub fn print_latex(&self) -> String {
+        if self.is_point() {
+            if let Endpoint::Closed(x) = &self.lower {
+                return format!("\\left\\{{{}\\right\\}}", x.print_latex());
+            }
+        }
+
+        let lower = match &self.lower {
+            Endpoint::Infinite => String::from("\\left(-\\infty"),
+            Endpoint::Open(e) => format!("\\left({}", e.print_latex()),
+            Endpoint::Closed(e) => format!("\\left[{}", e.print_latex()),
+        };
+        let upper = match &self.upper {
+            Endpoint::Infinite => String::from("\\infty\\right)"),
+            Endpoint::Open(e) => format!("{}\\right)", e.print_latex()),
+            Endpoint::Closed(e) => format!("{}\\right]", e.print_latex()),
+        };
+        format!("{}, {}", lower, upper)
+    }
+}
+
+impl Set {
+    pub fn empty() -> Self {
+        Self {
+            intervals: Vec::new(),
+        }
+    }
+
+    pub fn reals() -> Self {
+        Self {
+            intervals: vec![Interval::new(Endpoint::Infinite, Endpoint::Infinite)],
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.intervals.is_empty()
+    }
+
+    pub fn contains(&self, x: f64) -> bool {
+        self.intervals.iter().any(|interval| interval.contains(x))
+    }
+
+    pub fn print(&self) -> String {
+        if self.is_empty() {
+            return String::from("{}");
+        }
+
+        self.intervals
+            .iter()
+            .map(Interval::print)
+            .collect::<Vec<_>>()
+            .join(" U ")
+    }
+
+    pub fn print_latex(&self) -> String {
+        if self.is_empty() {
+            return String::from("\\emptyset");
+        }
+
+        self.intervals
+            .iter()
+            .map(Interval::print_latex)
+            .collect::<Vec<_>>()
+            .join(" \\cup ")
+    }
+}
+
+impl Display for Interval {
+    fn fmt(&self, fmt: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(fmt, "{}", self.print())
+    }
+}
+
+impl Display for Set {
+    fn fmt(&self, fmt: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(fmt, "{}", self.print())
+    }
+}
@@ -1,63 +1,631 @@
-use std::collections::HashMap;
+use std::{
+	collections::HashMap,
+	fmt::Display,
+	fs,
+	path::Path,
+	time::{Duration, Instant},
+};
 
-use crate::{Engine, Expr, ID, expr};
+use crate::{build::same_number, complex::approximate, egraph::{self, Cost}, rational, trace::{Step, Trace}, trig, Engine, Expr, IdKind, Number, Operator, SymErr, Tree, ID, expr};
 
+/// Where in the tree [`Simplifier::simplify`] tries the rules, every strategy repeats until no
+/// rule matches anywhere
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Strategy {
+	/// the arguments before the expression they are in, every rule once per pass
+	BottomUp,
+	/// the expression before its arguments, every rule once per pass
+	TopDown,
+	/// one rewrite at a time, always of the leftmost innermost subexpression a rule matches
+	Innermost,
+	/// every rule everywhere at once without dropping the forms they replace, the result is the
+	/// cheapest form found, see [`Simplifier::with_cost`]
+	Saturate,
+}
+
+/// How long [`Simplifier::simplify`] may rewrite, set with [`Engine::with_budget`]
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct Budget {
+	/// passes over the expression that change it, single rewrites for [`Strategy::Innermost`]
+	pub iterations: usize,
+	pub time: Option<Duration>,
+}
+
+impl Default for Budget {
+	fn default() -> Self {
+		Self { iterations: 1000, time: None }
+	}
+}
+
+/// Why [`Simplifier::simplify`] stopped
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Stop {
+	/// no rule changes the expression anymore
+	Fixpoint,
+	/// the rules lead back to an earlier form
+	Cycle,
+	Iterations,
+	Time,
+	/// [`Strategy::Saturate`] found more forms than the node limit of the simplifier
+	Nodes,
+}
+
+/// Diagnostics of [`Simplifier::simplify_with_report`]
+#[derive(Debug, PartialEq, Clone)]
+pub struct Report {
+	pub stop: Stop,
+	pub iterations: usize,
+	/// every form the expression took, the first is the input and the last the result
+	pub forms: Vec<Expr>,
+}
+
+impl Report {
+	/// the forms from the first occurrence of the result on, empty unless the rules cycle
+	pub fn cycle(&self) -> &[Expr] {
+		match (self.stop, self.forms.split_last()) {
+			(Stop::Cycle, Some((last, earlier))) => &self.forms[earlier.iter().position(|e| e == last).unwrap()..],
+			_ => &[],
+		}
+	}
+}
+
+impl Display for Report {
+	fn fmt(&self, fmt: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		let forms = |forms: &[Expr]| forms.iter().map(|e| e.to_string()).collect::<Vec<_>>().join(" -> ");
+		match self.stop {
+			Stop::Fixpoint => write!(fmt, "simplified in {} iterations", self.iterations),
+			Stop::Cycle => write!(fmt, "cycle after {} iterations: {}", self.iterations, forms(self.cycle())),
+			Stop::Iterations | Stop::Time | Stop::Nodes => write!(
+				fmt,
+				"{} budget used up after {} iterations, last forms: {}",
+				match self.stop {
+					Stop::Time => "time",
+					Stop::Nodes => "node",
+					_ => "iteration",
+				},
+				self.iterations,
+				forms(&self.forms[self.forms.len().saturating_sub(3)..])
+			),
+		}
+	}
+}
+
+/// what the identifiers of a rule matched
+pub(crate) type Bindings = HashMap<ID, Expr>;
+
+/// A condition on what an identifier of a [`Rule`] matched
+#[derive(Debug, PartialEq, Clone)]
+pub enum Guard {
+	/// the match has a numeric value that is not zero, so never if it contains variables
+	Nonzero(ID),
+	/// the match is an integer literal
+	Integer(ID),
+	/// the match is a nonnegative integer literal
+	Natural(ID),
+	/// the match is a number literal
+	Number(ID),
+}
+
+impl Guard {
+	fn holds(&self, ids: &Bindings) -> bool {
+		match self {
+			Guard::Nonzero(i) => ids.get(i).and_then(approximate).is_some_and(|z| z.abs() > 1e-9),
+			Guard::Integer(i) => matches!(ids.get(i), Some(Expr::Number(Number::Rational(_, 1)))),
+			Guard::Natural(i) => matches!(ids.get(i), Some(Expr::Number(Number::Rational(n, 1))) if *n >= 0),
+			Guard::Number(i) => matches!(ids.get(i), Some(Expr::Number(_))),
+		}
+	}
+}
+
+impl Guard {
+	/// `nonzero(x_)`, `integer(x_)`, `natural(x_)` or `number(x_)`
+	fn parse(text: &str, names: &[String]) -> Result<Self, SymErr> {
+		let (kind, name) = text.strip_suffix(')').and_then(|t| t.split_once('(')).ok_or(SymErr::InvalidRule)?;
+		let id = match Rule::identifier(name.trim()) {
+			Some(kind) if names.iter().any(|n| n == name.trim()) => Rule::id(name.trim(), kind, names),
+			_ => return Err(SymErr::InvalidRule),
+		};
+		match kind.trim() {
+			"nonzero" => Ok(Guard::Nonzero(id)),
+			"integer" => Ok(Guard::Integer(id)),
+			"natural" => Ok(Guard::Natural(id)),
+			"number" => Ok(Guard::Number(id)),
+			_ => Err(SymErr::InvalidRule),
+		}
+	}
+}
+
+/// `matcher` is replaced with `replace`, but only where all guards hold. Rules with a higher
+/// priority are tried first, a rule in a group is only tried while the group is enabled.
+#[derive(Debug, Clone)]
+pub struct Rule {
+	name: Option<String>,
+	group: Option<String>,
+	priority: i32,
+	matcher: Expr,
+	replace: Expr,
+	guards: Vec<Guard>,
+}
+
+/// the name, or else the rule itself
+impl Display for Rule {
+	fn fmt(&self, fmt: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		match &self.name {
+			Some(name) => write!(fmt, "{}", name),
+			None => write!(fmt, "{} -> {}", self.matcher, self.replace),
+		}
+	}
+}
+
+impl Rule {
+	pub fn new(matcher: Expr, replace: Expr) -> Self {
+		Self { name: None, group: None, priority: 0, matcher, replace, guards: Vec::new() }
+	}
+
+	pub fn with_guard(mut self, guard: Guard) -> Self {
+		self.guards.push(guard);
+		self
+	}
+
+	pub fn with_name(mut self, name: &str) -> Self {
+		self.name = Some(String::from(name));
+		self
+	}
+
+	pub fn name(&self) -> Option<&str> {
+		self.name.as_deref()
+	}
+
+	pub fn with_group(mut self, group: &str) -> Self {
+		self.group = Some(String::from(group));
+		self
+	}
+
+	pub fn group(&self) -> Option<&str> {
+		self.group.as_deref()
+	}
+
+	pub fn with_priority(mut self, priority: i32) -> Self {
+		self.priority = priority;
+		self
+	}
+
+	pub fn priority(&self) -> i32 {
+		self.priority
+	}
+
+	/// Parses `name: matcher -> replace if guard and guard` where the name and the guards are
+	/// optional. Variables ending in `_` are pattern variables, ones ending in `_num` or `_sym`
+	/// only match numbers or variables. The replacement can only use the pattern variables of
+	/// the matcher and the guards are the ones of [`Guard::parse`], e.g.
+	/// `x_ * x_^y_ -> x_^(y_+1) if natural(y_)`.
+	pub fn parse(engine: &Engine, text: &str) -> Result<Self, SymErr> {
+		let (name, text) = match text.split_once(':') {
+			Some((name, text)) => (Some(name.trim()), text),
+			None => (None, text),
+		};
+		let (text, guards) = match text.split_once(" if ") {
+			Some((text, guards)) => (text, guards.split(" and ").collect()),
+			None => (text, Vec::new()),
+		};
+		let (matcher, replace) = text.split_once("->").ok_or(SymErr::InvalidRule)?;
+
+		let (mut matcher, mut replace) = (Expr::parse(engine, matcher)?, Expr::parse(engine, replace)?);
+		let mut names = Vec::new();
+		Rule::pattern(&mut matcher, &mut names, true)?;
+		Rule::pattern(&mut replace, &mut names, false)?;
+		let mut rule = Rule::new(matcher, replace);
+		for guard in guards {
+			rule = rule.with_guard(Guard::parse(guard, &names)?);
+		}
+		Ok(match name {
+			Some(name) if !name.is_empty() => rule.with_name(name),
+			Some(_) => return Err(SymErr::InvalidRule),
+			None => rule,
+		})
+	}
+
+	/// what a pattern variable called `name` matches, `None` if it is a regular variable
+	fn identifier(name: &str) -> Option<IdKind> {
+		if name.ends_with("_num") {
+			Some(IdKind::Number)
+		} else if name.ends_with("_sym") {
+			Some(IdKind::Symbol)
+		} else if name.ends_with('_') {
+			Some(IdKind::Any)
+		} else {
+			None
+		}
+	}
+
+	/// pattern variables are numbered in the order they first appear
+	fn id(name: &str, kind: IdKind, names: &[String]) -> ID {
+		let id = names.iter().position(|n| n == name).unwrap() as u32;
+		match kind {
+			IdKind::Any => ID::new(id),
+			IdKind::Number => ID::number(id),
+			IdKind::Symbol => ID::symbol(id),
+		}
+	}
+
+	/// replaces the pattern variables in `expr` with identifiers, new ones only if `new` is set
+	fn pattern(expr: &mut Expr, names: &mut Vec<String>, new: bool) -> Result<(), SymErr> {
+		match expr {
+			Expr::Variable(name) => {
+				if let Some(kind) = Rule::identifier(name) {
+					if !names.contains(name) {
+						if !new {
+							return Err(SymErr::InvalidRule);
+						}
+						names.push(name.clone());
+					}
+					*expr = Expr::Identifier(Rule::id(name, kind, names));
+				}
+			}
+			Expr::Function(Tree { next: Some(next), .. }) | Expr::Operator(Tree { next: Some(next), .. }) => {
+				for e in next.iter_mut() {
+					Rule::pattern(e, names, new)?;
+				}
+			}
+			_ => (),
+		}
+		Ok(())
+	}
+
+	pub(crate) fn matcher(&self) -> &Expr {
+		&self.matcher
+	}
+
+	pub(crate) fn replace(&self) -> &Expr {
+		&self.replace
+	}
+
+	pub(crate) fn holds(&self, ids: &Bindings) -> bool {
+		self.guards.iter().all(|guard| guard.holds(ids))
+	}
+}
+
+/// where the rewriting is and what it did so far
+#[derive(Default)]
+struct Tracer {
+	path: Vec<usize>,
+	steps: Vec<Step>,
+	/// whether quotients of polynomials are cancelled too, see [`Simplifier::cancel`]
+	cancelling: bool,
+}
+
+impl Tracer {
+	fn record(&mut self, rule: String, before: &Expr, after: &Expr) {
+		self.steps.push(Step { rule, path: self.path.clone(), before: before.clone(), after: after.clone(), conditions: Vec::new() });
+	}
+}
+
+/// Rewrites expressions with its rules, see [`Simplifier::new`] for the built in ones
 pub struct Simplifier {
-    rules: Vec<(Expr, Expr)>,
+    rules: Vec<Rule>,
+    strategy: Strategy,
+    disabled: Vec<String>,
+    cost: Cost,
+    node_limit: usize,
 }
 
 impl Simplifier {
+	pub fn with_strategy(mut self, strategy: Strategy) -> Self {
+		self.strategy = strategy;
+		self
+	}
+
+	pub fn strategy(&self) -> Strategy {
+		self.strategy
+	}
 
-	fn compare(matcher: &Expr, target: &Expr, ids: &mut HashMap<ID, Expr>) -> bool {
-		if matcher.ty() != target.ty() {
-			return false;
+	/// what [`Strategy::Saturate`] minimizes, [`Cost::NodeCount`] by default
+	pub fn with_cost(mut self, cost: Cost) -> Self {
+		self.cost = cost;
+		self
+	}
+
+	pub fn cost(&self) -> &Cost {
+		&self.cost
+	}
+
+	/// how many nodes [`Strategy::Saturate`] may add, `10000` by default
+	pub fn with_node_limit(mut self, node_limit: usize) -> Self {
+		self.node_limit = node_limit;
+		self
+	}
+
+	pub fn node_limit(&self) -> usize {
+		self.node_limit
+	}
+
+	/// adds a rule after the others with the same or a higher priority
+	pub fn with_rule(mut self, rule: Rule) -> Self {
+		self.insert(rule);
+		self
+	}
+
+	fn insert(&mut self, rule: Rule) {
+		let i = self.rules.iter().take_while(|r| r.priority >= rule.priority).count();
+		self.rules.insert(i, rule);
+	}
+
+	/// adds a rule written like in [`Rule::parse`], see [`Simplifier::with_rule`]
+	pub fn add_rule(&mut self, engine: &Engine, rule: &str) -> Result<(), SymErr> {
+		self.insert(Rule::parse(engine, rule)?);
+		Ok(())
+	}
+
+	/// removes every rule called `name`
+	pub fn without_rule(mut self, name: &str) -> Self {
+		self.rules.retain(|rule| rule.name() != Some(name));
+		self
+	}
+
+	pub fn with_group(mut self, group: &str) -> Self {
+		self.disabled.retain(|g| g != group);
+		self
+	}
+
+	/// the rules in `group` are kept, but not tried until the group is enabled again
+	pub fn without_group(mut self, group: &str) -> Self {
+		if self.is_enabled(group) {
+			self.disabled.push(String::from(group));
 		}
-		
-		match (matcher, target) {
-			(Expr::Number(a), Expr::Number(b)) => a == b,
-			(Expr::Operator(a), Expr::Operator(b)) => {
-				if a.value != b.value {
-					return false;
-				}
+		self
+	}
 
-				let matcher_left = a.next.as_ref().unwrap()[0].as_ref();
-				let matcher_right = a.next.as_ref().unwrap()[1].as_ref();
-				let target_left = b.next.as_ref().unwrap()[0].as_ref();
-				let target_right = b.next.as_ref().unwrap()[1].as_ref();
-
-				let left_match = if let Expr::Identifier(i) = matcher_left {
-					if let Some(e) = ids.get(i) {
-						e == target_left
-					} else {
-						ids.insert(i.clone(), target_left.clone());
-						true
-					}
-				} else {
-					Simplifier::compare(matcher_left, target_left, ids)
-				};
-				let right_match = if let Expr::Identifier(i) = matcher_right {
-					if let Some(e) = ids.get(i) {
-						e == target_right
-					} else {
-						ids.insert(i.clone(), target_right.clone());
-						true
-					}
-				} else {
-					Simplifier::compare(matcher_right, target_right, ids)
-				};
+	pub fn is_enabled(&self, group: &str) -> bool {
+		!self.disabled.iter().any(|g| g == group)
+	}
+
+	/// the groups of the rules, in the order they are first tried
+	pub fn groups(&self) -> Vec<&str> {
+		let mut groups = Vec::new();
+		for group in self.rules.iter().filter_map(Rule::group) {
+			if !groups.contains(&group) {
+				groups.push(group);
+			}
+		}
+		groups
+	}
+
+	/// The built in rules with the factoring ones replaced by ones that distribute products over
+	/// sums, so `x*(y+1)` becomes `x*y+x` instead of the other way around
+	pub fn expanding() -> Self {
+		Simplifier::new().without_group("factoring").with_group("expanding")
+	}
+
+	/// The built in rules and the identities of [`trig`](crate::trig) saturated, the result is the
+	/// form with the lowest [`trig::cost`], see [`Expr::trigsimp`]
+	pub fn trig() -> Self {
+		let mut simplifier = Simplifier::new()
+			.with_strategy(Strategy::Saturate)
+			.with_cost(Cost::Custom(trig::cost))
+			.with_node_limit(2000);
+		for rule in trig::identities() {
+			simplifier.insert(rule);
+		}
+		simplifier
+	}
 
-				left_match && right_match
+	/// Only the rules in `rules`, one per line as in [`Rule::parse`]. Everything after a `#` is
+	/// a comment and blank lines are skipped, a line `[name]` puts the rules after it in the
+	/// group `name`.
+	pub fn from_str(engine: &Engine, rules: &str) -> Result<Self, SymErr> {
+		let mut simplifier = Simplifier { rules: Vec::new(), ..Simplifier::new() };
+		simplifier.disabled.clear();
+		let mut group = None;
+		for line in rules.lines() {
+			let line = line.split('#').next().unwrap().trim();
+			if let Some(name) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+				group = Some(name.trim()).filter(|name| !name.is_empty());
+			} else if !line.is_empty() {
+				let rule = Rule::parse(engine, line)?;
+				simplifier.insert(match group {
+					Some(group) => rule.with_group(group),
+					None => rule,
+				});
+			}
+		}
+		Ok(simplifier)
+	}
+
+	/// [`Simplifier::from_str`] with the contents of a file
+	pub fn from_file(engine: &Engine, path: impl AsRef<Path>) -> Result<Self, SymErr> {
+		Simplifier::from_str(engine, &fs::read_to_string(path).or(Err(SymErr::Unreadable))?)
+	}
+
+	/// all rules in the order they are tried, also the ones in disabled groups
+	pub fn rules(&self) -> &[Rule] {
+		&self.rules
+	}
+
+	pub(crate) fn enabled(&self) -> impl Iterator<Item = &Rule> {
+		self.rules.iter().filter(move |rule| rule.group().is_none_or(|group| self.is_enabled(group)))
+	}
+
+	/// `+` and `*` are associative and commutative
+	fn is_ac(oper: Operator) -> bool {
+		oper == Operator::Add || oper == Operator::Mul
+	}
+
+	/// the operands of nested `oper`s, `a + (b + c)` gives `[a, b, c]`
+	fn flatten(expr: &Expr, oper: Operator) -> Vec<Expr> {
+		match expr {
+			Expr::Operator(o) if o.value == oper => o.next.iter().flatten().flat_map(|e| Simplifier::flatten(e, oper)).collect(),
+			expr => vec![expr.clone()],
+		}
+	}
+
+	/// Every way to match each one of `matchers` with a different one of `targets` combined by
+	/// `oper`, in any order, with the targets that are left over. Those have to be none unless
+	/// `rest` is set, otherwise the last identifier matches all the remaining targets together.
+	fn match_operands(oper: Operator, matchers: &[Expr], targets: &[Expr], rest: bool, ids: &Bindings) -> Vec<(Bindings, Vec<Expr>)> {
+		let (matcher, matchers) = match matchers.split_first() {
+			Some(split) => split,
+			None if rest || targets.is_empty() => return vec![(ids.clone(), targets.to_vec())],
+			None => return Vec::new(),
+		};
+
+		if let (Expr::Identifier(_), [], false, Some((first, others))) = (matcher, matchers, rest, targets.split_first()) {
+			let combined = others.iter().fold(first.clone(), |acc, e| acc.operate(oper, Some(e.clone())).unwrap());
+			return Simplifier::matches(matcher, &combined, ids).into_iter().map(|ids| (ids, Vec::new())).collect();
+		}
+
+		let mut found = Vec::new();
+		for i in 0..targets.len() {
+			let others = [&targets[..i], &targets[i + 1..]].concat();
+			for ids in Simplifier::matches(matcher, &targets[i], ids) {
+				found.extend(Simplifier::match_operands(oper, matchers, &others, rest, &ids));
+			}
+		}
+		found
+	}
+
+	/// the operands of `expr` for `oper`, identifiers last so the last one can take what is left
+	fn operands(expr: &Expr, oper: Operator) -> Vec<Expr> {
+		let mut operands = Simplifier::flatten(expr, oper);
+		operands.sort_by_key(|e| matches!(e, Expr::Identifier(_)));
+		operands
+	}
+
+	/// every way to match the matchers with the targets in this order
+	fn match_sequence(matchers: &[Box<Expr>], targets: &[Box<Expr>], ids: &Bindings) -> Vec<Bindings> {
+		if matchers.len() != targets.len() {
+			return Vec::new();
+		}
+		matchers.iter().zip(targets).fold(vec![ids.clone()], |found, (matcher, target)| {
+			found.iter().flat_map(|ids| Simplifier::matches(matcher, target, ids)).collect()
+		})
+	}
+
+	/// every extension of the bindings `ids` with which `matcher` matches `target`
+	fn matches(matcher: &Expr, target: &Expr, ids: &Bindings) -> Vec<Bindings> {
+		let matched = match (matcher, target) {
+			(Expr::Identifier(i), _) => match ids.get(i) {
+				Some(e) => e == target,
+				None if !Simplifier::admits(i.kind(), target) => false,
+				None => {
+					let mut ids = ids.clone();
+					ids.insert(i.clone(), target.clone());
+					return vec![ids];
+				}
+			},
+			(Expr::Number(a), Expr::Number(b)) => same_number(a, b),
+			(Expr::Variable(a), Expr::Variable(b)) => a == b,
+			(Expr::Function(a), Expr::Function(b)) if a.value == b.value => {
+				return Simplifier::match_sequence(a.next.as_deref().unwrap_or(&[]), b.next.as_deref().unwrap_or(&[]), ids);
+			}
+			(Expr::Operator(a), Expr::Operator(b)) if a.value == b.value => {
+				if Simplifier::is_ac(a.value) {
+					let matchers = Simplifier::operands(matcher, a.value);
+					let targets = Simplifier::flatten(target, a.value);
+					return Simplifier::match_operands(a.value, &matchers, &targets, false, ids).into_iter().map(|(ids, _)| ids).collect();
+				}
+				return Simplifier::match_sequence(a.next.as_deref().unwrap_or(&[]), b.next.as_deref().unwrap_or(&[]), ids);
 			},
 			_ => false,
+		};
+		if matched { vec![ids.clone()] } else { Vec::new() }
+	}
+
+	fn admits(kind: IdKind, expr: &Expr) -> bool {
+		match kind {
+			IdKind::Any => true,
+			IdKind::Number => matches!(expr, Expr::Number(_)),
+			IdKind::Symbol => matches!(expr, Expr::Variable(_)),
+		}
+	}
+
+	/// The bindings of the first way the matcher of `rule` matches `target` for which its guards
+	/// hold, where a `+` or `*` matcher may match only some of the operands, the others are returned
+	fn compare(rule: &Rule, target: &Expr) -> Option<(Bindings, Vec<Expr>)> {
+		let found = match (&rule.matcher, target) {
+			(Expr::Operator(a), Expr::Operator(b)) if a.value == b.value && Simplifier::is_ac(a.value) => {
+				let matchers = Simplifier::operands(&rule.matcher, a.value);
+				let targets = Simplifier::flatten(target, a.value);
+				Simplifier::match_operands(a.value, &matchers, &targets, true, &Bindings::new())
+			}
+			_ => Simplifier::matches(&rule.matcher, target, &Bindings::new()).into_iter().map(|ids| (ids, Vec::new())).collect(),
+		};
+		found.into_iter().find(|(ids, _)| rule.holds(ids))
+	}
+
+	/// `oper` applied to the numbers, unless that makes an exact result out of exact numbers inexact
+	fn fold_numbers(oper: Operator, numbers: Vec<Number>) -> Option<Number> {
+		let exact = numbers.iter().all(|n| matches!(n, Number::Rational(..)));
+		let mut numbers = numbers.into_iter().map(Expr::Number);
+		let first = numbers.next()?;
+		let folded = match numbers.try_fold(first, |acc, n| acc.operate(oper, Some(n))) {
+			Ok(Expr::Number(n)) => n,
+			_ => return None,
+		};
+		match folded {
+			Number::Irrational(_) if exact => None,
+			Number::Irrational(f) if !f.is_finite() => None,
+			folded => Some(folded),
+		}
+	}
+
+	/// `expr` with the numbers its root operates on folded into one, `None` if there is nothing to
+	/// fold. The operands of `+` and `*` are folded in any order, the folded number goes last in
+	/// sums and first in products.
+	pub(crate) fn fold(expr: &Expr) -> Option<Expr> {
+		let Expr::Operator(Tree { value: oper, next: Some(next) }) = expr else {
+			return None;
+		};
+		if oper.is_relation() {
+			return None;
+		}
+		if !Simplifier::is_ac(*oper) {
+			let numbers = next.iter().map(|e| match e.as_ref() {
+				Expr::Number(n) => Some(n.clone()),
+				_ => None,
+			});
+			let numbers = numbers.collect::<Option<Vec<_>>>()?;
+			return match (oper, numbers.as_slice()) {
+				(Operator::Pos | Operator::Neg, [n]) => Some(Expr::Number(n.clone()).operate(*oper, None).ok()?),
+				_ => Some(Expr::Number(Simplifier::fold_numbers(*oper, numbers)?)),
+			};
+		}
+
+		let (numbers, mut others): (Vec<_>, Vec<_>) = Simplifier::flatten(expr, *oper).into_iter().partition(|e| matches!(e, Expr::Number(_)));
+		if numbers.len() < 2 {
+			return None;
+		}
+		let numbers = numbers.into_iter().map(|e| match e {
+			Expr::Number(n) => n,
+			_ => unreachable!(),
+		});
+		let folded = Expr::Number(Simplifier::fold_numbers(*oper, numbers.collect())?);
+		match oper {
+			Operator::Mul => others.insert(0, folded),
+			_ => others.push(folded),
+		}
+		let mut others = others.into_iter();
+		let first = others.next()?;
+		Some(others.fold(first, |acc, e| acc.operate(*oper, Some(e)).unwrap()))
+	}
+
+	/// `replace` with the bindings, combined with the operands of `target` the matcher left over
+	fn substitute(replace: &Expr, target: &Expr, rest: Vec<Expr>, ids: &mut Bindings) -> Expr {
+		let mut simplified = replace.clone();
+		Simplifier::replace(&mut simplified, ids);
+		match target {
+			Expr::Operator(o) => rest.into_iter().fold(simplified, |acc, e| {
+				// `operate` can only fail for operators without operands
+				acc.operate(o.value, Some(e)).unwrap()
+			}),
+			_ => simplified,
 		}
 	}
 
-	fn replace(expr: &mut Expr, ids: &mut HashMap<ID, Expr>) {
+	fn replace(expr: &mut Expr, ids: &mut Bindings) {
 		match expr {
 			Expr::Identifier(i) => {
 				if let Some(e) = ids.get(i) {
 					*expr = e.clone();
-					return;
 				}
 			}
 			Expr::Function(f) => {
@@ -71,201 +639,381 @@ impl Simplifier {
 	}
 
 	pub fn simplify_r(&self, engine: &Engine, expr: &Expr) -> (Expr, bool) {
+		self.apply(engine, expr, &mut Tracer::default())
+	}
+
+	/// the numbers of `expr` folded and then every rule once to the root of it
+	fn apply(&self, engine: &Engine, expr: &Expr, tracer: &mut Tracer) -> (Expr, bool) {
 		let mut simplified = expr.clone();
 		let mut found = false;
 
+		if let Some(folded) = Simplifier::fold(expr) {
+			tracer.record(String::from("fold"), expr, &folded);
+			simplified = folded;
+			found = true;
+		}
+		if let Some(cancelled) = self.cancel(engine, &simplified, tracer) {
+			simplified = cancelled;
+			found = true;
+		}
+
 		// apply all rules
-		for (matcher, replace) in self.rules.iter() {
+		for rule in self.enabled() {
 			// simplify root
-			let mut ids = HashMap::new();
-			if Simplifier::compare(matcher, &simplified, &mut ids) {
-				simplified = replace.clone();
-				Simplifier::replace(&mut simplified, &mut ids);
-				
+			if let Some((mut ids, rest)) = Simplifier::compare(rule, &simplified) {
+				let replaced = Simplifier::substitute(&rule.replace, &simplified, rest, &mut ids);
+				if replaced == simplified {
+					continue;
+				}
+				tracer.record(rule.to_string(), &simplified, &replaced);
+				simplified = replaced;
+
 				if engine.debugging {
-					println!("Match found: {} for {} and replaced with {}", matcher, expr, simplified);
+					println!("Match found: {} for {} and replaced with {}", rule.matcher, expr, simplified);
 				}
 
 				found = true;
 			}
 
-			// simplify subexprs
-			
 		}
 
         (simplified, found)
 	}
 
-    pub fn simplify(&self, engine: &Engine, expr: &Expr) -> Expr {
+	/// the root with its numbers folded or cancelled, or else the result of the first rule that
+	/// matches it
+	fn rewrite(&self, engine: &Engine, expr: &Expr, tracer: &mut Tracer) -> Option<Expr> {
+		if let Some(folded) = Simplifier::fold(expr) {
+			tracer.record(String::from("fold"), expr, &folded);
+			return Some(folded);
+		}
+		if let Some(cancelled) = self.cancel(engine, expr, tracer) {
+			return Some(cancelled);
+		}
+
+		self.enabled().find_map(|rule| {
+			let (mut ids, rest) = Simplifier::compare(rule, expr)?;
+			let simplified = Simplifier::substitute(&rule.replace, expr, rest, &mut ids);
+			if simplified == *expr {
+				return None;
+			}
+			tracer.record(rule.to_string(), expr, &simplified);
+
+			if engine.debugging {
+				println!("Match found: {} for {} and replaced with {}", rule.matcher, expr, simplified);
+			}
+
+			Some(simplified)
+		})
+	}
+
+	/// Quotients of polynomials, and sums and products with one, with their common factors
+	/// cancelled like [`rational::cancel`] if the tracer is cancelling. `None` if nothing cancels,
+	/// the step keeps the points where `expr` is undefined as conditions.
+	fn cancel(&self, engine: &Engine, expr: &Expr, tracer: &mut Tracer) -> Option<Expr> {
+		let quotient = |e: &Expr| matches!(e, Expr::Operator(Tree { value: Operator::Div, .. }));
+		let candidate = match expr {
+			Expr::Operator(Tree { value: Operator::Add | Operator::Sub | Operator::Mul, next: Some(next) }) => next.iter().any(|e| quotient(e)),
+			_ => quotient(expr),
+		};
+		if !tracer.cancelling || !candidate {
+			return None;
+		}
+
+		let (cancelled, common) = rational::cancel_common(engine, expr).ok()?;
+		if !common && cancelled.conditions.is_empty() || cancelled.value == *expr {
+			return None;
+		}
+		tracer.steps.push(Step { rule: String::from("cancel"), path: tracer.path.clone(), before: expr.clone(), after: cancelled.value.clone(), conditions: cancelled.conditions });
+		Some(cancelled.value)
+	}
+
+	/// every quotient in `expr` cancelled from the innermost out, see [`Simplifier::cancel`]
+	fn cancel_all(&self, engine: &Engine, expr: &Expr, tracer: &mut Tracer) -> (Expr, bool) {
+		let (expr, inner) = Simplifier::map_arguments(expr, tracer, |e, tracer| self.cancel_all(engine, e, tracer));
+		match self.cancel(engine, &expr, tracer) {
+			Some(cancelled) => (cancelled, true),
+			None => (expr, inner),
+		}
+	}
+
+	/// `f` applied to every argument of a function or operand of an operator, with the path to it
+	fn map_arguments(expr: &Expr, tracer: &mut Tracer, mut f: impl FnMut(&Expr, &mut Tracer) -> (Expr, bool)) -> (Expr, bool) {
 		let mut expr = expr.clone();
-		loop {
-			let res  = self.simplify_r(engine, &expr);
-			expr = res.0;
+		let mut found = false;
+		if let Expr::Function(Tree { next: Some(next), .. }) | Expr::Operator(Tree { next: Some(next), .. }) = &mut expr {
+			for (i, e) in next.iter_mut().enumerate() {
+				tracer.path.push(i);
+				let (simplified, f) = f(e, tracer);
+				tracer.path.pop();
+				**e = simplified;
+				found |= f;
+			}
+		}
+		(expr, found)
+	}
+
+	fn bottom_up(&self, engine: &Engine, expr: &Expr, tracer: &mut Tracer) -> (Expr, bool) {
+		let (expr, inner) = Simplifier::map_arguments(expr, tracer, |e, tracer| self.bottom_up(engine, e, tracer));
+		let (expr, outer) = self.apply(engine, &expr, tracer);
+		(expr, inner || outer)
+	}
+
+	fn top_down(&self, engine: &Engine, expr: &Expr, tracer: &mut Tracer) -> (Expr, bool) {
+		let (expr, outer) = self.apply(engine, expr, tracer);
+		let (expr, inner) = Simplifier::map_arguments(&expr, tracer, |e, tracer| self.top_down(engine, e, tracer));
+		(expr, inner || outer)
+	}
 
-			if !res.1 {
-				break;
+	fn innermost(&self, engine: &Engine, expr: &Expr, tracer: &mut Tracer) -> Option<Expr> {
+		let mut done = false;
+		let (inner, found) = Simplifier::map_arguments(expr, tracer, |e, tracer| {
+			if done {
+				return (e.clone(), false);
 			}
+			match self.innermost(engine, e, tracer) {
+				Some(simplified) => {
+					done = true;
+					(simplified, true)
+				}
+				None => (e.clone(), false),
+			}
+		});
+		if found {
+			Some(inner)
+		} else {
+			self.rewrite(engine, expr, tracer)
 		}
+	}
 
-		expr
+    pub fn simplify(&self, engine: &Engine, expr: &Expr) -> Expr {
+		let (simplified, report) = self.simplify_with_report(engine, expr);
+		if engine.debugging && report.stop != Stop::Fixpoint {
+			println!("Simplifying {} stopped: {}", expr, report);
+		}
+		simplified
     }
 
-    #[cfg_attr(rustfmt, rustfmt_skip)]
-    pub fn new() -> Self {
-        let mut rules = Vec::<(Expr, Expr)>::new();
+	/// Rewrites until no rule changes the expression, the rules lead back to a form seen before
+	/// or the budget of the engine is used up
+	pub fn simplify_with_report(&self, engine: &Engine, expr: &Expr) -> (Expr, Report) {
+		self.run(engine, expr, &mut Tracer::default())
+	}
 
-		// ordering
-        rules.push((
-			expr!(ID::new(0)) + (expr!(ID::new(1)) + expr!(ID::new(2))),
-			(expr!(ID::new(0)) + expr!(ID::new(1))) + expr!(ID::new(2))
-		));
+	/// Every rewrite of [`Simplifier::simplify`]. [`Strategy::Saturate`] does not rewrite one
+	/// subexpression at a time, its trace is a single step `saturation` to the result.
+	pub fn simplify_with_trace(&self, engine: &Engine, expr: &Expr) -> Trace {
+		let mut tracer = Tracer::default();
+		self.run(engine, expr, &mut tracer);
+		Trace { input: expr.clone(), steps: tracer.steps }
+	}
+
+	/// [`Simplifier::simplify_with_trace`] that also cancels quotients of polynomials wherever
+	/// they are, the steps `cancel` have the conditions, see [`Expr::simplify_with_conditions`].
+	/// [`Strategy::Saturate`] cancels before it saturates.
+	pub(crate) fn simplify_cancelling(&self, engine: &Engine, expr: &Expr) -> (Expr, Trace) {
+		let mut tracer = Tracer { cancelling: true, ..Tracer::default() };
+		let (simplified, _) = self.run(engine, expr, &mut tracer);
+		(simplified, Trace { input: expr.clone(), steps: tracer.steps })
+	}
+
+	fn run(&self, engine: &Engine, expr: &Expr, tracer: &mut Tracer) -> (Expr, Report) {
+		if self.strategy == Strategy::Saturate {
+			let cancelled = self.cancel_all(engine, expr, tracer).0;
+			let (simplified, report) = egraph::saturate(engine, self, &cancelled);
+			if simplified != cancelled {
+				tracer.record(String::from("saturation"), &cancelled, &simplified);
+			}
+			return (simplified, report);
+		}
 
-		// simple canceling out
+		let start = Instant::now();
+		let mut forms = vec![expr.clone()];
+		let stop = loop {
+			if forms.len() > engine.budget.iterations {
+				break Stop::Iterations;
+			}
+			if engine.budget.time.is_some_and(|time| start.elapsed() >= time) {
+				break Stop::Time;
+			}
+
+			let expr = forms.last().unwrap();
+			let simplified = match self.strategy {
+				Strategy::BottomUp => self.bottom_up(engine, expr, tracer),
+				Strategy::TopDown => self.top_down(engine, expr, tracer),
+				Strategy::Innermost => match self.innermost(engine, expr, tracer) {
+					Some(simplified) => (simplified, true),
+					None => (expr.clone(), false),
+				},
+				Strategy::Saturate => unreachable!(),
+			};
+			if !simplified.1 || simplified.0 == *expr {
+				break Stop::Fixpoint;
+			}
+
+			let seen = forms.contains(&simplified.0);
+			forms.push(simplified.0);
+			if seen {
+				break Stop::Cycle;
+			}
+		};
+
+		let report = Report { stop, iterations: forms.len() - 1, forms };
+		(report.forms.last().unwrap().clone(), report)
+	}
+
+    /// The built in rules in the groups `arithmetic`, `powers`, `factoring`, `trig` and `logs`,
+    /// and the disabled groups `expanding`, see [`Simplifier::expanding`], and `quotients`, which
+    /// writes quotients as products with an inverse
+    #[rustfmt::skip]
+    #[allow(clippy::vec_init_then_push)]
+    pub fn new() -> Self {
+        let mut rules = Vec::<Rule>::new();
+
+		// simple canceling out, `+` and `*` match in any order and grouping
 
         // x + 0 = x
-        rules.push((
-			expr!(0.0) + expr!(ID::new(0)),
+        rules.push(Rule::new(
+			expr!(0) + expr!(ID::new(0)),
 			expr!(ID::new(0))
-		));
-		// 0 + x = x
-        rules.push((
-			expr!(ID::new(0)) + expr!(0.0),
-			expr!(ID::new(0))
-		));
+		).with_name("add zero").with_group("arithmetic"));
 		// x - x = 0
-        rules.push((
+        rules.push(Rule::new(
 			expr!(ID::new(0)) - expr!(ID::new(0)),
-			expr!(0.0)
-		));
+			expr!(0)
+		).with_name("subtract self").with_group("arithmetic"));
         // x * 0 = 0
-        rules.push((
-			expr!(0.0) * expr!(ID::new(0)),
-			expr!(0.0)
-		));
-		// 0 * x = 0
-        rules.push((
-			expr!(ID::new(0)) * expr!(0.0),
-			expr!(0.0)
-		));
+        rules.push(Rule::new(
+			expr!(0) * expr!(ID::new(0)),
+			expr!(0)
+		).with_name("multiply zero").with_group("arithmetic"));
         // x * 1 = x
-        rules.push((
-			expr!(1.0) * expr!(ID::new(0)),
-			expr!(ID::new(0))
-		));
-		// 1 * x = x
-        rules.push((
-			expr!(ID::new(0)) * expr!(1.0),
+        rules.push(Rule::new(
+			expr!(1) * expr!(ID::new(0)),
 			expr!(ID::new(0))
-		));
+		).with_name("multiply one").with_group("arithmetic"));
 
 
 
         // x * x = x^2
-        rules.push((
+        rules.push(Rule::new(
 			expr!(ID::new(0)) * expr!(ID::new(0)),
-			expr!(ID::new(0)).pow(expr!(2.0))
-		));
+			expr!(ID::new(0)).pow(expr!(2))
+		).with_name("square").with_group("powers"));
         // x + x = 2x
-        rules.push((
+        rules.push(Rule::new(
 			expr!(ID::new(0)) + expr!(ID::new(0)),
-			expr!(2.0) * expr!(ID::new(0))
-		));
-        // x / x = 1
-        rules.push((
+			expr!(2) * expr!(ID::new(0))
+		).with_name("double").with_group("arithmetic"));
+        // x / x = 1 for nonzero x
+        rules.push(Rule::new(
 			expr!(ID::new(0)) / expr!(ID::new(0)),
-			expr!(1.0)
-		));
-        // x * x^y = x^(y+1)
-        rules.push((
+			expr!(1)
+		).with_guard(Guard::Nonzero(ID::new(0))).with_name("divide self").with_group("arithmetic"));
+        // x * x^y = x^(y+1) for natural y or nonzero x
+        rules.push(Rule::new(
 			expr!(ID::new(0)) * expr!(ID::new(0)).pow(expr!(ID::new(1))),
-			expr!(ID::new(0)).pow(expr!(ID::new(1)) + expr!(1.0))
-		));
-        // x^y * x = x^(y+1)
-        rules.push((
-			expr!(ID::new(0)).pow(expr!(ID::new(1))) * expr!(ID::new(0)),
-			expr!(ID::new(0)).pow(expr!(ID::new(1)) + expr!(1.0))
-		));
-        // (x^y)^z = x^(y*z)
-        rules.push((
+			expr!(ID::new(0)).pow(expr!(ID::new(1)) + expr!(1))
+		).with_guard(Guard::Natural(ID::new(1))).with_name("multiply power").with_group("powers"));
+        rules.push(Rule::new(
+			expr!(ID::new(0)) * expr!(ID::new(0)).pow(expr!(ID::new(1))),
+			expr!(ID::new(0)).pow(expr!(ID::new(1)) + expr!(1))
+		).with_guard(Guard::Nonzero(ID::new(0))).with_name("multiply power").with_group("powers"));
+        // (x^y)^z = x^(y*z) for integer z
+        rules.push(Rule::new(
 			expr!(ID::new(0)).pow(expr!(ID::new(1))).pow(expr!(ID::new(2))),
 			expr!(ID::new(0)).pow(expr!(ID::new(1)) * expr!(ID::new(2)))
-		));
-        // x * x^-1 = 1
-        rules.push((
-			expr!(ID::new(0)) * expr!(ID::new(0)).pow(expr!(-1.0)),
-			expr!(1.0)
-		));
-        // x / y = x * y^-1
-        rules.push((
+		).with_guard(Guard::Integer(ID::new(2))).with_name("power of power").with_group("powers"));
+        // x^1 = x
+        rules.push(Rule::new(
+			expr!(ID::new(0)).pow(expr!(1)),
+			expr!(ID::new(0))
+		).with_name("power one").with_group("powers"));
+        // x^0 = 1 for nonzero x
+        rules.push(Rule::new(
+			expr!(ID::new(0)).pow(expr!(0)),
+			expr!(1)
+		).with_guard(Guard::Nonzero(ID::new(0))).with_name("power zero").with_group("powers"));
+        // x * x^-1 = 1 for nonzero x
+        rules.push(Rule::new(
+			expr!(ID::new(0)) * expr!(ID::new(0)).pow(expr!(-1)),
+			expr!(1)
+		).with_guard(Guard::Nonzero(ID::new(0))).with_name("multiply inverse").with_group("powers"));
+        // x / y = x * y^-1, disabled so results keep their quotients
+        rules.push(Rule::new(
 			expr!(ID::new(0)) / expr!(ID::new(1)), 
-			expr!(ID::new(0)) * expr!(ID::new(1)).pow(expr!(-1.0))
-		));
+			expr!(ID::new(0)) * expr!(ID::new(1)).pow(expr!(-1))
+		).with_name("divide").with_group("quotients"));
 
 		// x + x * y
 
         // x + x * y = x * (y + 1)
-        rules.push((
+        rules.push(Rule::new(
             expr!(ID::new(0)) + expr!(ID::new(0)) * expr!(ID::new(1)),
-            expr!(ID::new(0)) * (expr!(ID::new(1)) + expr!(1.0)),
-        ));
-        // x + y * x = x * (y + 1)
-        rules.push((
-            expr!(ID::new(0)) + expr!(ID::new(1)) * expr!(ID::new(0)),
-            expr!(ID::new(0)) * (expr!(ID::new(1)) + expr!(1.0)),
-        ));
-        // x * y + x = x * (y + 1)
-        rules.push((
-            expr!(ID::new(0)) * expr!(ID::new(1)) + expr!(ID::new(0)),
-            expr!(ID::new(0)) * (expr!(ID::new(1)) + expr!(1.0)),
-        ));
-        // y * x + x = x * (y + 1)
-        rules.push((
-            expr!(ID::new(1)) * expr!(ID::new(0)) + expr!(ID::new(0)),
-            expr!(ID::new(0)) * (expr!(ID::new(1)) + expr!(1.0)),
-        ));
+            expr!(ID::new(0)) * (expr!(ID::new(1)) + expr!(1)),
+        ).with_name("factor sum").with_group("factoring"));
 		// x - x * y
-		// x - x * y = x * (y - 1)
-        rules.push((
+		// x - x * y = x * (1 - y)
+        rules.push(Rule::new(
             expr!(ID::new(0)) - expr!(ID::new(0)) * expr!(ID::new(1)),
-            expr!(ID::new(0)) * (expr!(ID::new(1)) - expr!(1.0)),
-		));
+            expr!(ID::new(0)) * (expr!(1) - expr!(ID::new(1))),
+		).with_name("factor difference").with_group("factoring"));
 		// x * y - x = x * (y - 1)
-        rules.push((
+        rules.push(Rule::new(
             expr!(ID::new(0)) *  expr!(ID::new(1)) - expr!(ID::new(0)),
-            expr!(ID::new(0)) * (expr!(ID::new(1)) - expr!(1.0)),
-		));
-		// x - y * x = x * (y - 1)
-        rules.push((
-            expr!(ID::new(0)) -  expr!(ID::new(1)) * expr!(ID::new(0)),
-            expr!(ID::new(0)) * (expr!(ID::new(1)) - expr!(1.0)),
-		));
-		// y * x - x = x * (y - 1)
-        rules.push((
-            expr!(ID::new(1)) *  expr!(ID::new(0)) - expr!(ID::new(0)),
-            expr!(ID::new(0)) * (expr!(ID::new(1)) - expr!(1.0)),
-		));
+            expr!(ID::new(0)) * (expr!(ID::new(1)) - expr!(1)),
+		).with_name("factor difference").with_group("factoring"));
 
 		// some power rules
 
-        // x^y * x^z = x^(y+z)
-        rules.push((
+        // x^y * x^z = x^(y+z) for natural y and z or nonzero x
+        rules.push(Rule::new(
+            expr!(ID::new(0)).pow(expr!(ID::new(1))) * expr!(ID::new(0)).pow(expr!(ID::new(2))),
+            expr!(ID::new(0)).pow(expr!(ID::new(1)) + expr!(ID::new(2))),
+        ).with_guard(Guard::Natural(ID::new(1))).with_guard(Guard::Natural(ID::new(2))).with_name("add exponents").with_group("powers"));
+        rules.push(Rule::new(
             expr!(ID::new(0)).pow(expr!(ID::new(1))) * expr!(ID::new(0)).pow(expr!(ID::new(2))),
             expr!(ID::new(0)).pow(expr!(ID::new(1)) + expr!(ID::new(2))),
-        ));
+        ).with_guard(Guard::Nonzero(ID::new(0))).with_name("add exponents").with_group("powers"));
+
+		// distributing, the opposite of factoring and so disabled by default
+
+        // x * (y + z) = x * y + x * z
+        rules.push(Rule::new(
+            expr!(ID::new(0)) * (expr!(ID::new(1)) + expr!(ID::new(2))),
+            expr!(ID::new(0)) * expr!(ID::new(1)) + expr!(ID::new(0)) * expr!(ID::new(2)),
+        ).with_name("distribute").with_group("expanding"));
+        // x * (y - z) = x * y - x * z
+        rules.push(Rule::new(
+            expr!(ID::new(0)) * (expr!(ID::new(1)) - expr!(ID::new(2))),
+            expr!(ID::new(0)) * expr!(ID::new(1)) - expr!(ID::new(0)) * expr!(ID::new(2)),
+        ).with_name("distribute").with_group("expanding"));
 
 		// functions
 
         // sin(x)^2 + cos(x)^2 = 1
-        rules.push((
-            Expr::function("sin", vec![expr!(ID::new(0))]).pow(expr!(2.0)) + Expr::function("cos", vec![expr!(ID::new(0))]).pow(expr!(2.0)),
-            expr!(1.0),
-        ));
+        rules.push(Rule::new(
+            Expr::function("sin", vec![expr!(ID::new(0))]).pow(expr!(2)) + Expr::function("cos", vec![expr!(ID::new(0))]).pow(expr!(2)),
+            expr!(1),
+        ).with_name("pythagorean").with_group("trig"));
         // log(e, x) = ln(x)
-        rules.push((
+        rules.push(Rule::new(
             Expr::function("log", vec![expr!("e"), expr!(ID::new(0))]),
             Expr::function("ln", vec![expr!(ID::new(0))]),
-        ));
+        ).with_name("natural log").with_group("logs"));
+
+        Simplifier {
+			rules,
+			strategy: Strategy::BottomUp,
+			disabled: vec![String::from("expanding"), String::from("quotients")],
+			cost: Cost::NodeCount,
+			node_limit: 10000,
+		}
+    }
+}
 
-        Simplifier { rules }
+impl Default for Simplifier {
+    fn default() -> Self {
+        Self::new()
     }
 }
@@ -0,0 +1,324 @@
+use std::fmt::Display;
+
+use crate::{roots, Engine, Expr, Number, Operator, SymErr, Tree};
+
+/// Something that has to hold for a [`Solution`] to be valid
+#[derive(Debug, PartialEq, Clone)]
+pub enum Condition {
+    NonZero(Expr),
+    NonNegative(Expr),
+    Positive(Expr),
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct Solution {
+    pub value: Expr,
+    pub conditions: Vec<Condition>,
+}
+
+impl Condition {
+    /// `Some` if the condition could be decided, `None` if it depends on unknown symbols
+    pub fn holds(&self, engine: &Engine) -> Option<bool> {
+        let value = match self.expr().eval(engine) {
+            Ok(Expr::Number(n)) => f64::from(n),
+            _ => return None,
+        };
+
+        Some(match self {
+            Condition::NonZero(_) => value != 0.0,
+            Condition::NonNegative(_) => value >= 0.0,
+            Condition::Positive(_) => value > 0.0,
+        })
+    }
+
+    pub fn print_latex(&self) -> String {
+        match self {
+            Condition::NonZero(e) => format!("{} \\neq 0", e.print_latex()),
+            Condition::NonNegative(e) => format!("{} \\geq 0", e.print_latex()),
+            Condition::Positive(e) => format!("{} > 0", e.print_latex()),
+        }
+    }
+
+    pub fn expr(&self) -> &Expr {
+        match self {
+            Condition::NonZero(e) | Condition::NonNegative(e) | Condition::Positive(e) => e,
+        }
+    }
+
+    fn map(self, f: impl FnOnce(Expr) -> Expr) -> Self {
+        match self {
+            Condition::NonZero(e) => Condition::NonZero(f(e)),
+            Condition::NonNegative(e) => Condition::NonNegative(f(e)),
+            Condition::Positive(e) => Condition::Positive(f(e)),
+        }
+    }
+}
+
+impl Display for Condition {
+    fn fmt(&self, fmt: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Condition::NonZero(e) => write!(fmt, "{} != 0", e),
+            Condition::NonNegative(e) => write!(fmt, "{} >= 0", e),
+            Condition::Positive(e) => write!(fmt, "{} > 0", e),
+        }
+    }
+}
+
+impl Display for Solution {
+    fn fmt(&self, fmt: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(fmt, "{}", self.value)?;
+        for (i, condition) in self.conditions.iter().enumerate() {
+            write!(
+                fmt,
+                "{}{}",
+                if i == 0 { ", if " } else { " and " },
+                condition
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// splits `lhs = rhs` into its sides, anything else is treated as `expr = 0`
+pub(crate) fn sides(expr: &Expr) -> (Expr, Expr) {
+    match expr {
+        Expr::Operator(Tree {
+            value: Operator::Eq,
+            next: Some(next),
+        }) => (next[0].as_ref().clone(), next[1].as_ref().clone()),
+        _ => (expr.clone(), Expr::from(0)),
+    }
+}
+
+fn is_exact(expr: &Expr) -> bool {
+    match expr {
+        Expr::Number(Number::Irrational(_)) => false,
+        Expr::Function(Tree { next, .. }) | Expr::Operator(Tree { next, .. }) => {
+            next.iter().flatten().all(|e| is_exact(e))
+        }
+        _ => true,
+    }
+}
+
+/// simplifies and evaluates what can be evaluated without losing exactness
+fn clean(engine: &Engine, expr: Expr) -> Expr {
+    let simplified = expr.simplify(engine);
+    match simplified.eval(engine) {
+        Ok(evaluated) if is_exact(&evaluated) || !is_exact(&simplified) => evaluated,
+        _ => simplified,
+    }
+}
+
+fn isolate(
+    engine: &Engine,
+    lhs: &Expr,
+    rhs: Expr,
+    var: &str,
+    conditions: Vec<Condition>,
+) -> Result<Vec<Solution>, SymErr> {
+    let (value, next) = match lhs {
+        Expr::Variable(v) if v == var => {
+            return Ok(vec![Solution {
+                value: rhs,
+                conditions,
+            }])
+        }
+        Expr::Operator(o) => (o.value, o.next.as_ref().unwrap()),
+        Expr::Function(f) => {
+            let next = f.next.as_ref().unwrap();
+            let inverse = engine
+                .inverses
+                .get(f.value.as_str())
+                .ok_or(SymErr::Unsolvable)?;
+            if next.len() != 1 {
+                return Err(SymErr::Unsolvable);
+            }
+
+            return isolate(engine, &next[0], inverse(rhs), var, conditions);
+        }
+        _ => return Err(SymErr::Unsolvable),
+    };
+
+    if value.is_unary() {
+        let rhs = if value == Operator::Neg { -rhs } else { rhs };
+        return isolate(engine, &next[0], rhs, var, conditions);
+    }
+
+    let (a, b) = (next[0].as_ref().clone(), next[1].as_ref().clone());
+    let (in_a, in_b) = (a.contains(var), b.contains(var));
+    if in_a && in_b {
+        return Err(SymErr::Unsolvable);
+    }
+
+    let with = |condition: Condition| {
+        let mut conditions = conditions.clone();
+        conditions.push(condition);
+        conditions
+    };
+
+    match value {
+        Operator::Add if in_a => isolate(engine, &a, rhs - b, var, conditions),
+        Operator::Add => isolate(engine, &b, rhs - a, var, conditions),
+        Operator::Sub if in_a => isolate(engine, &a, rhs + b, var, conditions),
+        Operator::Sub => isolate(engine, &b, a - rhs, var, conditions),
+        Operator::Mul if in_a => isolate(
+            engine,
+            &a,
+            rhs / b.clone(),
+            var,
+            with(Condition::NonZero(b)),
+        ),
+        Operator::Mul => isolate(
+            engine,
+            &b,
+            rhs / a.clone(),
+            var,
+            with(Condition::NonZero(a)),
+        ),
+        Operator::Div if in_a => isolate(
+            engine,
+            &a,
+            rhs * b.clone(),
+            var,
+            with(Condition::NonZero(b)),
+        ),
+        Operator::Div => isolate(
+            engine,
+            &b,
+            a / rhs.clone(),
+            var,
+            with(Condition::NonZero(rhs)),
+        ),
+        Operator::Pow if in_a => {
+            let exponent = b.eval(engine).or(Err(SymErr::Unsolvable))?;
+            match exponent {
+                Expr::Number(Number::Rational(0, _)) => Err(SymErr::Unsolvable),
+                // an even numerator loses the sign, the denominator is odd and keeps it
+                Expr::Number(Number::Rational(n, d)) if n % 2 == 0 => {
+                    let root = rhs
+                        .clone()
+                        .pow(Expr::from(Number::Rational(d * n.signum(), n.abs())));
+                    let conditions = if n < 0 {
+                        with(Condition::Positive(rhs))
+                    } else {
+                        with(Condition::NonNegative(rhs))
+                    };
+
+                    let mut solutions =
+                        isolate(engine, &a, -root.clone(), var, conditions.clone())?;
+                    solutions.append(&mut isolate(engine, &a, root, var, conditions)?);
+                    Ok(solutions)
+                }
+                Expr::Number(Number::Rational(n, 1)) => {
+                    let root = rhs
+                        .clone()
+                        .pow(Expr::from(Number::Rational(n.signum(), n.abs())));
+                    let conditions = if n < 0 {
+                        with(Condition::NonZero(rhs))
+                    } else {
+                        conditions.clone()
+                    };
+                    isolate(engine, &a, root, var, conditions)
+                }
+                _ => {
+                    let root = rhs.clone().pow(Expr::from(1) / exponent);
+                    isolate(engine, &a, root, var, with(Condition::NonNegative(rhs)))
+                }
+            }
+        }
+        Operator::Pow => {
+            // a^b = rhs <=> b = ln(rhs) / ln(a)
+            let mut conditions = with(Condition::Positive(a.clone()));
+            conditions.push(Condition::NonZero(a.clone() - Expr::from(1)));
+            conditions.push(Condition::Positive(rhs.clone()));
+
+            let value = Expr::function("ln", vec![rhs]) / Expr::function("ln", vec![a]);
+            isolate(engine, &b, value, var, conditions)
+        }
+        _ => Err(SymErr::Unsolvable),
+    }
+}
+
+/// the real roots of `expr` if it is a polynomial in `var`
+fn polynomial(engine: &Engine, expr: &Expr, var: &str) -> Result<Vec<Solution>, SymErr> {
+    let roots = match roots::roots(engine, expr, var) {
+        Err(SymErr::Inconvertible) => return Err(SymErr::Unsolvable),
+        roots => roots?,
+    };
+
+    Ok(roots
+        .into_iter()
+        .filter(|root| root.approximate().is_some_and(|c| c.is_real(1e-9)))
+        .map(|value| Solution {
+            value,
+            conditions: Vec::new(),
+        })
+        .collect())
+}
+
+/// Solves `expr` for `var` by inverting the operations around it, polynomials that can not be
+/// inverted directly are solved with [`roots::roots`].
+/// `expr` is either an equation `lhs = rhs` or an expression that is assumed to equal zero,
+/// inequalities are solved by [`crate::inequality::solve`].
+pub fn solve(engine: &Engine, expr: &Expr, var: &str) -> Result<Vec<Solution>, SymErr> {
+    if let Expr::Operator(o) = expr {
+        if o.value.is_relation() && o.value != Operator::Eq {
+            return Err(SymErr::InvalidOP);
+        }
+    }
+
+    let (lhs, rhs) = sides(expr);
+    let difference = lhs.clone() - rhs.clone();
+
+    let (lhs, rhs) = match (lhs.contains(var), rhs.contains(var)) {
+        (false, false) => return Err(SymErr::Unsolvable),
+        (true, false) => (lhs.simplify(engine), rhs),
+        (false, true) => (rhs.simplify(engine), lhs),
+        (true, true) => ((lhs - rhs).simplify(engine), Expr::from(0)),
+    };
+
+    if engine.debugging {
+        println!("Solving {} = {} for {}", lhs, rhs, var);
+    }
+
+    let candidates = match isolate(engine, &lhs, rhs, var, Vec::new()) {
+        Err(SymErr::Unsolvable) => polynomial(engine, &difference, var)?,
+        isolated => isolated?
+            .into_iter()
+            .map(|solution| Solution {
+                value: clean(engine, solution.value),
+                ..solution
+            })
+            .collect(),
+    };
+
+    let mut solutions = Vec::<Solution>::new();
+    for solution in candidates {
+        let value = solution.value;
+
+        let mut conditions = Vec::new();
+        let mut valid = true;
+        for condition in solution.conditions {
+            let condition = condition.map(|e| clean(engine, e));
+            match condition.holds(engine) {
+                Some(true) => (),
+                Some(false) => valid = false,
+                None if conditions.contains(&condition) => (),
+                None => conditions.push(condition),
+            }
+        }
+
+        let solution = Solution { value, conditions };
+        if valid && !solutions.contains(&solution) {
+            solutions.push(solution);
+        }
+    }
+
+    if engine.debugging {
+        solutions
+            .iter()
+            .for_each(|solution| println!("Solution: {} = {}", var, solution));
+    }
+
+    Ok(solutions)
+}
@@ -0,0 +1,403 @@
+use std::fmt::Display;
+
+use crate::{bigint::BigInt, polynomial::Polynomial, Number, SymErr};
+
+/// integer polynomial, lowest power first without leading zeros
+pub(crate) type Zx = Vec<BigInt>;
+
+/// Sturm sequence of the square-free part of a polynomial: the number of sign changes along it
+/// drops by one at every real root and nowhere else, so counting roots is exact. The sequence is
+/// kept in arbitrary precision integers, no coefficient or evaluation can overflow.
+#[derive(Debug, PartialEq, Clone)]
+pub struct Sturm {
+    sequence: Vec<Zx>,
+}
+
+/// `numerator / 2^exponent`, with an odd numerator unless the exponent is zero
+#[derive(Debug, PartialEq, Eq, Clone)]
+struct Dyadic {
+    numerator: BigInt,
+    exponent: u64,
+}
+
+/// An isolating interval `(lower, upper]` with exactly one real root, or the root itself when
+/// both ends are equal. The ends are exact dyadic rationals of any precision.
+#[derive(Debug, PartialEq, Clone)]
+pub struct RootInterval {
+    lower: Dyadic,
+    upper: Dyadic,
+}
+
+pub(crate) fn trim(mut p: Zx) -> Zx {
+    while p.last().is_some_and(BigInt::is_zero) {
+        p.pop();
+    }
+    p
+}
+
+fn degree(p: &Zx) -> usize {
+    p.len().saturating_sub(1)
+}
+
+/// divided by the positive gcd of the coefficients
+pub(crate) fn primitive(p: Zx) -> Zx {
+    let content = p.iter().fold(BigInt::zero(), |acc, c| acc.gcd(c));
+    if content.is_zero() {
+        return p;
+    }
+    p.iter().map(|c| c.div_rem(&content).0).collect()
+}
+
+/// a positive multiple of `p` with integer coefficients
+pub(crate) fn from_polynomial(p: &Polynomial) -> Result<Zx, SymErr> {
+    let mut denominator = BigInt::from(1i64);
+    for c in p.coefficients() {
+        match c {
+            Number::Rational(_, d) => {
+                let d = BigInt::from(*d).abs();
+                denominator = &denominator.div_rem(&denominator.gcd(&d)).0 * &d;
+            }
+            Number::Irrational(_) => return Err(SymErr::Inconvertible),
+        }
+    }
+    let integer = p
+        .coefficients()
+        .iter()
+        .map(|c| match c {
+            Number::Rational(n, d) => &BigInt::from(*n) * &denominator.div_rem(&BigInt::from(*d)).0,
+            Number::Irrational(_) => unreachable!(),
+        })
+        .collect();
+    Ok(primitive(trim(integer)))
+}
+
+fn derivative(p: &Zx) -> Zx {
+    trim(
+        p.iter()
+            .enumerate()
+            .skip(1)
+            .map(|(i, c)| c * &BigInt::from(i as i64))
+            .collect(),
+    )
+}
+
+/// a positive multiple of the remainder of `a / b`
+fn remainder(a: &Zx, b: &Zx) -> Zx {
+    let lc = b.last().unwrap();
+    let mut r = a.clone();
+    let mut multiplications = 0;
+    while !r.is_empty() && r.len() >= b.len() {
+        // lc * r - r_n x^shift * b cancels the leading term
+        let shift = r.len() - b.len();
+        let top = r.last().unwrap().clone();
+        r = r.iter().map(|c| c * lc).collect();
+        for (i, c) in b.iter().enumerate() {
+            r[i + shift] = &r[i + shift] - &(&top * c);
+        }
+        r = trim(r);
+        multiplications += 1;
+    }
+    if lc.signum() < 0 && multiplications % 2 == 1 {
+        r = r.iter().map(|c| -c).collect();
+    }
+    primitive(r)
+}
+
+/// `a / b` for a primitive `b` that divides `a`, which is then an integer polynomial
+fn exact_quotient(a: &Zx, b: &Zx) -> Zx {
+    let lc = b.last().unwrap();
+    let mut r = a.clone();
+    let mut quotient = vec![BigInt::zero(); (a.len() + 1).saturating_sub(b.len())];
+    while !r.is_empty() && r.len() >= b.len() {
+        let shift = r.len() - b.len();
+        let q = r.last().unwrap().div_rem(lc).0;
+        for (i, c) in b.iter().enumerate() {
+            r[i + shift] = &r[i + shift] - &(&q * c);
+        }
+        quotient[shift] = q;
+        r = trim(r);
+    }
+    trim(quotient)
+}
+
+/// the sign of `p(n / d)` for a positive `d`, from `d^deg p(n / d)` evaluated in integers
+fn sign_at(p: &Zx, n: &BigInt, d: &BigInt) -> i8 {
+    let mut power = BigInt::from(1i64);
+    let mut acc = p.last().cloned().unwrap_or_else(BigInt::zero);
+    for c in p.iter().rev().skip(1) {
+        power = &power * d;
+        acc = &(&acc * n) + &(c * &power);
+    }
+    acc.signum()
+}
+
+/// the numerator and positive denominator of a rational
+fn fraction(x: &Number) -> Result<(BigInt, BigInt), SymErr> {
+    match x {
+        Number::Rational(n, d) if *d < 0 => Ok((-&BigInt::from(*n), -&BigInt::from(*d))),
+        Number::Rational(n, d) => Ok((BigInt::from(*n), BigInt::from(*d))),
+        Number::Irrational(_) => Err(SymErr::Inconvertible),
+    }
+}
+
+fn variations(signs: impl Iterator<Item = i8>) -> usize {
+    let signs = signs.filter(|&s| s != 0).collect::<Vec<_>>();
+    signs.windows(2).filter(|w| w[0] != w[1]).count()
+}
+
+impl Sturm {
+    /// `p`, `p'` and then the negated remainders, all divided by their last element (the gcd of
+    /// `p` and `p'`) so repeated roots count once. [`SymErr::Inconvertible`] if the coefficients
+    /// are not rational, [`SymErr::Undefined`] for the zero polynomial.
+    pub fn new(p: &Polynomial) -> Result<Self, SymErr> {
+        if p.is_zero() {
+            return Err(SymErr::Undefined);
+        }
+
+        let p = from_polynomial(p)?;
+        let mut sequence = vec![p.clone()];
+        let d = primitive(derivative(&p));
+        if !d.is_empty() {
+            sequence.push(d);
+        }
+        while sequence.len() > 1 {
+            let n = sequence.len();
+            let r = remainder(&sequence[n - 2], &sequence[n - 1]);
+            if r.is_empty() {
+                break;
+            }
+            sequence.push(r.iter().map(|c| -c).collect());
+        }
+
+        let gcd = sequence.last().unwrap().clone();
+        if gcd.len() > 1 {
+            sequence = sequence.iter().map(|q| exact_quotient(q, &gcd)).collect();
+        }
+        Ok(Self { sequence })
+    }
+
+    /// the square-free polynomial with integer coefficients the sequence starts with, it has the
+    /// same real roots
+    pub fn polynomial(&self) -> Polynomial {
+        Polynomial::new(
+            self.sequence[0]
+                .iter()
+                .map(|c| match c.to_i64() {
+                    Some(c) => Number::Rational(c, 1),
+                    None => Number::Irrational(c.to_f64()),
+                })
+                .collect(),
+        )
+    }
+
+    fn variations_at(&self, n: &BigInt, d: &BigInt) -> usize {
+        variations(self.sequence.iter().map(|q| sign_at(q, n, d)))
+    }
+
+    /// at `inf`, or at `-inf` if not `positive`
+    fn variations_at_infinity(&self, positive: bool) -> usize {
+        variations(self.sequence.iter().map(|q| {
+            let s = q.last().unwrap().signum();
+            if !positive && degree(q) % 2 == 1 {
+                -s
+            } else {
+                s
+            }
+        }))
+    }
+
+    /// is `x` a root, [`SymErr::Inconvertible`] if it is not rational
+    pub fn is_root(&self, x: &Number) -> Result<bool, SymErr> {
+        let (n, d) = fraction(x)?;
+        Ok(sign_at(&self.sequence[0], &n, &d) == 0)
+    }
+
+    /// the number of distinct real roots in `(lower, upper]`, [`SymErr::Inconvertible`] if
+    /// either end is not rational
+    pub fn count(&self, lower: &Number, upper: &Number) -> Result<usize, SymErr> {
+        let (ln, ld) = fraction(lower)?;
+        let (un, ud) = fraction(upper)?;
+        Ok(self
+            .variations_at(&ln, &ld)
+            .saturating_sub(self.variations_at(&un, &ud)))
+    }
+
+    /// the number of distinct real roots
+    pub fn count_all(&self) -> usize {
+        self.variations_at_infinity(false) - self.variations_at_infinity(true)
+    }
+
+    fn count_dyadic(&self, lower: &Dyadic, upper: &Dyadic) -> usize {
+        let (ln, ld) = lower.fraction();
+        let (un, ud) = upper.fraction();
+        self.variations_at(&ln, &ld)
+            .saturating_sub(self.variations_at(&un, &ud))
+    }
+
+    fn is_root_dyadic(&self, x: &Dyadic) -> bool {
+        let (n, d) = x.fraction();
+        sign_at(&self.sequence[0], &n, &d) == 0
+    }
+
+    /// every real root is in `(-bound, bound)`, from Cauchy's bound `1 + max |a_i / a_n|`
+    fn bound(&self) -> BigInt {
+        let p = &self.sequence[0];
+        let leading = p.last().unwrap().abs();
+        let one = BigInt::from(1i64);
+        let max = p
+            .iter()
+            .map(|c| (&(&c.abs() + &leading) - &one).div_rem(&leading).0)
+            .max()
+            .unwrap();
+        &max + &one
+    }
+
+    /// Isolating intervals for every distinct real root, from left to right, found by bisecting
+    /// `(-bound, bound]` until every part has one root
+    pub fn isolate(&self) -> Vec<RootInterval> {
+        let bound = self.bound();
+        let mut intervals = Vec::new();
+        let mut pending = vec![(
+            Dyadic::new(-&bound, 0),
+            Dyadic::new(bound, 0),
+            self.count_all(),
+        )];
+        while let Some((lower, upper, count)) = pending.pop() {
+            match count {
+                0 => {}
+                1 => intervals.push(RootInterval { lower, upper }),
+                _ => {
+                    // the middle, or else a point a bit off it that is not a root
+                    let mut split = lower.middle(&upper);
+                    while self.is_root_dyadic(&split) {
+                        split = split.middle(&upper);
+                    }
+
+                    let left = self.count_dyadic(&lower, &split);
+                    pending.push((split.clone(), upper, count - left));
+                    pending.push((lower, split, left));
+                }
+            }
+        }
+        intervals
+    }
+}
+
+impl Dyadic {
+    fn new(numerator: BigInt, exponent: u64) -> Self {
+        let shift = numerator.trailing_zeros().min(exponent);
+        Self {
+            numerator: numerator.shr(shift),
+            exponent: exponent - shift,
+        }
+    }
+
+    /// the numerators of both over the same power of two
+    fn common(&self, other: &Self) -> (BigInt, BigInt, u64) {
+        let exponent = self.exponent.max(other.exponent);
+        (
+            self.numerator.shl(exponent - self.exponent),
+            other.numerator.shl(exponent - other.exponent),
+            exponent,
+        )
+    }
+
+    fn middle(&self, other: &Self) -> Self {
+        let (a, b, exponent) = self.common(other);
+        Self::new(&a + &b, exponent + 1)
+    }
+
+    fn fraction(&self) -> (BigInt, BigInt) {
+        (
+            self.numerator.clone(),
+            BigInt::from(1i64).shl(self.exponent),
+        )
+    }
+
+    /// exact if it fits in a rational
+    fn to_number(&self) -> Number {
+        match self.numerator.to_i64() {
+            Some(n) if self.exponent < 63 => Number::Rational(n, 1 << self.exponent),
+            _ => Number::Irrational(self.to_f64()),
+        }
+    }
+
+    fn to_f64(&self) -> f64 {
+        // only the top bits matter, and huge numerators would not fit in a float
+        let excess = self.numerator.bits().saturating_sub(64);
+        let scale = excess as f64 - self.exponent as f64;
+        self.numerator.shr(excess).to_f64() * 2f64.powf(scale)
+    }
+}
+
+impl Display for Dyadic {
+    fn fmt(&self, fmt: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.exponent == 0 {
+            write!(fmt, "{}", self.numerator)
+        } else {
+            write!(
+                fmt,
+                "{}/{}",
+                self.numerator,
+                BigInt::from(1i64).shl(self.exponent)
+            )
+        }
+    }
+}
+
+impl RootInterval {
+    /// the lower end, exact if it fits in a rational
+    pub fn lower(&self) -> Number {
+        self.lower.to_number()
+    }
+
+    /// the upper end, exact if it fits in a rational
+    pub fn upper(&self) -> Number {
+        self.upper.to_number()
+    }
+
+    pub fn is_exact(&self) -> bool {
+        self.lower == self.upper
+    }
+
+    /// the middle of the interval
+    pub fn approximate(&self) -> f64 {
+        self.lower.middle(&self.upper).to_f64()
+    }
+
+    /// is the interval at most `2^-bits` wide
+    pub fn within(&self, bits: u64) -> bool {
+        let (lower, upper, exponent) = self.lower.common(&self.upper);
+        let width = &upper - &lower;
+        if exponent >= bits {
+            width <= BigInt::from(1i64).shl(exponent - bits)
+        } else {
+            width.shl(bits - exponent) <= BigInt::from(1i64)
+        }
+    }
+
+    /// Bisects until the interval is at most `2^-bits` wide, or the root is hit exactly
+    pub fn refine(&mut self, sturm: &Sturm, bits: u64) {
+        while !self.is_exact() && !self.within(bits) {
+            let middle = self.lower.middle(&self.upper);
+            if sturm.is_root_dyadic(&middle) {
+                self.lower = middle.clone();
+                self.upper = middle;
+            } else if sturm.count_dyadic(&self.lower, &middle) == 1 {
+                self.upper = middle;
+            } else {
+                self.lower = middle;
+            }
+        }
+    }
+}
+
+impl Display for RootInterval {
+    fn fmt(&self, fmt: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.is_exact() {
+            write!(fmt, "{{{}}}", self.lower)
+        } else {
+            write!(fmt, "({}, {}]", self.lower, self.upper)
+        }
+    }
+}
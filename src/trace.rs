@@ -0,0 +1,111 @@
+//! Step by step records of [`Expr::simplify_with_trace`] and [`Expr::eval_with_trace`]
+
+use std::fmt::Display;
+
+use crate::{solver::Condition, Expr, Tree};
+
+/// One rewrite of the subexpression at `path`
+#[derive(Debug, PartialEq, Clone)]
+pub struct Step {
+    /// the name of the rule, `evaluate` for operators or the name of an evaluated function
+    pub rule: String,
+    /// the operand indices from the root down to the subexpression
+    pub path: Vec<usize>,
+    pub before: Expr,
+    pub after: Expr,
+    /// where `after` may not equal `before`, only cancelling excludes any points
+    pub conditions: Vec<Condition>,
+}
+
+/// The steps from `input` to the result, every step rewrites the result of the ones before it
+#[derive(Debug, PartialEq, Clone)]
+pub struct Trace {
+    pub input: Expr,
+    pub steps: Vec<Step>,
+}
+
+/// `expr` with the subexpression at `path` replaced by `with`
+fn replace_at(expr: &Expr, path: &[usize], with: &Expr) -> Expr {
+    let Some((&first, path)) = path.split_first() else {
+        return with.clone();
+    };
+    let mut expr = expr.clone();
+    if let Expr::Function(Tree {
+        next: Some(next), ..
+    })
+    | Expr::Operator(Tree {
+        next: Some(next), ..
+    }) = &mut expr
+    {
+        *next[first] = replace_at(&next[first], path, with);
+    }
+    expr
+}
+
+impl Trace {
+    /// the whole expression after every step, starting with the input
+    pub fn forms(&self) -> Vec<Expr> {
+        let mut forms = vec![self.input.clone()];
+        for step in &self.steps {
+            let last = forms.last().unwrap();
+            forms.push(replace_at(last, &step.path, &step.after));
+        }
+        forms
+    }
+
+    pub fn result(&self) -> Expr {
+        self.forms().pop().unwrap()
+    }
+
+    /// the input and then a line per step with the whole expression and what changed
+    pub fn print(&self) -> String {
+        let forms = self.forms();
+        let mut lines = vec![forms[0].print()];
+        for (step, form) in self.steps.iter().zip(&forms[1..]) {
+            let conditions = step
+                .conditions
+                .iter()
+                .enumerate()
+                .map(|(i, c)| format!("{}{}", if i == 0 { ", if " } else { " and " }, c))
+                .collect::<String>();
+            lines.push(format!(
+                "= {}    ({}: {} -> {}{})",
+                form, step.rule, step.before, step.after, conditions
+            ));
+        }
+        lines.join("\n")
+    }
+
+    /// the lines of [`Trace::print`] aligned at the equals signs
+    pub fn print_latex(&self) -> String {
+        let forms = self.forms();
+        let mut lines = vec![format!("& {}", forms[0].print_latex())];
+        for (step, form) in self.steps.iter().zip(&forms[1..]) {
+            let conditions = step
+                .conditions
+                .iter()
+                .enumerate()
+                .map(|(i, c)| {
+                    let joint = if i == 0 { ", if " } else { " and " };
+                    format!("\\text{{{}}} {}", joint, c.print_latex())
+                })
+                .collect::<String>();
+            lines.push(format!(
+                "&= {} && \\text{{{}}}{}",
+                form.print_latex(),
+                step.rule,
+                conditions
+            ));
+        }
+        format!(
+            "\\begin{{aligned}}\n{}\n\\end{{aligned}}",
+            lines.join(" \\\\\n")
+        )
+    }
+}
+
+impl Display for Trace {
+    fn fmt(&self, fmt: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(fmt, "{}", self.print())
+    }
+}
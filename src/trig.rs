@@ -0,0 +1,192 @@
+//! Trigonometric identities for [`Expr::trigsimp`], [`Expr::expand_trig`] and the conversions to
+//! and from exponential form
+
+use crate::{
+    egraph::Cost,
+    expand::expand,
+    simplifier::{Rule, Simplifier, Strategy},
+    Engine, Expr, Number, Operator, Tree,
+};
+
+/// equalities that [`Simplifier::trig`] saturates with, some only get shorter together with
+/// others or once their arguments are combined
+const IDENTITIES: &str = "
+[trig]
+pythagorean: sin(x_)^2 + cos(x_)^2 -> 1
+pythagorean: a_*sin(x_)^2 + a_*cos(x_)^2 -> a_
+pythagorean: 1 - sin(x_)^2 -> cos(x_)^2
+pythagorean: 1 - cos(x_)^2 -> sin(x_)^2
+pythagorean: sin(x_)^2 - 1 -> -cos(x_)^2
+pythagorean: cos(x_)^2 - 1 -> -sin(x_)^2
+pythagorean: 1 + tan(x_)^2 -> cos(x_)^(-2)
+tangent: sin(x_)*cos(x_)^(-1) -> tan(x_)
+odd: sin(-x_) -> -sin(x_)
+even: cos(-x_) -> cos(x_)
+odd: tan(-x_) -> -tan(x_)
+double angle: 2*sin(x_)*cos(x_) -> sin(2*x_)
+double angle: cos(x_)^2 - sin(x_)^2 -> cos(2*x_)
+double angle: 2*cos(x_)^2 - 1 -> cos(2*x_)
+double angle: 1 - 2*sin(x_)^2 -> cos(2*x_)
+half angle: (1 - cos(x_))/2 -> sin(x_/2)^2
+half angle: (1 + cos(x_))/2 -> cos(x_/2)^2
+sum to product: sin(a_) + sin(b_) -> 2*sin((a_+b_)/2)*cos((a_-b_)/2)
+sum to product: sin(a_) - sin(b_) -> 2*cos((a_+b_)/2)*sin((a_-b_)/2)
+sum to product: cos(a_) + cos(b_) -> 2*cos((a_+b_)/2)*cos((a_-b_)/2)
+sum to product: cos(a_) - cos(b_) -> -2*sin((a_+b_)/2)*sin((a_-b_)/2)
+";
+
+/// sines and cosines of sums and of natural multiples split up, the opposite of the double angle
+/// identities
+const EXPANSION: &str = "
+[expanding trig]
+angle sum: sin(a_ + b_) -> sin(a_)*cos(b_) + cos(a_)*sin(b_)
+angle sum: cos(a_ + b_) -> cos(a_)*cos(b_) - sin(a_)*sin(b_)
+angle difference: sin(a_ - b_) -> sin(a_)*cos(b_) - cos(a_)*sin(b_)
+angle difference: cos(a_ - b_) -> cos(a_)*cos(b_) + sin(a_)*sin(b_)
+double angle: sin(2*x_) -> 2*sin(x_)*cos(x_)
+double angle: cos(2*x_) -> cos(x_)^2 - sin(x_)^2
+multiple: sin(n_num*x_) -> sin((n_num-1)*x_)*cos(x_)+cos((n_num-1)*x_)*sin(x_) if natural(n_num)
+multiple: cos(n_num*x_) -> cos((n_num-1)*x_)*cos(x_)-sin((n_num-1)*x_)*sin(x_) if natural(n_num)
+odd: sin(-x_) -> -sin(x_)
+even: cos(-x_) -> cos(x_)
+";
+
+const TO_EXPONENTIAL: &str = "
+[exponential]
+sin(x_) -> (e^(ⅈ*x_) - e^(-ⅈ*x_))/(2*ⅈ)
+cos(x_) -> (e^(ⅈ*x_) + e^(-ⅈ*x_))/2
+tan(x_) -> (e^(ⅈ*x_) - e^(-ⅈ*x_))/(ⅈ*(e^(ⅈ*x_) + e^(-ⅈ*x_)))
+";
+
+/// tried from the root down, so whole quotients are converted before their sums are
+const FROM_EXPONENTIAL: &str = "
+[exponential]
+(e^(ⅈ*x_) - e^(-ⅈ*x_))/(ⅈ*(e^(ⅈ*x_) + e^(-ⅈ*x_))) -> tan(x_)
+(e^(ⅈ*x_) - e^(-ⅈ*x_))/(2*ⅈ) -> sin(x_)
+(e^(ⅈ*x_) + e^(-ⅈ*x_))/2 -> cos(x_)
+e^(ⅈ*x_) - e^(-ⅈ*x_) -> 2*ⅈ*sin(x_)
+e^(ⅈ*x_) + e^(-ⅈ*x_) -> 2*cos(x_)
+e^(ⅈ*x_) -> cos(x_) + ⅈ*sin(x_)
+";
+
+/// the rules in `text`, which only use the built in functions
+fn simplifier(text: &str) -> Simplifier {
+    Simplifier::from_str(&Engine::new().with_functions(), text).unwrap()
+}
+
+/// see [`IDENTITIES`]
+pub(crate) fn identities() -> Vec<Rule> {
+    simplifier(IDENTITIES).rules().to_vec()
+}
+
+/// only sums, products, natural powers and quotients by numbers, which [`expand`] can combine
+/// without dropping points where `expr` is undefined
+fn is_polynomial(expr: &Expr) -> bool {
+    match expr {
+        Expr::Number(_) | Expr::Variable(_) => true,
+        Expr::Operator(Tree {
+            value: Operator::Div,
+            next: Some(next),
+        }) => is_polynomial(&next[0]) && matches!(*next[1], Expr::Number(_)),
+        Expr::Operator(Tree {
+            value: Operator::Pow,
+            next: Some(next),
+        }) => {
+            is_polynomial(&next[0])
+                && matches!(*next[1], Expr::Number(Number::Rational(n, 1)) if n >= 0)
+        }
+        Expr::Operator(Tree {
+            value: Operator::Add | Operator::Sub | Operator::Mul | Operator::Pos | Operator::Neg,
+            next: Some(next),
+        }) => next.iter().all(|e| is_polynomial(e)),
+        _ => false,
+    }
+}
+
+/// `expr` with the arguments of its functions combined by [`expand`] where that makes them
+/// shorter, so `sin((x+y+x-y)/2)` becomes `sin(x)`
+fn combine_arguments(expr: &Expr) -> Expr {
+    match expr {
+        Expr::Function(Tree {
+            value,
+            next: Some(next),
+        }) => Expr::Function(Tree {
+            value: value.clone(),
+            next: Some(
+                next.iter()
+                    .map(|e| {
+                        let e = combine_arguments(e);
+                        let expanded = expand(&e, false);
+                        let shorter = Cost::NodeCount.cost(&expanded) < Cost::NodeCount.cost(&e);
+                        Box::new(if is_polynomial(&e) && shorter {
+                            expanded
+                        } else {
+                            e
+                        })
+                    })
+                    .collect(),
+            ),
+        }),
+        Expr::Operator(Tree {
+            value,
+            next: Some(next),
+        }) => Expr::Operator(Tree {
+            value: *value,
+            next: Some(
+                next.iter()
+                    .map(|e| Box::new(combine_arguments(e)))
+                    .collect(),
+            ),
+        }),
+        expr => expr.clone(),
+    }
+}
+
+/// The node count of `expr` once [`Expr::trigsimp`] has combined the arguments of its functions,
+/// and every sine, cosine and tangent counts once more so forms with fewer of them win
+pub fn cost(expr: &Expr) -> f64 {
+    fn functions(expr: &Expr) -> f64 {
+        match expr {
+            Expr::Function(Tree {
+                value,
+                next: Some(next),
+            }) => {
+                let own = ["sin", "cos", "tan"].contains(&value.as_str()) as u8 as f64;
+                own + next.iter().map(|e| functions(e)).sum::<f64>()
+            }
+            Expr::Operator(Tree {
+                next: Some(next), ..
+            }) => next.iter().map(|e| functions(e)).sum(),
+            _ => 0.0,
+        }
+    }
+    let combined = combine_arguments(expr);
+    Cost::NodeCount.cost(&combined) + functions(&combined)
+}
+
+/// see [`Expr::trigsimp`]
+pub fn trigsimp(engine: &Engine, expr: &Expr) -> Expr {
+    combine_arguments(&Simplifier::trig().simplify(engine, expr))
+}
+
+/// see [`Expr::expand_trig`], the products of the angle rules are multiplied out afterwards so
+/// the factoring rules do not leave them half done
+pub fn expand_trig(engine: &Engine, expr: &Expr) -> Expr {
+    let mut simplifier = Simplifier::new().without_group("trig");
+    for rule in self::simplifier(EXPANSION).rules() {
+        simplifier = simplifier.with_rule(rule.clone());
+    }
+    expand(&simplifier.simplify(engine, expr), false)
+}
+
+/// see [`Expr::to_exponential`]
+pub fn to_exponential(engine: &Engine, expr: &Expr) -> Expr {
+    simplifier(TO_EXPONENTIAL).simplify(engine, expr)
+}
+
+/// see [`Expr::from_exponential`]
+pub fn from_exponential(engine: &Engine, expr: &Expr) -> Expr {
+    simplifier(FROM_EXPONENTIAL)
+        .with_strategy(Strategy::TopDown)
+        .simplify(engine, expr)
+}
@@ -1,6 +1,19 @@
-use std::time::{SystemTime, UNIX_EPOCH};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
-use cas::{self, expr, Engine, Expr, Number, SymErr};
+use cas::{
+    self,
+    complex::Complex,
+    egraph::Cost,
+    expr, groebner,
+    multivariate::{MonomialOrder, MultiPolynomial},
+    nsolve::{self, Guess, Method, NSolver},
+    polynomial::Polynomial,
+    set::Set,
+    simplifier::{Budget, Guard, Rule, Simplifier, Stop, Strategy},
+    solver::Condition,
+    sturm::{RootInterval, Sturm},
+    Bindings, Engine, Expr, Number, SymErr, ID,
+};
 
 #[test]
 fn simple_eval() {
@@ -11,6 +24,10 @@ fn simple_eval() {
         ("43*(4(81/9))", expr!(1548)),
         ("5*4+3", expr!(23)),
         ("-2^6", expr!(-64)),
+        ("5-3-1", expr!(1)),
+        ("8/4/2", expr!(1)),
+        ("2*-3", expr!(-6)),
+        ("(-8)^(1/3)", expr!(-2)),
     ];
 
     for (i, e) in l.iter().enumerate() {
@@ -148,3 +165,1373 @@ fn number_test() {
     assert_eq!(Number::parse("50").unwrap(), Number::Rational(50, 1));
     assert_eq!(Number::parse("5.5.0").unwrap_err(), SymErr::NotANumber);
 }
+
+#[test]
+fn solve_test() {
+    let engine = Engine::new().with_functions();
+
+    let values = |s: &str| -> Vec<Expr> {
+        Expr::parse(&engine, s)
+            .unwrap()
+            .solve("x", &engine)
+            .unwrap()
+            .into_iter()
+            .map(|solution| solution.value)
+            .collect()
+    };
+
+    assert_eq!(values("x^2=4"), vec![expr!(-2), expr!(2)]);
+    assert_eq!(values("x^2=-4"), vec![]);
+    assert_eq!(values("2x+1=7"), vec![expr!(3)]);
+    assert_eq!(values("3=x-1"), vec![expr!(4)]);
+    assert_eq!(values("x^3+8"), vec![expr!(-2)]);
+    assert_eq!(values("ln(x)=2"), vec![expr!("e").pow(expr!(2))]);
+    let half = Expr::from(Number::Rational(1, 2));
+    assert_eq!(values("1/x=2"), vec![half.clone()]);
+    assert_eq!(values("x^(-2)=4"), vec![-half.clone(), half]);
+    assert_eq!(values("x^(2/3)=4"), vec![expr!(-8), expr!(8)]);
+    assert_eq!(values("x^(4/3)=16"), vec![expr!(-8), expr!(8)]);
+    assert_eq!(values("x^(1/3)=2"), vec![expr!(8)]);
+    let solutions = Expr::parse(&engine, "2^x=8")
+        .unwrap()
+        .solve("x", &engine)
+        .unwrap();
+    assert_eq!(solutions[0].value.to_string(), "ln(8)/ln(2)");
+
+    let solutions = Expr::parse(&engine, "a*x=b")
+        .unwrap()
+        .solve("x", &engine)
+        .unwrap();
+    assert_eq!(solutions.len(), 1);
+    assert_eq!(
+        solutions[0].conditions,
+        vec![Condition::NonZero(expr!("a"))]
+    );
+
+    assert_eq!(
+        Expr::parse(&engine, "y=2").unwrap().solve("x", &engine),
+        Err(SymErr::Unsolvable)
+    );
+}
+
+#[test]
+fn roots_test() {
+    let engine = Engine::new().with_functions();
+
+    let roots = |s: &str| {
+        Expr::parse(&engine, s)
+            .unwrap()
+            .roots("x", &engine)
+            .unwrap()
+    };
+
+    assert_eq!(roots("x^3-6x^2+11x-6"), vec![expr!(1), expr!(2), expr!(3)]);
+    assert_eq!(
+        roots("2x^2-3x+1"),
+        vec![expr!(Number::Rational(1, 2)), expr!(1)]
+    );
+    assert_eq!(
+        roots("x^2-8"),
+        vec![
+            -(expr!(2) * expr!(2).pow(expr!(Number::Rational(1, 2)))),
+            expr!(2) * expr!(2).pow(expr!(Number::Rational(1, 2))),
+        ]
+    );
+    assert_eq!(
+        roots("x^2+1"),
+        vec![-expr!(cas::constants::I), expr!(cas::constants::I)]
+    );
+    assert_eq!(
+        Expr::parse(&engine, "x^5-x-1").unwrap().roots("x", &engine),
+        Err(SymErr::Unsolvable)
+    );
+
+    // cubics and quartics in every discriminant case, checked numerically
+    for polynomial in [
+        "x^3-2",
+        "x^3-3x+1",
+        "x^3+3x+1",
+        "x^3-3x+2",
+        "x^4-10x^2+1",
+        "x^4+1",
+        "x^4-x-1",
+        "3x^4+2x^3-x+5",
+        // rounding hides the root of the resolvent, so this one is approximated
+        "x^4+3x^2+x/10000000-3",
+    ] {
+        let expr = Expr::parse(&engine, polynomial).unwrap();
+        let coefficients = expr.to_polynomial("x", &engine).unwrap();
+        let coefficients = coefficients.coefficients();
+        let roots = expr.roots("x", &engine).unwrap();
+        assert_eq!(roots.len(), coefficients.len() - 1, "{}", polynomial);
+
+        for root in roots {
+            let x = root.approximate().unwrap();
+            let value = coefficients
+                .iter()
+                .rev()
+                .fold(Complex::from(0.0), |acc, c| {
+                    acc * x + Complex::from(c.clone())
+                });
+            assert!(value.abs() < 1e-9, "{}: {} = {}", polynomial, root, value);
+        }
+    }
+
+    let solutions = Expr::parse(&engine, "x^3=6x^2-11x+6")
+        .unwrap()
+        .solve("x", &engine)
+        .unwrap();
+    assert_eq!(solutions.len(), 3);
+
+    // large and highly composite coefficients neither overflow nor take long
+    assert_eq!(roots("x^3-9223372036854775807").len(), 3);
+    assert_eq!(roots("-x^3-9223372036854775807x-1").len(), 3);
+    assert_eq!(roots("963761198400x^3+x+963761198400").len(), 3);
+    assert_eq!(roots("720720x^3+x+720720").len(), 3);
+    assert!(Expr::parse(&engine, "x^3+x=9223372036854775807")
+        .unwrap()
+        .solve("x", &engine)
+        .is_ok());
+    assert!(Expr::parse(&engine, "x^3-9223372036854775807>0")
+        .unwrap()
+        .solve_inequality("x", &engine)
+        .is_ok());
+}
+
+#[test]
+fn numeric_roots_test() {
+    let engine = Engine::new().with_functions();
+
+    let roots = Expr::parse(&engine, "x^30-1")
+        .unwrap()
+        .nroots("x", &engine)
+        .unwrap();
+    assert_eq!(roots.len(), 30);
+    for root in roots {
+        assert_eq!(root.multiplicity, 1);
+        assert!(root.error < 1e-10);
+        assert!((root.value.abs() - 1.0).abs() <= root.error);
+    }
+
+    let roots = Expr::parse(&engine, "(x-1)^3*(x+2)*x^2")
+        .unwrap()
+        .nroots("x", &engine)
+        .unwrap();
+    assert_eq!(
+        roots
+            .iter()
+            .map(|root| root.multiplicity)
+            .collect::<Vec<_>>(),
+        vec![1, 2, 3]
+    );
+    assert_eq!(roots[0].value, Complex::new(-2.0, 0.0));
+    assert_eq!(roots[1].value, Complex::new(0.0, 0.0));
+    assert!((roots[2].value - Complex::from(1.0)).abs() <= roots[2].error);
+
+    // every root of a degree 30 polynomial
+    let expr = Expr::parse(&engine, "3x^30+x^29-2x^7+x-5").unwrap();
+    let polynomial = expr.to_polynomial("x", &engine).unwrap();
+    let coefficients = polynomial.coefficients();
+    let roots = expr.nroots("x", &engine).unwrap();
+    assert_eq!(
+        roots.iter().map(|root| root.multiplicity).sum::<usize>(),
+        30
+    );
+    for root in roots {
+        let value = coefficients
+            .iter()
+            .rev()
+            .fold(Complex::from(0.0), |acc, c| {
+                acc * root.value + Complex::from(c.clone())
+            });
+        assert!(value.abs() < 1e-10, "{} = {}", root, value);
+    }
+}
+
+#[test]
+fn nsolve_test() {
+    let engine = Engine::new().with_functions();
+    let dottie = 0.7390851332151607;
+
+    let equation = Expr::parse(&engine, "x = cos(x)").unwrap();
+    let derivative = equation.derivative("x", &engine).unwrap();
+    assert_eq!(
+        derivative,
+        Expr::equation(expr!(1), -Expr::function("sin", vec![expr!("x")]))
+    );
+
+    let newton = equation.nsolve("x", Guess::Start(1.0), &engine).unwrap();
+    assert!(newton.converged);
+    assert_eq!(newton.method, Method::Newton);
+    assert!(newton.iterations < 10);
+    assert!((newton.root - dottie).abs() < 1e-12);
+
+    for method in [Method::Newton, Method::Bisection, Method::Brent] {
+        let result = NSolver::new()
+            .with_method(method)
+            .with_tolerance(1e-14)
+            .solve(&engine, &equation, "x", Guess::Bracket(0.0, 2.0))
+            .unwrap();
+        assert!(result.converged, "{}", result);
+        assert!((result.root - dottie).abs() < 1e-13, "{}", result);
+        assert!(result.residual.abs() < 1e-13);
+    }
+
+    // functions without a registered derivative use the secant method
+    let engine = engine.with_function("f", 1, cas::functions::exp);
+    let result = Expr::parse(&engine, "f(x) = 2")
+        .unwrap()
+        .nsolve("x", Guess::Start(0.0), &engine)
+        .unwrap();
+    assert_eq!(result.method, Method::Secant);
+    assert!((result.root - 2f64.ln()).abs() < 1e-12);
+
+    let limited = NSolver::new()
+        .with_max_iterations(3)
+        .solve(&engine, &equation, "x", Guess::Bracket(0.0, 2.0))
+        .unwrap();
+    assert!(!limited.converged);
+    assert_eq!(limited.iterations, 3);
+
+    let err = NSolver::new().with_method(Method::Brent).solve(
+        &engine,
+        &equation,
+        "x",
+        Guess::Bracket(1.0, 2.0),
+    );
+    assert_eq!(err, Err(SymErr::InvalidBracket));
+
+    // the sign change of 1/x is a pole, not a root
+    let reciprocal = Expr::parse(&engine, "1/x").unwrap();
+    let pole = reciprocal
+        .nsolve("x", Guess::Bracket(-1.0, 2.0), &engine)
+        .unwrap();
+    assert!(!pole.converged, "{}", pole);
+    for method in [Method::Bisection, Method::Brent] {
+        let pole = NSolver::new()
+            .with_method(method)
+            .solve(&engine, &reciprocal, "x", Guess::Bracket(-1.0, 2.0))
+            .unwrap();
+        assert!(!pole.converged, "{}", pole);
+    }
+
+    let mut bindings = Bindings::new();
+    bindings.insert("x", expr!(0));
+    bindings.insert("y", expr!(3));
+    assert_eq!(
+        Expr::parse(&engine, "x^2+exp(x)*y")
+            .unwrap()
+            .eval_with(&engine, &bindings),
+        Ok(expr!(3))
+    );
+}
+
+#[test]
+fn nsolve_system_test() {
+    let engine = Engine::new().with_functions();
+    let parse = |equations: &[&str]| {
+        equations
+            .iter()
+            .map(|e| Expr::parse(&engine, e).unwrap())
+            .collect::<Vec<_>>()
+    };
+
+    let circle = parse(&["x^2+y^2 = 4", "y = x"]);
+    let result = nsolve::nsolve_system(&engine, &circle, &["x", "y"], &[1.0, 3.0]).unwrap();
+    assert!(result.converged);
+    assert_eq!(result.method, Method::Newton);
+    assert!(result.residual < 1e-12);
+    for x in &result.solution {
+        assert!((x - 2f64.sqrt()).abs() < 1e-12, "{}", result);
+    }
+
+    // two link arm reaching for (1.2, 0.8)
+    let arm = parse(&["cos(a)+cos(a+b) = 1.2", "sin(a)+sin(a+b) = 0.8"]);
+    let result = nsolve::nsolve_system(&engine, &arm, &["a", "b"], &[0.5, 0.5]).unwrap();
+    assert!(result.converged);
+    let (a, b) = (result.solution[0], result.solution[1]);
+    assert!((a.cos() + (a + b).cos() - 1.2).abs() < 1e-12);
+    assert!((a.sin() + (a + b).sin() - 0.8).abs() < 1e-12);
+
+    // the jacobian is singular at the start, so newton can not take the first step
+    let result = nsolve::nsolve_system(&engine, &circle, &["x", "y"], &[1.0, -1.0]).unwrap();
+    assert!(result.converged);
+    assert_eq!(result.method, Method::LevenbergMarquardt);
+    assert!(result.residual < 1e-12);
+    for x in &result.solution {
+        assert!((x.abs() - 2f64.sqrt()).abs() < 1e-12, "{}", result);
+    }
+
+    // least squares for an overdetermined system
+    let overdetermined = parse(&["x = 1", "y = 2", "x+y = 3.5"]);
+    let result = nsolve::nsolve_system(&engine, &overdetermined, &["x", "y"], &[0.0, 0.0]).unwrap();
+    assert!(!result.converged && result.minimum);
+    assert!((result.solution[0] - 7.0 / 6.0).abs() < 1e-9, "{}", result);
+    assert!((result.solution[1] - 13.0 / 6.0).abs() < 1e-9, "{}", result);
+    assert!((result.residual - (3.0f64 / 36.0).sqrt()).abs() < 1e-9);
+
+    // no real solution, the iteration stalls at the minimum of x^2+1
+    let imaginary = parse(&["x^2+1 = 0", "y = x"]);
+    let result = nsolve::nsolve_system(&engine, &imaginary, &["x", "y"], &[1.0, 3.0]).unwrap();
+    assert!(!result.converged && result.minimum);
+    assert!((result.residual - 1.0).abs() < 1e-9, "{}", result);
+
+    let result = NSolver::new()
+        .with_method(Method::LevenbergMarquardt)
+        .solve(&engine, &parse(&["x^2 = 4"])[0], "x", Guess::Start(3.0))
+        .unwrap();
+    assert!(result.converged);
+    assert_eq!(result.method, Method::LevenbergMarquardt);
+    assert!((result.root - 2.0).abs() < 1e-12);
+}
+
+#[test]
+fn inequality_test() {
+    let engine = Engine::new().with_functions();
+    let solve = |s: &str| {
+        Expr::parse(&engine, s)
+            .unwrap()
+            .solve_inequality("x", &engine)
+            .unwrap()
+    };
+
+    let relation = Expr::parse(&engine, "x^2 - 4 >= 0").unwrap();
+    assert_eq!(relation.print(), "x^2-4>=0");
+    assert_eq!(relation.print_latex(), "x^{2}-4\\geq 0");
+
+    assert_eq!(solve("2x + 1 < 5").print(), "(-inf, 2)");
+    assert_eq!(solve("x^2 - 4 >= 0").print(), "(-inf, -2] U [2, inf)");
+    assert_eq!(
+        solve("x^2 - 4 >= 0").print_latex(),
+        "\\left(-\\infty, -2\\right] \\cup \\left[2, \\infty\\right)"
+    );
+    assert_eq!(solve("(x-1)/(x+2) <= 0").print(), "(-2, 1]");
+    assert_eq!(solve("1/x > 1").print(), "(0, 1)");
+    assert_eq!(solve("x^2 <= 0").print(), "{0}");
+    assert_eq!(solve("x^2 + 1 > 0"), Set::reals());
+    assert_eq!(solve("x^2 + 1 < 0"), Set::empty());
+    assert_eq!(solve("(x-1)^2/(x-1) >= 0").print(), "(1, inf)");
+    assert_eq!(solve("x^3 > x").print(), "(-1, 0) U (1, inf)");
+
+    let set = solve("x^2 < 2");
+    assert_eq!(set.intervals.len(), 1);
+    assert!(set.contains(1.414) && set.contains(-1.414));
+    assert!(!set.contains(1.415) && !set.contains(-1.415));
+
+    // roots of a quintic are approximated
+    let set = solve("x^5 - x - 1 >= 0");
+    assert!(set.contains(1.1674) && !set.contains(1.1673));
+
+    assert_eq!(
+        Expr::parse(&engine, "x < 2").unwrap().solve("x", &engine),
+        Err(SymErr::InvalidOP)
+    );
+}
+
+#[test]
+fn polynomial_test() {
+    let engine = Engine::new().with_functions();
+    let parse = |s: &str| {
+        Expr::parse(&engine, s)
+            .unwrap()
+            .to_polynomial("x", &engine)
+            .unwrap()
+    };
+    let r = |n, d| Number::Rational(n, d);
+
+    let p = parse("(x-1)^2*(2x+3)/4");
+    assert_eq!(p.degree(), Some(3));
+    assert_eq!(p.coefficients(), &[r(3, 4), r(-1, 1), r(-1, 4), r(1, 2)]);
+    assert_eq!(p.to_expr("x").print(), "(1/2)*x^3-(1/4)*x^2-x+(3/4)");
+    assert_eq!(parse("x^3-x-1").to_expr("y").print(), "y^3-y-1");
+    assert_eq!(parse("-x^2+1").to_expr("x").print(), "-x^2+1");
+    assert_eq!(parse("x-x"), Polynomial::zero());
+    assert_eq!(Polynomial::zero().degree(), None);
+    assert_eq!(
+        Expr::parse(&engine, "1/x")
+            .unwrap()
+            .to_polynomial("x", &engine),
+        Err(SymErr::Inconvertible)
+    );
+
+    let a = parse("x^3-2x^2-4");
+    let b = parse("x-3");
+    assert_eq!(a.clone() + b.clone(), parse("x^3-2x^2+x-7"));
+    assert_eq!(a.clone() - b.clone(), parse("x^3-2x^2-x-1"));
+    assert_eq!(a.clone() * b.clone(), parse("x^4-5x^3+6x^2-4x+12"));
+    let (q, rem) = a.div_rem(&b).unwrap();
+    assert_eq!(q, parse("x^2+x+3"));
+    assert_eq!(rem, parse("5"));
+    assert_eq!(q * b.clone() + rem, a);
+    let (q, rem) = parse("x^2+1").div_rem(&parse("2x")).unwrap();
+    assert_eq!((q, rem), (parse("x/2"), parse("1")));
+    assert_eq!(a.div_rem(&Polynomial::zero()), Err(SymErr::Undefined));
+    // the coefficients stay exact or the conversion fails, they never turn into floats
+    assert_eq!(
+        parse("x^3").div_rem(&parse("x-10000000000")),
+        Err(SymErr::Overflow)
+    );
+    assert_eq!(
+        Expr::parse(&engine, "(x+10000000000)^2")
+            .unwrap()
+            .to_polynomial("x", &engine),
+        Err(SymErr::Overflow)
+    );
+
+    assert_eq!(a.derivative(), parse("3x^2-4x"));
+    assert_eq!(a.evaluate(&r(3, 1)), r(5, 1));
+    assert_eq!(a.evaluate(&r(1, 2)), r(-35, 8));
+    assert_eq!(a.compose(&b), parse("(x-3)^3-2(x-3)^2-4"));
+    assert_eq!(parse("2x^2+4").monic(), parse("x^2+2"));
+}
+
+#[test]
+fn multivariate_test() {
+    let engine = Engine::new().with_functions();
+    let vars = ["x", "y", "z"];
+    let parse = |s: &str, order| {
+        Expr::parse(&engine, s)
+            .unwrap()
+            .to_multivariate(&vars, order, &engine)
+            .unwrap()
+    };
+
+    let f = "4x*y^2*z+4z^2-5x^3+7x^2*z^2";
+    let lex = parse(f, MonomialOrder::Lex);
+    assert_eq!(lex.to_string(), "-5*x^3+7*x^2*z^2+4*x*y^2*z+4*z^2");
+    assert_eq!(
+        lex.with_order(MonomialOrder::GrLex).to_string(),
+        "7*x^2*z^2+4*x*y^2*z-5*x^3+4*z^2"
+    );
+    assert_eq!(
+        parse(f, MonomialOrder::GrevLex).to_string(),
+        "4*x*y^2*z+7*x^2*z^2-5*x^3+4*z^2"
+    );
+    assert_eq!(lex.leading_monomial(), Some(&vec![3, 0, 0]));
+    assert_eq!(lex.leading_coefficient(), Number::Rational(-5, 1));
+    assert_eq!(lex.total_degree(), Some(4));
+    assert_eq!(lex.degree("y"), Some(2));
+
+    // canonical forms make equal polynomials compare equal
+    let order = MonomialOrder::GrevLex;
+    let a = parse("x^2*y+3x*y^2", order);
+    let b = parse("x*y*(x+3y)", order);
+    assert_eq!(a, b);
+    assert_eq!(
+        a.clone() - b,
+        MultiPolynomial::new(vars.iter().map(|v| v.to_string()).collect(), order)
+    );
+    assert_eq!(
+        parse("x+y", order) * parse("x-y", order),
+        parse("x^2-y^2", order)
+    );
+    assert_eq!(parse("(x+y+z)^2", order).terms().len(), 6);
+    assert_eq!(parse("(x+y)/2", order).to_string(), "(1/2)*x+(1/2)*y");
+    assert_eq!(
+        parse("x^2*y-z", order).evaluate(&[
+            Number::Rational(2, 1),
+            Number::Rational(3, 1),
+            Number::Rational(1, 1)
+        ]),
+        Number::Rational(11, 1)
+    );
+
+    // polynomials in different variables are combined over all of them
+    let u = Expr::parse(&engine, "u^2")
+        .unwrap()
+        .to_multivariate(&["u"], order, &engine)
+        .unwrap();
+    let sum = parse("x", order) + u;
+    assert_eq!(sum.vars(), &["x", "y", "z", "u"]);
+    assert_eq!(sum.to_string(), "u^2+x");
+    assert_eq!(
+        parse("x^3-x", order).to_univariate("x"),
+        Ok(Expr::parse(&engine, "x^3-x")
+            .unwrap()
+            .to_polynomial("x", &engine)
+            .unwrap())
+    );
+    assert_eq!(
+        parse("x*y", order).to_univariate("x"),
+        Err(SymErr::Inconvertible)
+    );
+    assert_eq!(
+        Expr::parse(&engine, "x/y")
+            .unwrap()
+            .to_multivariate(&vars, order, &engine),
+        Err(SymErr::Inconvertible)
+    );
+}
+
+#[test]
+fn gcd_test() {
+    let engine = Engine::new().with_functions();
+    let univariate = |s: &str| {
+        Expr::parse(&engine, s)
+            .unwrap()
+            .to_polynomial("x", &engine)
+            .unwrap()
+    };
+
+    assert_eq!(
+        univariate("x^3-1").gcd(&univariate("2x^2-2")),
+        Ok(univariate("x-1"))
+    );
+    // the remainders of plain euclid leave i64 here and lose the common factor to rounding
+    assert_eq!(
+        univariate("(x^3+7x-100)*(99x^6-17x^5+3x^2-1000)")
+            .gcd(&univariate("(x^3+7x-100)*(31x^6+1234x^3-77x+5)")),
+        Ok(univariate("x^3+7x-100"))
+    );
+    assert_eq!(
+        univariate("(x^2+1/3)*(7x^5-x+2)").gcd(&univariate("(3x^2+1)*(x^4-2)")),
+        Ok(univariate("x^2+1/3"))
+    );
+    assert_eq!(
+        univariate("x^3-1").exact_div(&univariate("x-1")),
+        Ok(univariate("x^2+x+1"))
+    );
+    assert_eq!(
+        univariate("x^3-1").exact_div(&univariate("x+1")),
+        Err(SymErr::NotDivisible)
+    );
+    assert_eq!(
+        univariate("x^2-1").resultant(&univariate("x-2")),
+        Ok(Number::Rational(3, 1))
+    );
+    assert_eq!(
+        univariate("x^2-1").resultant(&univariate("x^2+x")),
+        Ok(Number::Rational(0, 1))
+    );
+    assert_eq!(
+        univariate("x^2-2").resultant(&univariate("x-10000000000")),
+        Err(SymErr::Overflow)
+    );
+    assert_eq!(
+        univariate("x^2-3x+2").discriminant(),
+        Ok(Number::Rational(1, 1))
+    );
+    assert_eq!(
+        univariate("x^3-x+1").discriminant(),
+        Ok(Number::Rational(-23, 1))
+    );
+
+    let vars = ["x", "y", "z", "a", "b", "c"];
+    let parse = |s: &str| {
+        Expr::parse(&engine, s)
+            .unwrap()
+            .to_multivariate(&vars, MonomialOrder::Lex, &engine)
+            .unwrap()
+    };
+
+    assert_eq!(
+        parse("x^2-y^2").gcd(&parse("x^2+2x*y+y^2")),
+        Ok(parse("x+y"))
+    );
+    assert_eq!(
+        parse("(x+y)*(2x-4z)^2").gcd(&parse("(x-2z)*(y+1)")),
+        Ok(parse("x-2z"))
+    );
+    assert_eq!(parse("6x*y").gcd(&parse("4y^2")), Ok(parse("y")));
+    assert_eq!(parse("x+1").gcd(&parse("y+1")), Ok(parse("1")));
+    assert_eq!(
+        parse("x^2*y-y^3").exact_div(&parse("x-y")),
+        Ok(parse("x*y+y^2"))
+    );
+    assert_eq!(
+        parse("x^2+y").exact_div(&parse("x+y")),
+        Err(SymErr::NotDivisible)
+    );
+
+    // eliminating y from the circle and the diagonal leaves their x coordinates
+    assert_eq!(
+        parse("x^2+y^2-1").resultant(&parse("x-y"), "y"),
+        Ok(parse("2x^2-1"))
+    );
+    assert_eq!(
+        parse("x^2-2").resultant(&parse("x-10000000000"), "x"),
+        Err(SymErr::Overflow)
+    );
+    assert_eq!(
+        parse("a*x^2+b*x+c").discriminant("x"),
+        Ok(parse("b^2-4a*c"))
+    );
+}
+
+#[test]
+fn factor_test() {
+    let engine = Engine::new().with_functions();
+    let factor = |s: &str| {
+        Expr::parse(&engine, s)
+            .unwrap()
+            .factor(&engine)
+            .unwrap()
+            .to_string()
+    };
+
+    assert_eq!(factor("2x^2-2"), "2*(x-1)*(x+1)");
+    assert_eq!(factor("x^3-3x^2+3x-1"), "(x-1)^3");
+    assert_eq!(factor("x^4-1"), "(x-1)*(x+1)*(x^2+1)");
+    assert_eq!(factor("x^2/2-x/2"), "(1/2)*x*(x-1)");
+    assert_eq!(factor("-x^2+4"), "-(x-2)*(x+2)");
+    assert_eq!(factor("x^4+4"), "(x^2-2*x+2)*(x^2+2*x+2)");
+    assert_eq!(factor("x^2+1"), "x^2+1");
+    assert_eq!(factor("6x^5+11x^4-x^3+5x-6"), "(2*x+3)*(3*x-2)*(x^3+x^2+1)");
+    assert_eq!(
+        factor("(123x^3-45x+7)*(99x^2+1000x-3)"),
+        "(99*x^2+1000*x-3)*(123*x^3-45*x+7)"
+    );
+    // swinnerton-dyer: irreducible, but splits into four quadratics modulo every prime
+    assert_eq!(factor("x^4-10x^2+1"), "x^4-10*x^2+1");
+
+    assert_eq!(factor("x^2-y^2"), "(x-y)*(x+y)");
+    assert_eq!(factor("x^2*y+2x*y+y"), "y*(x+1)^2");
+    assert_eq!(factor("x^3*y-x*y^3+x^2*z-y^2*z"), "(x-y)*(x+y)*(x*y+z)");
+    // homogeneous ones are factored with y = 1, the substitution for x^30-y^30 would be of
+    // degree 960
+    let power = |var: &str, e: usize| match e {
+        0 => None,
+        1 => Some(var.to_string()),
+        e => Some(format!("{}^{}", var, e)),
+    };
+    let terms = (0..23)
+        .map(|i| {
+            let factors = [power("x", 22 - i), power("y", i)];
+            factors
+                .iter()
+                .flatten()
+                .cloned()
+                .collect::<Vec<_>>()
+                .join("*")
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(factor("x^23-y^23"), format!("(x-y)*({})", terms.join("+")));
+    assert_eq!(
+        factor("x^30-y^30"),
+        concat!(
+            "(x-y)*(x+y)*(x^2-x*y+y^2)*(x^2+x*y+y^2)*(x^4-x^3*y+x^2*y^2-x*y^3+y^4)",
+            "*(x^4+x^3*y+x^2*y^2+x*y^3+y^4)*(x^8-x^7*y+x^5*y^3-x^4*y^4+x^3*y^5-x*y^7+y^8)",
+            "*(x^8+x^7*y-x^5*y^3-x^4*y^4-x^3*y^5+x*y^7+y^8)"
+        )
+    );
+    assert_eq!(
+        factor("x^6*y^2-y^8"),
+        "y^2*(x-y)*(x+y)*(x^2-x*y+y^2)*(x^2+x*y+y^2)"
+    );
+    // too large to substitute, which is an error rather than an irreducible result
+    assert_eq!(
+        Expr::parse(&engine, "x^30-y^30+1").unwrap().factor(&engine),
+        Err(SymErr::Unsolvable)
+    );
+    // the factors could have coefficients above the largest modulus the lifting reaches
+    let large = Expr::parse(&engine, "(x^2+1000000007x+3)*(x^2-999999937x+5)").unwrap();
+    assert_eq!(large.expand().factor(&engine), Err(SymErr::Overflow));
+    assert_eq!(
+        Expr::parse(&engine, "sin(x)").unwrap().factor(&engine),
+        Err(SymErr::Inconvertible)
+    );
+}
+
+#[test]
+fn groebner_test() {
+    let engine = Engine::new().with_functions();
+    let vars = ["x", "y"];
+    let parse = |s: &str, order| {
+        Expr::parse(&engine, s)
+            .unwrap()
+            .to_multivariate(&vars, order, &engine)
+            .unwrap()
+    };
+
+    let lex = MonomialOrder::Lex;
+    assert_eq!(
+        groebner::groebner(&[parse("x^2+y^2-1", lex), parse("x-y", lex)], lex),
+        Ok(vec![parse("x-y", lex), parse("y^2-1/2", lex)])
+    );
+    // 10^20 does not fit, the basis is not computed with floats instead
+    assert_eq!(
+        groebner::groebner(&[parse("x^2", lex), parse("x-10000000000", lex)], lex),
+        Err(SymErr::Overflow)
+    );
+
+    let order = MonomialOrder::GrLex;
+    let generators = [parse("x^3-2x*y", order), parse("x^2*y-2y^2+x", order)];
+    let basis = groebner::groebner(&generators, order).unwrap();
+    assert_eq!(
+        basis,
+        vec![
+            parse("x^2", order),
+            parse("x*y", order),
+            parse("y^2-x/2", order)
+        ]
+    );
+    assert_eq!(
+        groebner::is_member(&parse("x^2+3x*y", order), &generators),
+        Ok(true)
+    );
+    assert_eq!(
+        groebner::is_member(&parse("x", order), &generators),
+        Ok(false)
+    );
+    assert_eq!(
+        groebner::reduce(&parse("x^3*y+y^2", order), &basis),
+        Ok(parse("x/2", order))
+    );
+
+    let equations = |s: &[&str]| {
+        s.iter()
+            .map(|s| Expr::parse(&engine, s).unwrap())
+            .collect::<Vec<_>>()
+    };
+    let approximate = |solutions: Vec<Vec<Expr>>| {
+        solutions
+            .iter()
+            .map(|s| {
+                s.iter()
+                    .map(|e| e.approximate().unwrap().re)
+                    .collect::<Vec<_>>()
+            })
+            .collect::<Vec<_>>()
+    };
+
+    assert_eq!(
+        groebner::solve(&engine, &equations(&["x*y=2", "x+y=3"]), &vars),
+        Ok(vec![
+            vec![Expr::from(2), Expr::from(1)],
+            vec![Expr::from(1), Expr::from(2)]
+        ])
+    );
+    let circle =
+        approximate(groebner::solve(&engine, &equations(&["x^2+y^2=1", "x=y"]), &vars).unwrap());
+    let half = 0.5f64.sqrt();
+    assert_eq!(circle.len(), 2);
+    assert!((circle[0][0] + half).abs() < 1e-12 && (circle[0][1] + half).abs() < 1e-12);
+    assert!((circle[1][0] - half).abs() < 1e-12 && (circle[1][1] - half).abs() < 1e-12);
+    // the complex intersections are left out
+    assert_eq!(
+        groebner::solve(&engine, &equations(&["x^2+y^2=1", "y=2"]), &vars),
+        Ok(Vec::new())
+    );
+    assert_eq!(
+        groebner::solve(&engine, &equations(&["x=1", "x=2"]), &vars),
+        Ok(Vec::new())
+    );
+    assert_eq!(
+        groebner::solve(&engine, &equations(&["x^2=y"]), &vars),
+        Err(SymErr::Unsolvable)
+    );
+}
+
+#[test]
+fn sturm_test() {
+    let engine = Engine::new().with_functions();
+    let sturm = |s: &str| {
+        Sturm::new(
+            &Expr::parse(&engine, s)
+                .unwrap()
+                .to_polynomial("x", &engine)
+                .unwrap(),
+        )
+        .unwrap()
+    };
+    let r = |n, d| Number::Rational(n, d);
+
+    let s = sturm("x^2-2");
+    assert_eq!(s.count_all(), 2);
+    assert_eq!(s.count(&r(-2, 1), &r(2, 1)), Ok(2));
+    assert_eq!(s.count(&r(0, 1), &r(1, 1)), Ok(0));
+    assert_eq!(s.count(&r(1, 1), &r(3, 2)), Ok(1));
+
+    // repeated roots count once, a root on the upper end counts, one on the lower end does not
+    let s = sturm("(x-1)^3*(x+2)^2*(x^2+1)");
+    assert_eq!(s.count_all(), 2);
+    assert_eq!(s.count(&r(-2, 1), &r(1, 1)), Ok(1));
+    assert_eq!(s.count(&r(-3, 1), &r(-2, 1)), Ok(1));
+
+    // roots closer together than a float would tell apart, with coefficients that need more
+    // than 64 bits along the sequence
+    let s = sturm("(1000000000x-1)*(1000000000x-2)*(x^2-3)");
+    let mut intervals = s.isolate();
+    assert_eq!(intervals.len(), 4);
+    let expected = [-(3f64.sqrt()), 1e-9, 2e-9, 3f64.sqrt()];
+    for (interval, root) in intervals.iter_mut().zip(expected.iter()) {
+        interval.refine(&s, 80);
+        assert!(interval.within(80));
+        assert!((interval.approximate() - root).abs() <= 1e-15 * root.abs());
+    }
+
+    // exact rational roots are hit by the bisection
+    let s = sturm("(4x-1)*(x+3)");
+    let mut intervals = s.isolate();
+    intervals
+        .iter_mut()
+        .for_each(|interval| interval.refine(&s, 200));
+    assert!(intervals.iter().all(RootInterval::is_exact));
+    assert_eq!(intervals[0].lower(), r(-3, 1));
+    assert_eq!(intervals[1].to_string(), "{1/4}");
+
+    let s = sturm("x^3-2");
+    let mut cube_root = s.isolate().remove(0);
+    assert_eq!(cube_root.to_string(), "(-3, 3]");
+    cube_root.refine(&s, 40);
+    assert!((f64::from(cube_root.lower()) - 2f64.cbrt()).abs() < 1e-12);
+    assert!(sturm("x^2+1").isolate().is_empty());
+}
+
+#[test]
+fn expand_test() {
+    let engine = Engine::new().with_functions();
+    let expand = |s: &str| Expr::parse(&engine, s).unwrap().expand().to_string();
+
+    assert_eq!(expand("(x+1)^3"), "x^3+3*x^2+3*x+1");
+    assert_eq!(expand("(a+b)*(c-d)"), "a*c-a*d+b*c-b*d");
+    assert_eq!(expand("(a+b+c)^2"), "a^2+2*a*b+2*a*c+b^2+2*b*c+c^2");
+    assert_eq!(expand("(x-y)^2-(x+y)^2"), "-4*x*y");
+    assert_eq!(expand("(x/y+y)^2"), "y^2+2*x+x^2/y^2");
+    assert_eq!(expand("2*(x+1)=x^2"), "2*x+2=x^2");
+    assert_eq!(expand("(x+1)*(x-1)-x^2+1"), "0");
+
+    // only natural powers of sums are multiplied out
+    assert_eq!(expand("1/(x+1)"), "1/(x+1)");
+    assert_eq!(expand("(x+1)^(1/2)*(x+1)"), "(x+1)^(1/2)*x+(x+1)^(1/2)");
+
+    let e = Expr::parse(&engine, "sin((x+1)^2)*(x+1)").unwrap();
+    assert_eq!(e.expand().to_string(), "sin((x+1)^2)*x+sin((x+1)^2)");
+    assert_eq!(
+        e.expand_all().to_string(),
+        "sin(x^2+2*x+1)*x+sin(x^2+2*x+1)"
+    );
+}
+
+#[test]
+fn rational_test() {
+    let engine = Engine::new().with_functions();
+    let parse = |s: &str| Expr::parse(&engine, s).unwrap();
+
+    assert_eq!(parse("a*x+b*x+c").collect("x").to_string(), "(a+b)*x+c");
+    assert_eq!(parse("a*x-b*x").collect("x").to_string(), "(a-b)*x");
+    assert_eq!(
+        parse("x^2*y+3*x^2+x*z-5").collect("x").to_string(),
+        "(y+3)*x^2+z*x-5"
+    );
+
+    assert_eq!(
+        parse("1/(x+1)+1/(x-1)").together().to_string(),
+        "2*x/((x+1)*(x-1))"
+    );
+    assert_eq!(parse("x/2+y/3").together().to_string(), "(3*x+2*y)/6");
+    assert_eq!(
+        parse("a/(x*y)+b/x^2").together().to_string(),
+        "(a*x+b*y)/(x^2*y)"
+    );
+
+    let apart = |s: &str| parse(s).apart("x", &engine).map(|e| e.to_string());
+    assert_eq!(
+        apart("(x+3)/((x-1)*(x+2))"),
+        Ok(String::from("4/(3*(x-1))-1/(3*(x+2))"))
+    );
+    assert_eq!(apart("(x^3+1)/(x^2-1)"), Ok(String::from("x+1/(x-1)")));
+    assert_eq!(apart("1/(x*(x^2+1))"), Ok(String::from("1/x-x/(x^2+1)")));
+    assert_eq!(
+        apart("(2*x+1)/(x+1)^2"),
+        Ok(String::from("2/(x+1)-1/(x+1)^2"))
+    );
+    assert_eq!(apart("sin(x)/(x+1)"), Err(SymErr::Inconvertible));
+}
+
+#[test]
+fn cancel_test() {
+    let engine = Engine::new().with_functions();
+    let parse = |s: &str| Expr::parse(&engine, s).unwrap();
+    let cancel = |s: &str| parse(s).cancel(&engine).map(|c| c.to_string());
+
+    assert_eq!(
+        cancel("(x^2-1)/(x-1)"),
+        Ok(String::from("x+1, if x-1 != 0"))
+    );
+    assert_eq!(
+        cancel("(6*x^2-6)/(4*x+4)"),
+        Ok(String::from("(3*x-3)/2, if x+1 != 0"))
+    );
+    assert_eq!(
+        cancel("(x^2-y^2)/(x^2+2*x*y+y^2)"),
+        Ok(String::from("(x-y)/(x+y)"))
+    );
+    assert_eq!(
+        cancel("1/(x-1)-2/(x^2-1)"),
+        Ok(String::from("1/(x+1), if x-1 != 0"))
+    );
+    assert_eq!(
+        cancel("(x^3-x)/(-x^2+x)"),
+        Ok(String::from("-x-1, if x != 0 and x-1 != 0"))
+    );
+    assert_eq!(cancel("sin(x)/x"), Err(SymErr::Inconvertible));
+
+    let simplified = parse("x/x").simplify_with_conditions(&engine);
+    assert_eq!(simplified.value, expr!(1));
+    assert_eq!(simplified.conditions, vec![Condition::NonZero(expr!("x"))]);
+    assert_eq!(parse("(x^2-1)/(x-1)").simplify(&engine), parse("x+1"));
+
+    // quotients inside other expressions cancel, and the rules keep going afterwards
+    let simplify = |s: &str| parse(s).simplify_with_conditions(&engine).to_string();
+    assert_eq!(
+        simplify("sin(x)^2+cos(x)^2+(x^2-1)/(x-1)"),
+        "x+2, if x-1 != 0"
+    );
+    assert_eq!(
+        simplify("(2*x+2)/(x+1)*sin(y)^2+cos(y)^2*2"),
+        "2*sin(y)^2+cos(y)^2*2, if x+1 != 0"
+    );
+    assert_eq!(simplify("x*(1/x)+y"), "1+y, if x != 0");
+    let simplifier = Simplifier::new()
+        .with_strategy(Strategy::Saturate)
+        .with_node_limit(200);
+    let engine = Engine::new().with_functions().with_simplifier(simplifier);
+    assert_eq!(
+        parse("(x^2-1)/(x-1)+0")
+            .simplify_with_conditions(&engine)
+            .to_string(),
+        "x+1, if x-1 != 0"
+    );
+}
+
+#[test]
+fn simplify_subexpressions_test() {
+    let engine = Engine::new().with_functions();
+    let x = || expr!("x");
+
+    // float literals in the input
+    let e = expr!(2) * (x() + expr!(0.0));
+    assert_eq!(e.simplify(&engine), expr!(2) * x());
+    let e = Expr::function("sin", vec![x() * expr!(1.0) - x() * expr!(1.0)]);
+    assert_eq!(e.simplify(&engine), Expr::function("sin", vec![expr!(0)]));
+
+    for strategy in [Strategy::BottomUp, Strategy::TopDown, Strategy::Innermost] {
+        let simplifier = Simplifier::new().with_strategy(strategy);
+        assert_eq!(simplifier.strategy(), strategy);
+        let e = (x() + expr!(0.0)) * (expr!(1.0) * expr!("y")) - expr!(0.0) * x();
+        assert_eq!(
+            simplifier.simplify(&engine, &e),
+            x() * expr!("y") - expr!(0),
+            "{:?}",
+            strategy
+        );
+    }
+}
+
+#[test]
+fn ac_matching_test() {
+    let engine = Engine::new().with_functions();
+    let parse = |s: &str| Expr::parse(&engine, s).unwrap();
+    let simplify = |s: &str| parse(s).simplify(&engine).to_string();
+
+    // one rule for every order and grouping, the operands it does not use are kept
+    assert_eq!(simplify("x+(y+x)"), "2*x+y");
+    assert_eq!(simplify("y*x+x"), "x*(y+1)");
+    assert_eq!(simplify("x+a*(b*x)"), "x*(a*b+1)");
+    assert_eq!(simplify("x-y*x"), "x*(1-y)");
+    assert_eq!(simplify("y*(x*y)"), "y^2*x");
+
+    // functions and variables match too
+    let e = Expr::function("log", vec![expr!("e"), parse("x+y")]);
+    assert_eq!(e.simplify(&engine).to_string(), "ln(x+y)");
+    assert_eq!(simplify("sin(x)*sin(x)"), "sin(x)^2");
+}
+
+#[test]
+fn guarded_rules_test() {
+    let engine = Engine::new().with_functions();
+    let simplifier = Simplifier::new();
+    let simplify = |e: Expr| simplifier.simplify(&engine, &e).to_string();
+    let x = || expr!("x");
+
+    // x / x = 1 only if x is known to be nonzero, or else with the condition that it is
+    assert_eq!(simplify(x() / x()), "x/x");
+    assert_eq!(
+        (x() / x()).simplify_with_conditions(&engine).to_string(),
+        "1, if x != 0"
+    );
+    assert_eq!(simplify(x() * x().pow(expr!(2))), "x^3");
+    let one = || Expr::function("sin", vec![expr!(1)]);
+    assert_eq!(simplify(one() / one()), "1");
+
+    // (x^y)^z = x^(y*z) only for integer z
+    assert_eq!(simplify(x().pow(expr!(2)).pow(expr!(3))), "x^6");
+    let root = x().pow(expr!(2)).pow(Expr::from(Number::Rational(1, 2)));
+    assert_eq!(simplify(root.clone()), root.to_string());
+
+    // identifiers that only match numbers or only symbols
+    let g = |e: Expr| Expr::function("g", vec![e]);
+    let simplifier = Simplifier::new()
+        .with_rule(Rule::new(g(expr!(ID::number(0))), expr!(ID::number(0))))
+        .with_rule(
+            Rule::new(g(expr!(ID::symbol(0))), expr!(0)).with_guard(Guard::Nonzero(ID::new(1))),
+        )
+        .with_rule(Rule::new(
+            g(expr!(ID::symbol(0))),
+            expr!(ID::symbol(0)).pow(expr!(2)),
+        ));
+    let simplify = |e: Expr| simplifier.simplify(&engine, &e).to_string();
+    assert_eq!(simplify(g(expr!(3))), "3");
+    assert_eq!(simplify(g(x())), "x^2");
+    assert_eq!(simplify(g(x() + expr!(1))), "g(x+1)");
+}
+
+#[test]
+fn rule_parsing_test() {
+    let engine = Engine::new().with_functions();
+    let parse = |s: &str| Expr::parse(&engine, s).unwrap();
+
+    let mut simplifier = Simplifier::from_str(&engine, "").unwrap();
+    assert!(simplifier.rules().is_empty());
+    simplifier
+        .add_rule(&engine, "x_ * x_^y_ -> x_^(y_+1) if natural(y_)")
+        .unwrap();
+    let simplify = |s: &Simplifier, e: &str| s.simplify(&engine, &parse(e)).to_string();
+    assert_eq!(simplify(&simplifier, "a*a^2*b"), "a^3*b");
+    assert_eq!(simplify(&simplifier, "a*a^(-1)"), "a*a^(-1)");
+
+    let rules = "
+        # names are optional, comments are skipped
+
+        double angle: 2*sin(x_)*cos(x_) -> sin(2*x_)
+        power: (x_^n_num)^m_num -> x_^(n_num*m_num) if integer(m_num) # only integers
+        g: sin(x_sym) + cos(x_sym) -> 0
+    ";
+    let simplifier = Simplifier::from_str(&engine, rules).unwrap();
+    let names = simplifier
+        .rules()
+        .iter()
+        .map(|r| r.name())
+        .collect::<Vec<_>>();
+    assert_eq!(names, [Some("double angle"), Some("power"), Some("g")]);
+    assert_eq!(simplify(&simplifier, "cos(t)*sin(t)*2"), "sin(2*t)");
+    assert_eq!(simplify(&simplifier, "(t^2)^3"), "t^6");
+    assert_eq!(simplify(&simplifier, "(t^x)^3"), "(t^x)^3");
+    assert_eq!(simplify(&simplifier, "sin(t)+cos(t)"), "0");
+    assert_eq!(simplify(&simplifier, "sin(2)+cos(2)"), "sin(2)+cos(2)");
+
+    let path = std::env::temp_dir().join("cas_rule_parsing_test.rules");
+    std::fs::write(&path, rules).unwrap();
+    let from_file = Simplifier::from_file(&engine, &path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    assert_eq!(from_file.rules().len(), 3);
+    assert_eq!(
+        Simplifier::from_file(&engine, &path).err(),
+        Some(SymErr::Unreadable)
+    );
+
+    for invalid in [
+        "x_ + 1",
+        "x_ -> y_",
+        "x_ -> 1 if odd(x_)",
+        "x_ -> 1 if nonzero(y_)",
+    ] {
+        assert_eq!(
+            Simplifier::from_str(&engine, invalid).err(),
+            Some(SymErr::InvalidRule),
+            "{}",
+            invalid
+        );
+    }
+}
+
+#[test]
+fn simplifier_profiles_test() {
+    let engine = Engine::new().with_functions();
+    let parse = |s: &str| Expr::parse(&engine, s).unwrap();
+
+    let simplifier = Simplifier::new();
+    assert_eq!(
+        simplifier.groups(),
+        [
+            "arithmetic",
+            "powers",
+            "quotients",
+            "factoring",
+            "expanding",
+            "trig",
+            "logs"
+        ]
+    );
+    assert!(!simplifier.is_enabled("expanding"));
+    assert_eq!(parse("x+x*y").simplify(&engine).to_string(), "x*(y+1)");
+
+    // the same expression in the expanding profile
+    let expanding = Engine::new()
+        .with_functions()
+        .with_simplifier(Simplifier::expanding());
+    assert!(expanding.simplifier().is_enabled("expanding"));
+    assert!(!expanding.simplifier().is_enabled("factoring"));
+    assert_eq!(parse("x*(y+z)").simplify(&expanding).to_string(), "x*y+x*z");
+
+    // disabled groups and removed rules are not tried
+    let plain = Engine::new().with_functions().with_simplifier(
+        Simplifier::new()
+            .without_group("trig")
+            .without_rule("double"),
+    );
+    assert_eq!(
+        parse("sin(x)^2+cos(x)^2").simplify(&plain).to_string(),
+        "sin(x)^2+cos(x)^2"
+    );
+    assert_eq!(parse("x+x").simplify(&plain).to_string(), "x+x");
+    assert_eq!(parse("x*x").simplify(&plain).to_string(), "x^2");
+
+    // higher priorities first, rule files can name groups
+    let mut simplifier = Simplifier::new().with_rule(
+        Rule::new(
+            expr!(ID::new(0)) + expr!(ID::new(0)),
+            expr!(ID::new(0)) * expr!(2),
+        )
+        .with_priority(1),
+    );
+    assert_eq!(simplifier.rules()[0].priority(), 1);
+    simplifier
+        .add_rule(&engine, "a_^0 -> 1 if nonzero(a_)")
+        .unwrap();
+    assert_eq!(simplifier.rules()[1].name(), Some("add zero"));
+    let engine = Engine::new().with_functions().with_simplifier(simplifier);
+    assert_eq!(parse("x+x").simplify(&engine).to_string(), "x*2");
+
+    let rules = "[logs]\nln(e^x_) -> x_\n[]\nexp(ln(x_)) -> x_";
+    let simplifier = Simplifier::from_str(&engine, rules).unwrap();
+    let groups = simplifier
+        .rules()
+        .iter()
+        .map(Rule::group)
+        .collect::<Vec<_>>();
+    assert_eq!(groups, [Some("logs"), None]);
+}
+
+#[test]
+fn simplify_termination_test() {
+    let engine = Engine::new().with_functions();
+    let parse = |s: &str| Expr::parse(&engine, s).unwrap();
+
+    // rules that do not change anything do not count as progress
+    let identity = Simplifier::from_str(&engine, "a_ -> a_").unwrap();
+    let (simplified, report) = identity.simplify_with_report(&engine, &parse("x+1"));
+    assert_eq!(simplified, parse("x+1"));
+    assert_eq!((report.stop, report.iterations), (Stop::Fixpoint, 0));
+
+    // rules that undo each other are caught as soon as a form repeats
+    let swap = Simplifier::from_str(&engine, "sin(a_) -> cos(a_)\ncos(a_) -> sin(a_)")
+        .unwrap()
+        .with_strategy(Strategy::Innermost);
+    let (simplified, report) = swap.simplify_with_report(&engine, &parse("sin(x)"));
+    assert_eq!(simplified, parse("sin(x)"));
+    assert_eq!((report.stop, report.iterations), (Stop::Cycle, 2));
+    assert_eq!(
+        report.to_string(),
+        "cycle after 2 iterations: sin(x) -> cos(x) -> sin(x)"
+    );
+
+    // rules that grow forever stop at the budget
+    let grow = Simplifier::from_str(&engine, "a_sym -> a_sym + 1").unwrap();
+    let limited = Engine::new().with_functions().with_budget(Budget {
+        iterations: 3,
+        time: None,
+    });
+    let (simplified, report) = grow.simplify_with_report(&limited, &parse("x"));
+    assert_eq!(simplified.to_string(), "x+3");
+    assert_eq!((report.stop, report.iterations), (Stop::Iterations, 3));
+    assert_eq!(
+        report.to_string(),
+        "iteration budget used up after 3 iterations, last forms: x+1 -> x+2 -> x+3"
+    );
+
+    let timed = Engine::new().with_functions().with_budget(Budget {
+        iterations: usize::MAX,
+        time: Some(Duration::from_millis(20)),
+    });
+    let (_, report) = grow.simplify_with_report(&timed, &parse("x"));
+    assert_eq!(report.stop, Stop::Time);
+    assert_eq!(timed.budget().time, Some(Duration::from_millis(20)));
+}
+
+#[test]
+fn saturation_test() {
+    let engine = Engine::new().with_functions();
+    let parse = |s: &str| Expr::parse(&engine, s).unwrap();
+
+    // rules that undo each other are no problem, every form is kept
+    let rules = "x_*y_ + x_*z_ -> x_*(y_+z_)\nx_*(y_+z_) -> x_*y_ + x_*z_\nx_^2 -> x_*x_";
+    let saturate = Simplifier::from_str(&engine, rules)
+        .unwrap()
+        .with_strategy(Strategy::Saturate);
+    let (simplified, report) = saturate.simplify_with_report(&engine, &parse("a*b+a*c"));
+    assert_eq!(simplified.to_string(), "a*(b+c)");
+    assert_eq!(report.stop, Stop::Fixpoint);
+
+    let factored = saturate.with_cost(Cost::Factored);
+    assert_eq!(
+        factored.simplify(&engine, &parse("a*b+c*a")).to_string(),
+        "a*(b+c)"
+    );
+    let no_powers = |e: &Expr| {
+        let printed = e.to_string();
+        10.0 * printed.matches('^').count() as f64 + printed.len() as f64
+    };
+    let custom = factored.with_cost(Cost::Custom(no_powers));
+    assert_eq!(custom.simplify(&engine, &parse("a^2")).to_string(), "a*a");
+
+    // the built in rules, within a node limit
+    let simplifier = Simplifier::new()
+        .with_strategy(Strategy::Saturate)
+        .with_cost(Cost::Factored);
+    let engine = Engine::new().with_functions().with_simplifier(simplifier);
+    assert_eq!(parse("x+x*y").simplify(&engine).to_string(), "x*(y+1)");
+    assert_eq!(parse("y*x - x*y").simplify(&engine).to_string(), "0");
+    let limited = Simplifier::new()
+        .with_strategy(Strategy::Saturate)
+        .with_node_limit(20);
+    let (_, report) = limited.simplify_with_report(&engine, &parse("a+b+c+d+e+f"));
+    assert_eq!(report.stop, Stop::Nodes);
+}
+
+#[test]
+fn trace_test() {
+    let engine = Engine::new().with_functions();
+    let parse = |s: &str| Expr::parse(&engine, s).unwrap();
+
+    let trace = parse("sin(x-x)+y*y").simplify_with_trace(&engine);
+    let steps = trace
+        .steps
+        .iter()
+        .map(|s| {
+            (
+                s.rule.as_str(),
+                s.path.clone(),
+                s.before.to_string(),
+                s.after.to_string(),
+            )
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(
+        steps,
+        [
+            ("subtract self", vec![0, 0], "x-x".into(), "0".into()),
+            ("square", vec![1], "y*y".into(), "y^2".into()),
+        ]
+    );
+    assert_eq!(trace.result(), parse("sin(0)+y^2"));
+    assert_eq!(
+        trace.print(),
+        "sin(x-x)+y*y\n\
+         = sin(0)+y*y    (subtract self: x-x -> 0)\n\
+         = sin(0)+y^2    (square: y*y -> y^2)"
+    );
+    assert!(trace.print_latex().starts_with("\\begin{aligned}\n& "));
+    assert!(trace.print_latex().contains("&& \\text{square}"));
+
+    // cancelling is one step, unnamed rules show themselves
+    let trace = parse("(x^2-1)/(x-1)").simplify_with_trace(&engine);
+    assert_eq!(trace.steps.len(), 1);
+    assert_eq!(trace.steps[0].rule, "cancel");
+    assert!(!trace.steps[0].conditions.is_empty());
+    assert!(trace.print().ends_with(", if x-1 != 0)"));
+    assert!(trace
+        .print_latex()
+        .contains("\\text{cancel}\\text{, if } x-1 \\neq 0"));
+    let simplifier = Simplifier::from_str(&engine, "a_*0 -> 0").unwrap();
+    let trace = simplifier.simplify_with_trace(&engine, &parse("2+x*0"));
+    assert_eq!(trace.steps[0].rule, "i:0*0 -> 0");
+    assert_eq!(trace.steps[0].path, [1]);
+
+    let mut bindings = Bindings::new();
+    bindings.insert("x", expr!(3));
+    let trace = parse("2*x+1").eval_with_trace(&engine, &bindings).unwrap();
+    assert_eq!(trace.result(), expr!(7));
+    assert_eq!(
+        trace.print(),
+        "2*x+1\n\
+         = 2*3+1    (substitute: x -> 3)\n\
+         = 6+1    (evaluate: 2*3 -> 6)\n\
+         = 7    (evaluate: 6+1 -> 7)"
+    );
+}
+
+#[test]
+fn numeric_literal_test() {
+    let engine = Engine::new().with_functions();
+    let parse = |s: &str| Expr::parse(&engine, s).unwrap();
+    let simplify = |s: &str| parse(s).simplify(&engine).to_string();
+
+    // typed literals match the rules, whether exact or not
+    assert_eq!(simplify("x+0"), "x");
+    assert_eq!(simplify("1*x"), "x");
+    assert_eq!(simplify("y+x*0.0"), "y");
+    let expanding = Engine::new()
+        .with_functions()
+        .with_simplifier(Simplifier::expanding());
+    assert_eq!(parse("x*(y+1)").simplify(&expanding).to_string(), "x*y+x");
+    let doubling = Simplifier::from_str(&engine, "x_*2.0 -> x_+x_").unwrap();
+    assert_eq!(
+        doubling.simplify(&engine, &parse("sin(2*y)")).to_string(),
+        "sin(y+y)"
+    );
+
+    // numbers fold in any order and stay exact
+    assert_eq!(simplify("2*x*3"), "6*x");
+    assert_eq!(simplify("sin(1/3+1/6)"), "sin((1/2))");
+    assert_eq!(simplify("x^(4-2)*y^(2*(1/2))"), "x^2*y");
+    assert_eq!(simplify("x+0.5+0.25"), "x+0.75");
+    assert_eq!(parse("(1+1)*y").simplify(&expanding).to_string(), "2*y");
+
+    // unless that would make them inexact or undefined
+    assert_eq!(simplify("x+2^(1/2)"), "x+2^(1/2)");
+    assert_eq!(simplify("x+1/0"), "x+1/0");
+    let e = Expr::from(i64::MAX) * expr!("x") * expr!(2);
+    assert_eq!(e.simplify(&engine), e);
+}
+
+#[test]
+fn trig_test() {
+    let engine = Engine::new().with_functions();
+    let parse = |s: &str| Expr::parse(&engine, s).unwrap();
+
+    let trigsimp = |s: &str| parse(s).trigsimp(&engine).to_string();
+    assert_eq!(trigsimp("3*sin(x)^2+3*cos(x)^2"), "3");
+    assert_eq!(trigsimp("y*(1-cos(t)^2)"), "y*sin(t)^2");
+    assert_eq!(trigsimp("cos(x)^2-sin(x)^2"), "cos(2*x)");
+    assert_eq!(trigsimp("sin(x)/cos(x)"), "tan(x)");
+    assert_eq!(trigsimp("sin(x+y)+sin(x-y)"), "2*sin(x)*cos(y)");
+
+    let expand_trig = |s: &str| parse(s).expand_trig(&engine).to_string();
+    assert_eq!(expand_trig("sin(x+y)"), "cos(x)*sin(y)+cos(y)*sin(x)");
+    assert_eq!(expand_trig("cos(x-y)"), "cos(x)*cos(y)+sin(x)*sin(y)");
+    assert_eq!(expand_trig("sin(2*x)"), "2*cos(x)*sin(x)");
+    assert_eq!(expand_trig("sin(-x)"), "-sin(x)");
+    // multiplied out and collected, the factoring rules do not undo it
+    assert_eq!(
+        parse("cos(3*x)").expand_trig(&engine),
+        parse("cos(x)^3-3*sin(x)^2*cos(x)").expand()
+    );
+    assert_eq!(expand_trig("cos(3*x)"), "cos(x)^3-3*cos(x)*sin(x)^2");
+    assert_eq!(expand_trig("sin(3*x)"), "3*cos(x)^2*sin(x)-sin(x)^3");
+
+    let exponential = parse("cos(x)+tan(y)").to_exponential(&engine);
+    assert_eq!(
+        exponential.to_string(),
+        "(e^(ⅈ*x)+e^(-ⅈ*x))/2+(e^(ⅈ*y)-e^(-ⅈ*y))/(ⅈ*(e^(ⅈ*y)+e^(-ⅈ*y)))"
+    );
+    assert_eq!(
+        exponential.from_exponential(&engine),
+        parse("cos(x)+tan(y)")
+    );
+    assert_eq!(
+        parse("e^(ⅈ*t)").from_exponential(&engine).to_string(),
+        "cos(t)+ⅈ*sin(t)"
+    );
+
+    // the variable i is not the imaginary unit
+    assert_eq!(
+        parse("sin(i)").to_exponential(&engine).to_string(),
+        "(e^(ⅈ*i)-e^(-ⅈ*i))/(2*ⅈ)"
+    );
+    assert_eq!(
+        parse("e^(i*t)").from_exponential(&engine).to_string(),
+        "e^(i*t)"
+    );
+}